@@ -0,0 +1,110 @@
+// benches/indexing.rs
+//
+// Run with `cargo bench`. For a flamegraph, profile a single benchmark for a
+// fixed time, e.g. `cargo flamegraph --bench indexing -- --bench --profile-time 10 scan/default/1000`.
+
+use cooklang_indexer::{HtmlOptions, IngredientIndex};
+use criterion::{criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+use std::fs;
+use std::path::Path;
+use tempfile::TempDir;
+
+const BASES: &[&str] = &[
+    "flour", "sugar", "butter", "egg", "milk", "salt", "pepper", "garlic", "onion", "tomato", "basil",
+    "olive oil", "chicken thigh", "rice", "lemon", "crème fraîche", "paprika", "cumin", "carrot", "potato",
+];
+const VARIANTS: &[&str] = &["", "fresh ", "dried ", "smoked ", "red ", "Ground "];
+const UNITS: &[&str] = &["g", "ml", "tbsp", "tsp", "cup", "clove"];
+
+/// Writes `count` synthetic recipes into a fresh directory, spread over a few
+/// subfolders, with sections, amounts and a realistic share of repeated names
+///
+/// The corpus is deterministic so runs stay comparable.
+fn generate_corpus(count: usize) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    // A small LCG keeps the corpus reproducible without another dependency
+    let mut state: u64 = 0x2545_f491_4f6c_dd1d;
+    let mut next = move |bound: usize| {
+        state = state.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        (state >> 33) as usize % bound
+    };
+
+    for n in 0..count {
+        let mut content = format!(">> title: Recipe {}\n", n);
+        for section in 0..1 + next(3) {
+            content.push_str(&format!("= Part {}\n", section + 1));
+            for _ in 0..3 + next(8) {
+                let name = format!("{}{}", VARIANTS[next(VARIANTS.len())], BASES[next(BASES.len())]);
+                content.push_str(&format!(
+                    "Add @{}{{{}%{}}} and stir for ~{{{}%minutes}}.\n",
+                    name,
+                    1 + next(500),
+                    UNITS[next(UNITS.len())],
+                    1 + next(20)
+                ));
+            }
+        }
+        let path = dir.path().join(format!("folder-{}/recipe-{}.cook", n % 10, n));
+        write(&path, &content);
+    }
+    dir
+}
+
+fn write(path: &Path, content: &str) {
+    fs::create_dir_all(path.parent().unwrap()).unwrap();
+    fs::write(path, content).unwrap();
+}
+
+/// Reading every recipe file without parsing it, the floor under [`bench_scan`]
+fn bench_read(c: &mut Criterion) {
+    let mut group = c.benchmark_group("read");
+    for count in [100, 1000] {
+        let corpus = generate_corpus(count);
+        group.throughput(Throughput::Elements(count as u64));
+        group.bench_with_input(BenchmarkId::from_parameter(count), &corpus, |b, corpus| {
+            b.iter(|| read_corpus(corpus.path()))
+        });
+    }
+    group.finish();
+}
+
+/// Total length of the files below `dir`, so none of the reads is optimized away
+fn read_corpus(dir: &Path) -> usize {
+    fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .map(|path| if path.is_dir() { read_corpus(&path) } else { fs::read(path).unwrap().len() })
+        .sum()
+}
+
+/// Scanning, parsing and building the ingredient map, i.e. all of `IngredientIndex::new`
+///
+/// Each corpus is indexed both ways over the same files. A low-memory index
+/// shares one copy of each path between the ingredients and keeps no snippets
+/// or sections, so the gap between the two is what copying paths into the
+/// buckets and keeping the context costs; `read` gives the part spent on I/O.
+fn bench_scan(c: &mut Criterion) {
+    let mut group = c.benchmark_group("scan");
+    for count in [100, 1000] {
+        let corpus = generate_corpus(count);
+        group.throughput(Throughput::Elements(count as u64));
+        for low_memory in [false, true] {
+            let name = if low_memory { "low_memory" } else { "default" };
+            group.bench_with_input(BenchmarkId::new(name, count), &corpus, |b, corpus| {
+                b.iter(|| IngredientIndex::builder(corpus.path()).low_memory(low_memory).build().unwrap())
+            });
+        }
+    }
+    group.finish();
+}
+
+/// Rendering the HTML index of an already built index
+fn bench_html(c: &mut Criterion) {
+    let corpus = generate_corpus(1000);
+    let index = IngredientIndex::new(corpus.path()).unwrap();
+    let options = HtmlOptions { show_quantities: true, ..HtmlOptions::default() };
+    c.bench_function("html/1000", |b| b.iter(|| index.generate_html_report(&options).unwrap()));
+}
+
+criterion_group!(benches, bench_read, bench_scan, bench_html);
+criterion_main!(benches);
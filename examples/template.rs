@@ -0,0 +1,17 @@
+// examples/template.rs
+//
+// Renders ./test_recipes with examples/templates/cards.html:
+//     cargo run --example template --features templates
+use cooklang_indexer::{HtmlOptions, IngredientIndex};
+use anyhow::Result;
+
+fn main() -> Result<()> {
+    let index = IngredientIndex::new("./test_recipes")?;
+    let template = std::fs::read_to_string("examples/templates/cards.html")?;
+
+    let html = index.generate_html_from_template(Some(&template), &HtmlOptions::default())?;
+    std::fs::write("ingredient-cards.html", html)?;
+    println!("Wrote ingredient-cards.html");
+
+    Ok(())
+}
@@ -0,0 +1,76 @@
+// File: src/atomic.rs
+
+//! Output files replaced in one step, so readers never see them half written
+
+use anyhow::{Context, Result};
+use std::fs::{self, File};
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+
+/// Writes a file by writing a temporary file next to it and renaming it over
+/// the target
+///
+/// If the write fails, e.g. because the disk is full, the target keeps its
+/// previous content and the temporary file is removed. A process killed midway
+/// leaves the target untouched too, though possibly a stray `.*.tmp` file.
+///
+/// # Arguments
+/// * `path` - Path of the file to create or replace
+/// * `bytes` - The new content
+///
+/// # Returns
+/// * `Result<()>` - Success or error status
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{write_atomically, IngredientIndex};
+///
+/// let index = IngredientIndex::new("./recipes").unwrap();
+/// let html = index.generate_html("https://example.com/recipes").unwrap();
+/// write_atomically("public/index.html", html).unwrap();
+/// ```
+pub fn write_atomically(path: impl AsRef<Path>, bytes: impl AsRef<[u8]>) -> Result<()> {
+    write_atomically_with(path, |file| file.write_all(bytes.as_ref()))
+}
+
+/// Like [`write_atomically`], but with the content written by `write`, for
+/// output produced in pieces
+///
+/// # Arguments
+/// * `path` - Path of the file to create or replace
+/// * `write` - Writes the new content to the temporary file; an error leaves
+///   the target as it was
+///
+/// # Returns
+/// * `Result<()>` - Success or error status
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{write_atomically_with, HtmlOptions, IngredientIndex};
+///
+/// let index = IngredientIndex::new("./recipes").unwrap();
+/// write_atomically_with("public/index.html", |file| {
+///     index.write_html(file, &HtmlOptions::default()).map_err(std::io::Error::other)
+/// })
+/// .unwrap();
+/// ```
+pub fn write_atomically_with(path: impl AsRef<Path>, write: impl FnOnce(&mut File) -> io::Result<()>) -> Result<()> {
+    let path = path.as_ref();
+    let temp = temp_path(path);
+    let written = File::create(&temp).and_then(|mut file| {
+        write(&mut file)?;
+        file.sync_all()
+    });
+    if let Err(err) = written.and_then(|()| fs::rename(&temp, path)) {
+        let _ = fs::remove_file(&temp);
+        return Err(err).with_context(|| format!("failed to write {}", path.display()));
+    }
+    Ok(())
+}
+
+/// The temporary file written before replacing `path`: hidden, in the same
+/// directory so the rename stays on one file system, and unique per process
+fn temp_path(path: &Path) -> PathBuf {
+    let name = path.file_name().map_or_else(Default::default, |name| name.to_string_lossy());
+    path.with_file_name(format!(".{}.{}.tmp", name, std::process::id()))
+}
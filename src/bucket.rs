@@ -0,0 +1,104 @@
+// File: src/bucket.rs
+
+//! The recipes listed under one ingredient of the index
+
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, OnceLock};
+
+/// Paths of the recipes using one ingredient, sorted and without duplicates
+#[derive(Debug, Clone)]
+pub(crate) enum Bucket {
+    /// A copy of each path, as handed out by
+    /// [`get_recipes_for_ingredient`](crate::IngredientIndex::get_recipes_for_ingredient)
+    Owned(Vec<PathBuf>),
+    /// Paths shared with the other buckets of a
+    /// [low-memory](crate::IngredientIndexBuilder::low_memory) index, copied
+    /// into `owned` only once they are asked for
+    Interned {
+        paths: Vec<Arc<Path>>,
+        owned: OnceLock<Vec<PathBuf>>,
+    },
+}
+
+impl Bucket {
+    /// An empty bucket, sharing its paths if `low_memory` is set
+    pub(crate) fn new(low_memory: bool) -> Self {
+        if low_memory {
+            Bucket::Interned { paths: Vec::new(), owned: OnceLock::new() }
+        } else {
+            Bucket::Owned(Vec::new())
+        }
+    }
+
+    /// Number of recipes
+    pub(crate) fn len(&self) -> usize {
+        match self {
+            Bucket::Owned(paths) => paths.len(),
+            Bucket::Interned { paths, .. } => paths.len(),
+        }
+    }
+
+    /// Whether no recipe is left
+    pub(crate) fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// The paths of the recipes, sorted
+    pub(crate) fn iter(&self) -> impl Iterator<Item = &Path> {
+        let (owned, interned): (&[PathBuf], &[Arc<Path>]) = match self {
+            Bucket::Owned(paths) => (paths, &[]),
+            Bucket::Interned { paths, .. } => (&[], paths),
+        };
+        owned.iter().map(PathBuf::as_path).chain(interned.iter().map(|path| &**path))
+    }
+
+    /// The paths of the recipes as owned paths, copied on first use in a
+    /// low-memory index
+    pub(crate) fn paths(&self) -> &Vec<PathBuf> {
+        match self {
+            Bucket::Owned(paths) => paths,
+            Bucket::Interned { paths, owned } => {
+                owned.get_or_init(|| paths.iter().map(|path| path.to_path_buf()).collect())
+            }
+        }
+    }
+
+    /// Adds a recipe, keeping the paths sorted; `path` is shared rather than
+    /// copied in a low-memory index
+    pub(crate) fn insert(&mut self, path: &Arc<Path>) {
+        match self {
+            Bucket::Owned(paths) => {
+                if let Err(pos) = paths.binary_search_by(|p| p.as_path().cmp(path)) {
+                    paths.insert(pos, path.to_path_buf());
+                }
+            }
+            Bucket::Interned { paths, owned } => {
+                if let Err(pos) = paths.binary_search(path) {
+                    paths.insert(pos, Arc::clone(path));
+                    owned.take();
+                }
+            }
+        }
+    }
+
+    /// Removes a recipe, returning whether it was listed
+    pub(crate) fn remove(&mut self, path: &Path) -> bool {
+        match self {
+            Bucket::Owned(paths) => match paths.binary_search_by(|p| p.as_path().cmp(path)) {
+                Ok(pos) => {
+                    paths.remove(pos);
+                    true
+                }
+                Err(_) => false,
+            },
+            Bucket::Interned { paths, owned } => match paths.binary_search_by(|p| (**p).cmp(path)) {
+                Ok(pos) => {
+                    paths.remove(pos);
+                    owned.take();
+                    true
+                }
+                Err(_) => false,
+            },
+        }
+    }
+}
@@ -0,0 +1,22 @@
+// File: src/collation.rs
+
+//! Locale-aware ordering of ingredient names (requires the `collation` feature)
+
+use anyhow::{anyhow, Result};
+use icu_collator::{Collator, CollatorOptions};
+use icu_locid::Locale;
+
+/// Builds the Unicode collator for a BCP 47 locale such as `fr` or `de-CH`
+///
+/// # Arguments
+/// * `locale` - Locale identifier whose collation rules to follow
+///
+/// # Returns
+/// * `Result<Collator>` - The collator, or an error naming an unparsable locale
+pub(crate) fn collator(locale: &str) -> Result<Collator> {
+    let parsed: Locale = locale
+        .parse()
+        .map_err(|err| anyhow!("invalid collation locale {:?}: {}", locale, err))?;
+    Collator::try_new(&(&parsed).into(), CollatorOptions::new())
+        .map_err(|err| anyhow!("no collation data for locale {:?}: {}", locale, err))
+}
@@ -0,0 +1,43 @@
+// File: src/csv.rs
+
+//! CSV export of the index
+
+use crate::url::export_path;
+use crate::IngredientIndex;
+
+impl IngredientIndex {
+    /// Generates the index as CSV, with an `ingredient,recipe` row for each
+    /// recipe using each ingredient
+    ///
+    /// Rows follow the order of [`ingredients`](Self::ingredients), then of the
+    /// recipe paths. Recipe paths are relative to the recipes directory and use
+    /// `/` on every platform. Fields are quoted as RFC 4180 requires.
+    ///
+    /// # Returns
+    /// * `String` - The CSV document, starting with the header row
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// std::fs::write("ingredients.csv", index.generate_csv()).unwrap();
+    /// ```
+    pub fn generate_csv(&self) -> String {
+        let mut csv = String::from("ingredient,recipe\r\n");
+        for name in self.ingredients() {
+            for path in self.index[name].iter() {
+                csv.push_str(&format!("{},{}\r\n", csv_field(name), csv_field(&export_path(path, self.root_of(path)))));
+            }
+        }
+        csv
+    }
+}
+
+/// Quotes a field when it holds a comma, quote or line break, doubling its quotes
+fn csv_field(field: &str) -> String {
+    if field.contains([',', '"', '\r', '\n']) {
+        format!("\"{}\"", field.replace('"', "\"\""))
+    } else {
+        field.to_string()
+    }
+}
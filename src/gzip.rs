@@ -0,0 +1,70 @@
+// File: src/gzip.rs
+
+//! Gzip-compressed output (requires the `gzip` feature)
+
+use crate::{write_atomically_with, HtmlOptions, IngredientIndex};
+use anyhow::Result;
+use flate2::write::GzEncoder;
+use flate2::Compression;
+use std::io::{self, BufWriter, Write};
+use std::path::Path;
+
+impl IngredientIndex {
+    /// Writes the HTML index as a gzip-compressed file
+    ///
+    /// The content is the same as [`generate_html`](Self::generate_html); serving
+    /// it as `index.html.gz` lets static hosts skip compressing it on the fly.
+    ///
+    /// # Arguments
+    /// * `path` - Path of the compressed file, created or replaced at once with
+    ///   [`write_atomically_with`]
+    /// * `base_url` - Base URL where recipes will be hosted
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error status
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// index.write_html_gz("public/index.html.gz", "https://example.com/recipes").unwrap();
+    /// ```
+    pub fn write_html_gz(&self, path: impl AsRef<Path>, base_url: &str) -> Result<()> {
+        let options = HtmlOptions {
+            base_url: Some(base_url.to_string()),
+            ..HtmlOptions::default()
+        };
+        write_gz(path.as_ref(), |encoder| self.write_html(encoder, &options))
+    }
+
+    /// Writes the JSON index as a gzip-compressed file
+    ///
+    /// The content is the same as [`generate_json`](Self::generate_json).
+    ///
+    /// # Arguments
+    /// * `path` - Path of the compressed file, created or replaced at once with
+    ///   [`write_atomically_with`]
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success or error status
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// index.write_json_gz("public/index.json.gz").unwrap();
+    /// ```
+    pub fn write_json_gz(&self, path: impl AsRef<Path>) -> Result<()> {
+        let json = self.generate_json()?;
+        write_gz(path.as_ref(), |encoder| Ok(encoder.write_all(json.as_bytes())?))
+    }
+}
+
+/// Writes whatever `write` produces to `path`, gzip-compressed
+fn write_gz(path: &Path, write: impl FnOnce(&mut dyn Write) -> Result<()>) -> Result<()> {
+    write_atomically_with(path, |file| {
+        let mut encoder = GzEncoder::new(BufWriter::new(file), Compression::default());
+        write(&mut encoder).map_err(io::Error::other)?;
+        encoder.finish()?.flush()
+    })
+}
@@ -0,0 +1,1733 @@
+// File: src/html.rs
+
+//! HTML generation for the ingredient index
+
+use crate::url::{build_relative_url, build_url, strip_base_dir, validate_base_url, UrlError, UrlMapper, UrlMode, UrlOptions};
+use crate::{IngredientIndex, IngredientOrder, Recipe, ShoppingItem, SlugSet};
+use anyhow::{Context, Result};
+use unicode_normalization::UnicodeNormalization;
+use std::borrow::Cow;
+use std::cmp::{Ordering, Reverse};
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::io::{self, Write};
+use std::path::{Component, Path, PathBuf};
+use std::sync::Arc;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+/// What to do with a recipe whose path cannot be turned into a valid URL
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BrokenLinks {
+    /// Leave the recipe out of the generated HTML (the default)
+    #[default]
+    Skip,
+    /// Link the recipe anyway, replacing the offending characters lossily
+    Lossy,
+}
+
+/// Color scheme of the embedded stylesheet
+///
+/// The built-in stylesheet takes its colors from CSS custom properties set
+/// on `:root`: `--background`, `--text`, `--heading`, `--rule` (the line under
+/// the heading), `--ingredient`, `--link` and `--muted` (letters without
+/// ingredients). The built-in themes only differ in those.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Theme {
+    /// Dark text on a light background (the default)
+    #[default]
+    Light,
+    /// Light text on a dark background, with brighter links for contrast
+    Dark,
+    /// Light or dark following the reader's system setting, through the
+    /// `prefers-color-scheme` media query
+    Auto,
+    /// Custom property declarations of your own, e.g. `--link: #c0392b;`,
+    /// applied on top of the light colors so any left out keep their default
+    Variables(String),
+    /// A complete stylesheet of your own, embedded instead of the built-in one
+    Custom(String),
+}
+
+/// Order in which ingredients are listed
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum IngredientSort {
+    /// As the index lists them, following its [`IngredientOrder`] (the default)
+    #[default]
+    Index,
+    /// By name
+    Alphabetical,
+    /// Most-used ingredients first, ties broken by name
+    ByRecipeCountDesc,
+    /// Least-used ingredients first, ties broken by name
+    ByRecipeCountAsc,
+    /// In the given order, e.g. grouped by category; names not in the index are
+    /// ignored and ingredients missing from the list follow alphabetically
+    Custom(Vec<String>),
+}
+
+/// Order of the recipes listed under an ingredient
+#[derive(Debug, Clone, Default)]
+pub enum RecipeSort {
+    /// By display name, ignoring case (the default)
+    #[default]
+    ByName,
+    /// Most recently modified files first; recipes whose modification time
+    /// is unknown come last
+    NewestFirst,
+    /// By a key computed from each recipe, ties broken by path; see
+    /// [`HtmlOptions::recipe_sort_key`]
+    Key(RecipeSortKey),
+}
+
+/// A user-supplied function computing the key recipes are sorted by
+///
+/// Created with [`RecipeSortKey::new`] or [`HtmlOptions::recipe_sort_key`].
+#[derive(Clone)]
+pub struct RecipeSortKey(Arc<SortKeyFn>);
+
+type SortKeyFn = dyn Fn(&Recipe) -> String + Send + Sync;
+
+impl RecipeSortKey {
+    /// Wraps a closure receiving the parsed recipe
+    pub fn new(key: impl Fn(&Recipe) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(key))
+    }
+
+    /// Computes the sort key of a recipe
+    pub fn key(&self, recipe: &Recipe) -> String {
+        (self.0)(recipe)
+    }
+}
+
+impl fmt::Debug for RecipeSortKey {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("RecipeSortKey(..)")
+    }
+}
+
+/// Footer line closing every generated page
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum Footer {
+    /// No footer (the default)
+    #[default]
+    None,
+    /// When and by which version the page was generated, e.g.
+    /// "Generated 2024-06-01 14:02 UTC by cooklang-indexer 0.3.1"
+    Auto,
+    /// When the page was generated and how many recipes and ingredients the
+    /// index holds, e.g. "Generated on 2024-06-01 14:02 UTC — 848 recipes, 312 ingredients"
+    Stats,
+    /// A text of your own, escaped like every other text
+    Custom(String),
+}
+
+/// User-visible text of the generated pages, in English by default
+///
+/// Each `{}` in a label stands for a value filled in when the page is built,
+/// e.g. the number in [`recipes`](Self::recipes). The page title is set with
+/// [`HtmlOptions::title`] and the document language with [`HtmlOptions::lang`].
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::{HtmlLabels, HtmlOptions};
+///
+/// let options = HtmlOptions {
+///     lang: "de".to_string(),
+///     title: "Zutaten".to_string(),
+///     labels: HtmlLabels {
+///         recipe: "{} Rezept".to_string(),
+///         recipes: "{} Rezepte".to_string(),
+///         ..HtmlLabels::default()
+///     },
+///     ..HtmlOptions::default()
+/// };
+/// assert_eq!(options.labels.recipe_count(3), "3 Rezepte");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+#[cfg_attr(feature = "templates", derive(serde::Serialize))]
+pub struct HtmlLabels {
+    /// Count of one recipe, e.g. next to an ingredient: "{} recipe"
+    pub recipe: String,
+    /// Count of any other number of recipes: "{} recipes"
+    pub recipes: String,
+    /// Count of one ingredient: "{} ingredient"
+    pub ingredient: String,
+    /// Count of any other number of ingredients: "{} ingredients"
+    pub ingredients: String,
+    /// The summary line, filled with the ingredient and recipe counts: "{} across {}"
+    pub summary: String,
+    /// Appended to the summary with the recipes directory: ", generated from {}"
+    pub summary_dir: String,
+    /// Recipes missing from the summary, filled with their count:
+    /// "skipped {} that could not be linked"
+    pub summary_skipped: String,
+    /// Placeholder and accessible name of the filter box: "Filter ingredients or recipes"
+    pub filter_placeholder: String,
+    /// Shown when the filter box matches nothing: "No matches"
+    pub no_matches: String,
+    /// Heading of the recipes without tags on the tag index: "untagged"
+    pub untagged: String,
+    /// The automatic footer, filled with the time and the generator: "Generated {} by {}"
+    pub generated: String,
+    /// The statistics footer, filled with the time and the recipe and
+    /// ingredient counts: "Generated on {} — {}, {}"
+    pub generated_stats: String,
+    /// Link back to the ingredient index on site pages: "All ingredients"
+    pub all_ingredients: String,
+    /// Link to, and heading of, the recipe list of a site: "All recipes"
+    pub all_recipes: String,
+    /// Link from a recipe page to the recipe file: "Source"
+    pub source: String,
+    /// Link to the previous page of a paginated index: "Previous"
+    pub previous_page: String,
+    /// Link to the next page of a paginated index: "Next"
+    pub next_page: String,
+    /// Position among the pages of a paginated index, filled with the page
+    /// number and the number of pages: "Page {} of {}"
+    pub page_position: String,
+    /// Accessible name of the A–Z navigation: "Ingredients by letter"
+    pub alphabet_nav: String,
+    /// Accessible name of the ingredient cloud: "Ingredients by use"
+    pub ingredient_cloud: String,
+    /// Accessible name of the page links of a paginated index: "Pages"
+    pub pagination: String,
+}
+
+impl Default for HtmlLabels {
+    fn default() -> Self {
+        Self {
+            recipe: "{} recipe".to_string(),
+            recipes: "{} recipes".to_string(),
+            ingredient: "{} ingredient".to_string(),
+            ingredients: "{} ingredients".to_string(),
+            summary: "{} across {}".to_string(),
+            summary_dir: ", generated from {}".to_string(),
+            summary_skipped: "skipped {} that could not be linked".to_string(),
+            filter_placeholder: "Filter ingredients or recipes".to_string(),
+            no_matches: "No matches".to_string(),
+            untagged: "untagged".to_string(),
+            generated: "Generated {} by {}".to_string(),
+            generated_stats: "Generated on {} — {}, {}".to_string(),
+            all_ingredients: "All ingredients".to_string(),
+            all_recipes: "All recipes".to_string(),
+            source: "Source".to_string(),
+            previous_page: "Previous".to_string(),
+            next_page: "Next".to_string(),
+            page_position: "Page {} of {}".to_string(),
+            alphabet_nav: "Ingredients by letter".to_string(),
+            ingredient_cloud: "Ingredients by use".to_string(),
+            pagination: "Pages".to_string(),
+        }
+    }
+}
+
+impl HtmlLabels {
+    /// A number of recipes as shown next to ingredients and tags, e.g. "3 recipes"
+    pub fn recipe_count(&self, count: usize) -> String {
+        fill(if count == 1 { &self.recipe } else { &self.recipes }, &[&count.to_string()])
+    }
+
+    /// A number of ingredients, e.g. "12 ingredients"
+    pub fn ingredient_count(&self, count: usize) -> String {
+        fill(if count == 1 { &self.ingredient } else { &self.ingredients }, &[&count.to_string()])
+    }
+}
+
+/// Replaces each `{}` in a label with the next value, or nothing once they run out
+pub(crate) fn fill(label: &str, values: &[&str]) -> String {
+    let mut parts = label.split("{}");
+    let mut filled = parts.next().unwrap_or_default().to_string();
+    for (part, value) in parts.zip(values.iter().copied().chain(std::iter::repeat(""))) {
+        filled.push_str(value);
+        filled.push_str(part);
+    }
+    filled
+}
+
+/// Options controlling HTML generation
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{HtmlOptions, IngredientIndex, IngredientSort};
+///
+/// let index = IngredientIndex::new("./recipes").unwrap();
+/// let options = HtmlOptions {
+///     base_url: Some("https://example.com/recipes".to_string()),
+///     title: "What's in the pantry".to_string(),
+///     sort: IngredientSort::ByRecipeCountDesc,
+///     show_counts: true,
+///     ..HtmlOptions::default()
+/// };
+/// let html = index.generate_html_with(&options).unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct HtmlOptions {
+    /// Base URL where recipes will be hosted (e.g., "http://example.com/recipes")
+    ///
+    /// When `None`, recipes are linked with plain relative paths from `output_dir`.
+    pub base_url: Option<String>,
+    /// Directory the HTML file will be written to, used for relative links
+    ///
+    /// Defaults to the recipes directory itself.
+    pub output_dir: Option<PathBuf>,
+    /// How recipe paths are turned into URLs
+    pub url: UrlOptions,
+    /// How to handle recipes whose paths cannot be converted to URLs
+    pub broken_links: BrokenLinks,
+    /// Accept a bare host such as `example.com/recipes` as the base URL by
+    /// prepending `https://`, instead of rejecting it
+    pub lenient_base_url: bool,
+    /// Color scheme of the embedded stylesheet
+    pub theme: Theme,
+    /// Page title, used for `<title>` and, unless `heading` is set, the main heading
+    pub title: String,
+    /// Language of the pages, as the `<html lang>` attribute; `"en"` by default
+    pub lang: String,
+    /// Every other text of the pages, e.g. to translate them along with `lang`
+    pub labels: HtmlLabels,
+    /// Text of the main `<h1>` heading when it should differ from `title`
+    pub heading: Option<String>,
+    /// HTML inserted verbatim below the heading, e.g. a short description
+    ///
+    /// Not escaped, so only pass trusted markup.
+    pub intro_html: Option<String>,
+    /// Class of a `<div>` wrapping the index markup, so that an embedding
+    /// page can style or place it; `None` (the default) adds no wrapper
+    ///
+    /// Applies to full pages and to [`generate_html_fragment`](crate::IngredientIndex::generate_html_fragment) alike.
+    pub container_class: Option<String>,
+    /// Whether to embed the theme's stylesheet at all
+    pub include_default_css: bool,
+    /// URL of an external stylesheet, linked before the embedded styles
+    pub stylesheet_href: Option<String>,
+    /// Extra rules appended to the embedded `<style>` block
+    ///
+    /// Still embedded when `include_default_css` is off.
+    pub extra_css: Option<String>,
+    /// Whether to leave out the indentation and line breaks between tags and
+    /// shrink the embedded stylesheet, for a smaller page
+    ///
+    /// [`intro_html`](Self::intro_html) is kept as written. Ignored by templates.
+    pub minify: bool,
+    /// Order in which ingredients are listed
+    pub sort: IngredientSort,
+    /// Order of the recipes under each ingredient; ties are broken by name and
+    /// then by path, so the output never depends on the platform or on the
+    /// order files were found in
+    pub recipe_sort: RecipeSort,
+    /// Locale whose Unicode collation orders the ingredients, e.g. `"fr"` so
+    /// that `échalote` is listed among the e's rather than after `zucchini`
+    ///
+    /// Applies to every [`sort`](Self::sort), including ties, and to the order
+    /// within each letter group. `None` (the default) sorts by byte value.
+    #[cfg(feature = "collation")]
+    pub collation: Option<String>,
+    /// Whether to show the number of recipes next to each ingredient, e.g.
+    /// "garlic (37 recipes)"; on by default
+    ///
+    /// The count is always that of the recipes actually listed, so skipped
+    /// links are not counted.
+    pub show_counts: bool,
+    /// When recipes listed under the same ingredient share a display name,
+    /// append their parent folder to tell them apart, e.g. "soup (dinners)",
+    /// or their path below the recipes directory if the folders match too
+    ///
+    /// Recipes whose `>> title:` metadata differs are never suffixed.
+    pub disambiguate_names: bool,
+    /// Whether to show an A–Z row at the top linking to the first ingredient
+    /// of each letter; names starting with anything else share a `#` bucket
+    pub alphabet_nav: bool,
+    /// Whether to group ingredients under an `<h2>` heading per starting letter,
+    /// bucketed like the A–Z navigation; the order within a letter follows `sort`.
+    /// Ingredient names then become `<h3>` headings instead of `<h2>`.
+    pub group_by_letter: bool,
+    /// Whether to add a search box that filters ingredients and recipes as you
+    /// type, using a small inline script; without JavaScript the box stays
+    /// hidden and everything is shown
+    pub filter_box: bool,
+    /// Whether to render each ingredient as a collapsible `<details>` element
+    /// whose summary always shows the recipe count
+    pub collapsible: bool,
+    /// Whether collapsible ingredients start expanded
+    pub open_by_default: bool,
+    /// Whether to produce a page meant for paper: the interactive parts, i.e.
+    /// the filter box, the A–Z navigation, the ingredient cloud and collapsible
+    /// ingredients, are left out, and the stylesheet gains `@media print`
+    /// rules printing black on white without splitting an ingredient across
+    /// pages
+    pub print: bool,
+    /// Whether to show how much of the ingredient each recipe uses, e.g.
+    /// "pesto — 2 cups"
+    pub show_quantities: bool,
+    /// Whether to quote the recipe step around each recipe's first use of the
+    /// ingredient, with the ingredient highlighted in a `<mark>` tag. Recipes
+    /// parsed in low-memory mode have no steps to quote.
+    pub show_context: bool,
+    /// How many of each recipe's ingredients to list next to it on the recipe
+    /// index, in order of first use; `0` (the default) lists none
+    pub recipe_index_ingredients: usize,
+    /// How many ingredients each page of the site's ingredient index lists at
+    /// most, splitting it into `index.html`, `index-2.html` and so on with
+    /// links to the previous and next pages; `0` (the default) keeps one page
+    ///
+    /// The A–Z navigation of every page links to the page each letter starts
+    /// on; the filter box only filters the page it is on. Only used by
+    /// [`generate_site`](crate::IngredientIndex::generate_site).
+    pub ingredients_per_page: usize,
+    /// Whether the tag index ends with a section listing the recipes without
+    /// tags; on by default
+    pub show_untagged: bool,
+    /// Whether to split each ingredient's recipes by the top-level folder they
+    /// are in, under a small `mains/` style label; recipes at the top of the
+    /// recipes directory come first, without a label
+    ///
+    /// Ignored by the default template; custom templates get each recipe's `folder`.
+    pub group_recipes_by_dir: bool,
+    /// Whether to open the page with a line such as "312 ingredients across
+    /// 848 recipes, generated from ./recipes", also saying how many recipes
+    /// were left out because they could not be linked
+    pub show_summary: bool,
+    /// Whether the summary names the recipes directory; absolute paths are
+    /// shown relative to the working directory when below it, and as the bare
+    /// directory name otherwise
+    pub summary_dir: bool,
+    /// Whether to open the page with a cloud of all ingredients, the most used
+    /// ones in larger type, each linking to its entry
+    pub tag_cloud: bool,
+    /// Footer line at the end of each page
+    pub footer: Footer,
+    /// Time reported as the generation time instead of the current time, so
+    /// that repeated builds produce identical output
+    pub generated_at: Option<SystemTime>,
+}
+
+impl Default for HtmlOptions {
+    fn default() -> Self {
+        Self {
+            base_url: None,
+            output_dir: None,
+            url: UrlOptions::default(),
+            broken_links: BrokenLinks::default(),
+            lenient_base_url: false,
+            theme: Theme::default(),
+            title: "Recipe Ingredient Index".to_string(),
+            lang: "en".to_string(),
+            labels: HtmlLabels::default(),
+            heading: None,
+            intro_html: None,
+            container_class: None,
+            include_default_css: true,
+            stylesheet_href: None,
+            extra_css: None,
+            minify: false,
+            sort: IngredientSort::default(),
+            recipe_sort: RecipeSort::default(),
+            #[cfg(feature = "collation")]
+            collation: None,
+            show_counts: true,
+            disambiguate_names: true,
+            alphabet_nav: true,
+            group_by_letter: false,
+            filter_box: false,
+            collapsible: false,
+            open_by_default: false,
+            print: false,
+            show_quantities: false,
+            show_context: false,
+            recipe_index_ingredients: 0,
+            ingredients_per_page: 0,
+            show_untagged: true,
+            group_recipes_by_dir: false,
+            show_summary: false,
+            summary_dir: true,
+            tag_cloud: false,
+            footer: Footer::default(),
+            generated_at: None,
+        }
+    }
+}
+
+/// A recipe that could not be linked cleanly while generating HTML
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct LinkWarning {
+    /// Path of the offending recipe
+    pub path: PathBuf,
+    /// Why the URL could not be built
+    pub error: UrlError,
+}
+
+impl fmt::Display for LinkWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "cannot link {}: {}", self.path.display(), self.error)
+    }
+}
+
+impl HtmlOptions {
+    /// Routes every recipe link through a custom closure instead of [`path_to_url`](crate::path_to_url)
+    ///
+    /// The closure receives the recipe path and the base directory and returns
+    /// the final href, which lets the index match any site generator's URL layout.
+    /// The Markdown export links recipes through the same closure.
+    ///
+    /// # Example
+    /// ```
+    /// use cooklang_indexer::HtmlOptions;
+    ///
+    /// let options = HtmlOptions::default().url_mapper(|path, _base_dir| {
+    ///     let stem = path.file_stem().unwrap().to_string_lossy();
+    ///     format!("/recipes/{}/", stem.to_lowercase())
+    /// });
+    /// ```
+    pub fn url_mapper(mut self, mapper: impl Fn(&Path, &Path) -> String + Send + Sync + 'static) -> Self {
+        self.url.mapper = Some(UrlMapper::new(mapper));
+        self
+    }
+
+    /// Sorts the recipes under each ingredient by a key computed from each
+    /// recipe, e.g. one of its [`metadata`](Recipe::metadata) values
+    ///
+    /// Keys are compared as strings in ascending order; recipes with equal keys
+    /// are ordered by path.
+    ///
+    /// # Example
+    /// ```
+    /// use cooklang_indexer::HtmlOptions;
+    ///
+    /// // Highest `>> rating:` first, assuming single-digit ratings
+    /// let options = HtmlOptions::default().recipe_sort_key(|recipe| {
+    ///     let rating: u32 = recipe.metadata.get("rating").and_then(|r| r.parse().ok()).unwrap_or(0);
+    ///     (9 - rating.min(9)).to_string()
+    /// });
+    /// ```
+    pub fn recipe_sort_key(mut self, key: impl Fn(&Recipe) -> String + Send + Sync + 'static) -> Self {
+        self.recipe_sort = RecipeSort::Key(RecipeSortKey::new(key));
+        self
+    }
+}
+
+/// Generated HTML along with any warnings raised while building it
+#[derive(Debug, Clone)]
+pub struct HtmlReport {
+    /// The HTML document
+    pub html: String,
+    /// Recipes that were skipped or linked lossily
+    pub warnings: Vec<LinkWarning>,
+}
+
+/// Built-in stylesheet shared by every built-in theme, which only set the
+/// custom properties it uses
+const BASE_CSS: &str = r#"        body {
+            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
+            max-width: 800px;
+            margin: 0 auto;
+            padding: 20px;
+            line-height: 1.6;
+            background-color: var(--background);
+            color: var(--text);
+        }
+        h1 {
+            color: var(--heading);
+            border-bottom: 2px solid var(--rule);
+            padding-bottom: 10px;
+        }
+        .ingredient {
+            margin: 20px 0;
+        }
+        .ingredient-name {
+            font-size: 1em;
+            font-weight: bold;
+            color: var(--ingredient);
+            margin: 0 0 5px;
+        }
+        .ingredient-name a {
+            color: inherit;
+        }
+        .recipe-list {
+            margin-left: 20px;
+            list-style-type: none;
+        }
+        .recipe-list li {
+            margin: 5px 0;
+        }
+        a {
+            color: var(--link);
+            text-decoration: none;
+        }
+        a:hover {
+            text-decoration: underline;
+        }
+        .alphabet-nav {
+            margin: 10px 0 20px;
+        }
+        .alphabet-nav a, .alphabet-nav span {
+            margin-right: 6px;
+        }
+        .alphabet-nav span {
+            color: var(--muted);
+        }
+"#;
+
+/// Colors of [`Theme::Light`]; the background and text are the browser's own
+const LIGHT_VARIABLES: &str = r#"--background: Canvas;
+--text: CanvasText;
+--heading: #2c3e50;
+--rule: #eee;
+--ingredient: #34495e;
+--link: #3498db;
+--muted: #bbb;
+"#;
+
+/// Colors of [`Theme::Dark`], with brighter links for contrast
+const DARK_VARIABLES: &str = r#"--background: #1e2227;
+--text: #d7dae0;
+--heading: #e6e9ef;
+--rule: #3a3f4b;
+--ingredient: #c8ccd4;
+--link: #6cb6ff;
+--muted: #5c6370;
+"#;
+
+/// A `:root` rule setting the given declarations, indented like the stylesheet
+fn root_rule(declarations: &str, indent: &str) -> String {
+    let mut rule = format!("{}        :root {{\n", indent);
+    for line in declarations.lines().map(str::trim).filter(|line| !line.is_empty()) {
+        rule.push_str(&format!("{}            {}\n", indent, line));
+    }
+    rule.push_str(&format!("{}        }}\n", indent));
+    rule
+}
+
+/// Extra rules for [`HtmlOptions::collapsible`] layered on the built-in themes
+const COLLAPSIBLE_CSS: &str = r#"        summary.ingredient-name {
+            cursor: pointer;
+        }
+        details.ingredient[open] > summary.ingredient-name {
+            margin-bottom: 5px;
+        }
+"#;
+
+/// Extra rules for [`HtmlOptions::show_context`] layered on the built-in themes
+const CONTEXT_CSS: &str = r#"        .recipe-context {
+            font-size: 0.9em;
+            opacity: 0.8;
+        }
+"#;
+
+/// Extra rules for [`HtmlOptions::tag_cloud`] layered on the built-in themes;
+/// the margin keeps the links apart once minifying drops the line breaks
+const TAG_CLOUD_CSS: &str = r#"        .ingredient-cloud a {
+            margin-right: 6px;
+        }
+"#;
+
+/// Rules for [`HtmlOptions::print`], whatever the theme
+const PRINT_CSS: &str = r#"        @media print {
+            :root {
+                --background: #fff;
+                --text: #000;
+                --heading: #000;
+                --ingredient: #000;
+                --link: #000;
+            }
+            body {
+                max-width: none;
+                padding: 0;
+                font-size: 11pt;
+            }
+            .ingredient {
+                break-inside: avoid;
+            }
+            .ingredient-name, .letter-heading {
+                break-after: avoid;
+            }
+            .alphabet-nav, .ingredient-cloud, #ingredient-filter, #no-matches {
+                display: none;
+            }
+        }
+"#;
+
+/// Markup of the filter box, hidden until [`FILTER_SCRIPT`] reveals it
+pub(crate) fn filter_input(labels: &HtmlLabels) -> String {
+    let placeholder = escape_html(&labels.filter_placeholder);
+    format!(
+        r#"    <input type="search" id="ingredient-filter" placeholder="{0}" aria-label="{0}" style="width: 100%; padding: 6px; box-sizing: border-box;" hidden>
+    <p id="no-matches" hidden>{1}</p>
+"#,
+        placeholder,
+        escape_html(&labels.no_matches)
+    )
+}
+
+/// Inline script behind [`HtmlOptions::filter_box`]
+///
+/// Ingredients whose name contains the query stay visible with all their
+/// recipes; otherwise only the matching recipes are kept. Matching ignores case
+/// and diacritics.
+pub(crate) const FILTER_SCRIPT: &str = r#"<script>
+(function () {
+    var input = document.getElementById("ingredient-filter");
+    var noMatches = document.getElementById("no-matches");
+    var fold = function (text) {
+        return text.normalize("NFD").replace(/[\u0300-\u036f]/g, "").toLowerCase();
+    };
+    input.hidden = false;
+    input.addEventListener("input", function () {
+        var query = fold(input.value.trim());
+        var shown = 0;
+        document.querySelectorAll(".ingredient").forEach(function (section) {
+            var nameMatches = fold(section.querySelector(".ingredient-name a").textContent).indexOf(query) !== -1;
+            var recipesShown = 0;
+            section.querySelectorAll(".recipe-list li").forEach(function (item) {
+                item.hidden = !nameMatches && fold(item.textContent).indexOf(query) === -1;
+                if (!item.hidden) recipesShown++;
+            });
+            section.hidden = !nameMatches && recipesShown === 0;
+            if (!section.hidden) shown++;
+        });
+        noMatches.hidden = shown > 0;
+    });
+})();
+</script>
+"#;
+
+/// One ingredient as it appears in the generated index
+#[derive(Debug, Clone)]
+pub(crate) struct IngredientEntry {
+    /// Normalized ingredient name
+    pub name: String,
+    /// Unique element id: the [`slugify`](crate::slugify)d name, with `-2`, `-3`…
+    /// appended when several ingredients share a slug
+    pub anchor: String,
+    /// Id of the A–Z navigation target placed before this entry, set on the
+    /// first ingredient of each letter when `alphabet_nav` is on
+    pub letter_anchor: Option<String>,
+    /// Label of the letter heading placed before this entry, set on the first
+    /// ingredient of each letter when `group_by_letter` is on
+    pub letter_heading: Option<char>,
+    /// Recipes using the ingredient that could be linked
+    pub recipes: Vec<RecipeLink>,
+}
+
+/// A linked recipe listed under an ingredient
+#[derive(Debug, Clone)]
+pub(crate) struct RecipeLink {
+    /// Link text, possibly disambiguated with the parent folder
+    pub name: String,
+    /// Final href of the recipe
+    pub url: String,
+    /// Path of the recipe file
+    #[cfg_attr(not(feature = "templates"), allow(dead_code))]
+    pub path: PathBuf,
+    /// How much of the ingredient the recipe uses, when `show_quantities` is on
+    pub quantity: Option<String>,
+    /// HTML of the step quoting the ingredient, when `show_context` is on
+    pub context: Option<String>,
+    /// Top-level folder of the recipe below the recipes directory, `None` for
+    /// recipes directly in it
+    pub folder: Option<String>,
+}
+
+/// Prepares `options` for rendering `index`
+///
+/// Falls back to the index's [`url_builder`](crate::IngredientIndexBuilder::url_builder)
+/// when no URL mapper is set, then checks the base URLs, normalizing them if
+/// `lenient_base_url` is set. Base URLs only matter when the built-in URL scheme
+/// uses them, so options with a mapper or a non-standard mode skip the check;
+/// the collation locale is checked either way.
+pub(crate) fn resolve_options<'a>(index: &IngredientIndex, options: &'a HtmlOptions) -> Result<Cow<'a, HtmlOptions>> {
+    let mut options = Cow::Borrowed(options);
+    if options.print {
+        let options = options.to_mut();
+        options.filter_box = false;
+        options.alphabet_nav = false;
+        options.tag_cloud = false;
+        options.collapsible = false;
+    }
+    if options.url.mapper.is_none() {
+        if let Some(url_builder) = &index.options.url_builder {
+            options.to_mut().url.mapper = Some(url_builder.clone());
+        }
+    }
+    #[cfg(feature = "collation")]
+    if let Some(locale) = &options.collation {
+        crate::collation::collator(locale)?;
+    }
+    if options.url.mapper.is_some() || options.url.mode != UrlMode::Standard {
+        return Ok(options);
+    }
+
+    let lenient = options.lenient_base_url;
+    if let Some(base_url) = &options.base_url {
+        let base_url = validate_base_url(base_url, lenient)?;
+        options.to_mut().base_url = Some(base_url);
+    }
+    for entry in &mut options.to_mut().url.dir_base_urls {
+        entry.base_url = validate_base_url(&entry.base_url, lenient)?;
+    }
+    Ok(options)
+}
+
+/// Resolves the ingredient index into the entries shown on the page
+///
+/// Ingredients are ordered according to `options.sort` and every recipe is
+/// linked according to the URL options; recipes that cannot be linked cleanly
+/// are reported in the returned warnings.
+pub(crate) fn collect_entries(
+    index: &IngredientIndex,
+    options: &HtmlOptions,
+) -> (Vec<IngredientEntry>, Vec<LinkWarning>) {
+    let mut linker = Linker::new(index, options, options.output_dir.as_deref().unwrap_or(&index.base_dir));
+
+    let mut ingredients = sorted_ingredients(index, options);
+    if options.group_by_letter {
+        // Stable, so the requested order is kept within each letter
+        ingredients.sort_by_key(|ingredient| letter_bucket(ingredient).map_or(u32::MAX, u32::from));
+    }
+
+    let mut anchors = SlugSet::new();
+    let mut letters_seen = Vec::new();
+    let entries = ingredients
+        .into_iter()
+        .map(|ingredient| {
+            let letter = letter_bucket(ingredient);
+            let first_of_letter = !letters_seen.contains(&letter);
+            if first_of_letter {
+                letters_seen.push(letter);
+            }
+            IngredientEntry {
+                name: ingredient.clone(),
+                anchor: anchors.insert(ingredient),
+                letter_anchor: (options.alphabet_nav && first_of_letter).then(|| letter_anchor_id(letter)),
+                letter_heading: (options.group_by_letter && first_of_letter).then(|| letter_label(letter)),
+                recipes: linker.links(ingredient, &index.index[ingredient].iter().collect::<Vec<_>>()),
+            }
+        })
+        .collect();
+    (entries, linker.warnings)
+}
+
+/// The A–Z navigation buckets in display order, `None` being the `#` bucket
+fn alphabet() -> impl Iterator<Item = Option<char>> {
+    ('a'..='z').map(Some).chain([None])
+}
+
+/// The navigation bucket of an ingredient: its first letter with diacritics
+/// folded away, or `None` for names starting with a digit, symbol or non-Latin letter
+fn letter_bucket(name: &str) -> Option<char> {
+    let first = name.trim_start().nfd().next()?;
+    first.is_ascii_alphabetic().then(|| first.to_ascii_lowercase())
+}
+
+/// Display label of a bucket: the uppercase letter, or `#`
+fn letter_label(letter: Option<char>) -> char {
+    letter.map_or('#', |letter| letter.to_ascii_uppercase())
+}
+
+/// Element id of the navigation target for a bucket
+///
+/// The underscore keeps these ids apart from ingredient anchors, which come
+/// from [`slugify`](crate::slugify) and never contain one.
+fn letter_anchor_id(letter: Option<char>) -> String {
+    match letter {
+        Some(letter) => format!("letter_{}", letter),
+        None => "letter_other".to_string(),
+    }
+}
+
+/// The A–Z navigation row: each label with the id it links to, or `None` when
+/// no ingredient falls under it
+pub(crate) fn alphabet_links(entries: &[IngredientEntry]) -> Vec<(char, Option<String>)> {
+    alphabet()
+        .map(|letter| {
+            let id = letter_anchor_id(letter);
+            let used = entries.iter().any(|entry| entry.letter_anchor.as_ref() == Some(&id));
+            (letter_label(letter), used.then_some(id))
+        })
+        .collect()
+}
+
+/// builds basic html with the list of ingredients and which recipes they 
+/// are included in.
+///
+/// The output is deterministic: ingredients come from [`sorted_ingredients`] and
+/// each recipe list is kept sorted by the index, so nothing depends on `HashMap`
+/// iteration order and the same recipes always produce the same bytes.
+pub(crate) fn generate_html_index(index: &IngredientIndex, options: &HtmlOptions) -> Result<HtmlReport> {
+    let mut html = Vec::new();
+    let warnings = write_html_index(index, options, &mut html)?;
+    Ok(HtmlReport { html: String::from_utf8(html)?, warnings })
+}
+
+/// Streams the HTML index to `out` one ingredient at a time, returning the
+/// link warnings; see [`generate_html_index`]
+pub(crate) fn write_html_index(index: &IngredientIndex, options: &HtmlOptions, out: &mut dyn Write) -> Result<Vec<LinkWarning>> {
+    let options = resolve_options(index, options)?;
+    let (entries, warnings) = collect_entries(index, &options);
+
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.out = Some(out);
+    writer.write_head();
+    writer.write_index(index, &entries, &warnings).context("failed to write HTML")?;
+    writer.write_tail();
+    writer.flush().context("failed to write HTML")?;
+    Ok(warnings)
+}
+
+/// Generates the index markup alone, as embedded in the page by [`generate_html_index`]
+///
+/// The filter script, which the page carries at its very end, follows the
+/// markup when the filter box is on.
+pub(crate) fn generate_html_fragment(index: &IngredientIndex, options: &HtmlOptions) -> Result<String> {
+    let options = resolve_options(index, options)?;
+    let (entries, warnings) = collect_entries(index, &options);
+
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.write_index(index, &entries, &warnings)?;
+    if options.filter_box {
+        writer.push(FILTER_SCRIPT);
+    }
+    Ok(writer.html)
+}
+
+/// The summary paragraph behind [`HtmlOptions::show_summary`], or `None` when it is off
+///
+/// Recipes are counted once however many ingredients list them.
+pub(crate) fn summary(
+    index: &IngredientIndex,
+    entries: &[IngredientEntry],
+    warnings: &[LinkWarning],
+    options: &HtmlOptions,
+) -> Option<String> {
+    if !options.show_summary {
+        return None;
+    }
+    let recipes: HashSet<&Path> =
+        entries.iter().flat_map(|entry| entry.recipes.iter().map(|recipe| recipe.path.as_path())).collect();
+    let labels = &options.labels;
+    let counts = [labels.ingredient_count(entries.len()), labels.recipe_count(recipes.len())];
+    let mut text = fill(&labels.summary, &[&counts[0], &counts[1]]);
+    if options.summary_dir {
+        text.push_str(&fill(&labels.summary_dir, &[&display_dir(&index.base_dir)]));
+    }
+    let mut html = format!("    <p class=\"index-summary\">{}", escape_html(&text));
+    if options.broken_links == BrokenLinks::Skip {
+        let skipped: HashSet<&Path> = warnings.iter().map(|warning| warning.path.as_path()).collect();
+        if !skipped.is_empty() {
+            let skipped = fill(&labels.summary_skipped, &[&labels.recipe_count(skipped.len())]);
+            html.push_str(&format!(" <span class=\"skipped-count\">({})</span>", escape_html(&skipped)));
+        }
+    }
+    html.push_str("</p>\n");
+    Some(html)
+}
+
+/// The cloud behind [`HtmlOptions::tag_cloud`], or `None` when it is off
+///
+/// Font sizes run from 0.8em to 2em by the percentile of the ingredient's
+/// recipe count, i.e. the share of ingredients used in fewer recipes, so a few
+/// very common ingredients don't shrink all others to the same size. The
+/// percentiles are scaled so the most used ingredients get the largest size.
+pub(crate) fn tag_cloud(entries: &[IngredientEntry], options: &HtmlOptions) -> Option<String> {
+    if !options.tag_cloud {
+        return None;
+    }
+    let mut counts: Vec<usize> = entries.iter().map(|entry| entry.recipes.len()).collect();
+    counts.sort_unstable();
+    let below = |n: usize| counts.partition_point(|&count| count < n);
+    let below_max = counts.last().map_or(0, |&max| below(max));
+    let mut html = format!(
+        "    <nav class=\"ingredient-cloud\" aria-label=\"{}\">\n",
+        escape_html(&options.labels.ingredient_cloud)
+    );
+    for entry in entries {
+        let percentile = if below_max > 0 { below(entry.recipes.len()) as f64 / below_max as f64 } else { 1.0 };
+        html.push_str(&format!(
+            "        <a href=\"#{}\" style=\"font-size: {:.2}em\">{}</a>\n",
+            entry.anchor,
+            0.8 + 1.2 * percentile,
+            escape_html(&entry.name)
+        ));
+    }
+    html.push_str("    </nav>\n");
+    Some(html)
+}
+
+/// How the recipes directory is named in the summary, without leaking the
+/// absolute path of a home directory
+fn display_dir(dir: &Path) -> String {
+    if dir.is_relative() {
+        return dir.display().to_string();
+    }
+    if let Ok(cwd) = std::env::current_dir() {
+        if let Ok(relative) = dir.strip_prefix(cwd) {
+            return Path::new(".").join(relative).display().to_string();
+        }
+    }
+    dir.file_name().map_or_else(|| dir.display().to_string(), |name| name.to_string_lossy().into_owned())
+}
+
+/// Builds the page listing every recipe by title, grouped under an `<h2>` per
+/// starting letter
+///
+/// Recipes are titled by their `>> title:` metadata, falling back to the file
+/// name, and sorted case-insensitively. Recipes that cannot be linked are
+/// handled like on the ingredient index.
+pub(crate) fn generate_recipe_index(index: &IngredientIndex, options: &HtmlOptions) -> Result<String> {
+    let options = resolve_options(index, options)?;
+    let mut linker = Linker::new(index, &options, options.output_dir.as_deref().unwrap_or(&index.base_dir));
+
+    let mut recipes: Vec<_> = index
+        .recipes
+        .iter()
+        .map(|recipe| (recipe_name(recipe), recipe))
+        .collect();
+    recipes.sort_by_cached_key(|(title, recipe)| {
+        (letter_bucket(title).map_or(u32::MAX, u32::from), title.to_lowercase(), &recipe.path)
+    });
+
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.write_head();
+    let mut current_letter = None;
+    for (title, recipe) in &recipes {
+        let Some(url) = linker.recipe_url(&recipe.path) else { continue };
+        let letter = letter_label(letter_bucket(title));
+        if current_letter != Some(letter) {
+            if current_letter.is_some() {
+                writer.push("    </ul>\n");
+            }
+            writer.push(&format!("<h2 class=\"letter-heading\">{}</h2>\n", letter));
+            writer.push("    <ul class=\"recipe-list\">\n");
+            current_letter = Some(letter);
+        }
+        writer.push(&format!("        <li><a href=\"{}\">{}</a>", escape_html(&url), escape_html(title)));
+        let mut ingredients: Vec<&String> = Vec::new();
+        for ingredient in &recipe.ingredients {
+            if ingredients.len() < options.recipe_index_ingredients && !ingredients.contains(&&ingredient.name) {
+                ingredients.push(&ingredient.name);
+            }
+        }
+        if !ingredients.is_empty() {
+            let names: Vec<_> = ingredients.iter().map(|name| escape_html(name)).collect();
+            writer.push(&format!(" <span class=\"recipe-ingredients\">— {}</span>", names.join(", ")));
+        }
+        writer.push("</li>\n");
+    }
+    if current_letter.is_some() {
+        writer.push("    </ul>\n");
+    }
+    writer.write_tail();
+    Ok(writer.html)
+}
+
+/// Builds the page with a section per tag, most used tags first
+///
+/// Tags are grouped by their lowercased form and shown as first spelled.
+pub(crate) fn generate_tag_index(index: &IngredientIndex, options: &HtmlOptions) -> Result<String> {
+    let options = resolve_options(index, options)?;
+    let mut linker = Linker::new(index, &options, options.output_dir.as_deref().unwrap_or(&index.base_dir));
+
+    // (display form, recipes) per lowercased tag, recipes in path order
+    let mut tags: HashMap<String, (&str, Vec<&Recipe>)> = HashMap::new();
+    let mut untagged = Vec::new();
+    for recipe in &index.recipes {
+        for tag in &recipe.tags {
+            tags.entry(tag.to_lowercase()).or_insert_with(|| (tag, Vec::new())).1.push(recipe);
+        }
+        if recipe.tags.is_empty() {
+            untagged.push(recipe);
+        }
+    }
+    let mut sections: Vec<_> = tags.into_iter().collect();
+    sections.sort_by(|(a, (_, a_recipes)), (b, (_, b_recipes))| {
+        b_recipes.len().cmp(&a_recipes.len()).then(a.cmp(b))
+    });
+    if options.show_untagged && !untagged.is_empty() {
+        sections.push((options.labels.untagged.clone(), (&options.labels.untagged, untagged)));
+    }
+
+    let mut anchors = SlugSet::new();
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.write_head();
+    for (key, (display, recipes)) in &sections {
+        let links: Vec<RecipeLink> = recipes
+            .iter()
+            .filter_map(|recipe| {
+                Some(RecipeLink {
+                    name: recipe_name(recipe),
+                    url: linker.recipe_url(&recipe.path)?,
+                    path: recipe.path.clone(),
+                    quantity: None,
+                    context: None,
+                    folder: linker.folder(&recipe.path),
+                })
+            })
+            .collect();
+        let anchor = anchors.insert(&format!("tag {}", key));
+        writer.push(&format!("<section class=\"tag\" id=\"{}\">\n", anchor));
+        writer.push(&format!(
+            "    <h2 class=\"tag-name\"><a href=\"#{}\">{}</a>",
+            anchor,
+            escape_html(display)
+        ));
+        if options.show_counts {
+            let count = escape_html(&options.labels.recipe_count(links.len()));
+            writer.push(&format!(" <span class=\"tag-count\">({})</span>", count));
+        }
+        writer.push("</h2>\n");
+        writer.write_recipe_list(&links);
+        writer.push("</section>\n");
+    }
+    writer.write_tail();
+    Ok(writer.html)
+}
+
+/// Orders the ingredient names as requested, always breaking ties by name
+///
+/// Names are compared with the collation of `options.collation` when set,
+/// falling back to byte order for names the collation considers equal.
+fn sorted_ingredients<'a>(index: &'a IngredientIndex, options: &HtmlOptions) -> Vec<&'a String> {
+    #[cfg(feature = "collation")]
+    let collator = options.collation.as_deref().and_then(|locale| crate::collation::collator(locale).ok());
+    let compare = |a: &str, b: &str| -> Ordering {
+        #[cfg(feature = "collation")]
+        if let Some(collator) = &collator {
+            return collator.compare(a, b).then_with(|| a.cmp(b));
+        }
+        a.cmp(b)
+    };
+
+    let sort = match (&options.sort, index.options.ingredient_order) {
+        (IngredientSort::Index, IngredientOrder::FirstSeen) => return index.ingredients(),
+        (IngredientSort::Index, IngredientOrder::Alphabetical) => &IngredientSort::Alphabetical,
+        (IngredientSort::Index, IngredientOrder::ByFrequency) => &IngredientSort::ByRecipeCountDesc,
+        (sort, _) => sort,
+    };
+    let index = &index.index;
+    let mut ingredients: Vec<_> = index.keys().collect();
+    match sort {
+        IngredientSort::Index | IngredientSort::Alphabetical => ingredients.sort_by(|a, b| compare(a, b)),
+        IngredientSort::ByRecipeCountDesc => {
+            ingredients.sort_by(|a, b| index[*b].len().cmp(&index[*a].len()).then_with(|| compare(a, b)))
+        }
+        IngredientSort::ByRecipeCountAsc => {
+            ingredients.sort_by(|a, b| index[*a].len().cmp(&index[*b].len()).then_with(|| compare(a, b)))
+        }
+        IngredientSort::Custom(order) => {
+            let position: HashMap<&str, usize> = order
+                .iter()
+                .enumerate()
+                .rev()
+                .map(|(pos, name)| (name.as_str(), pos))
+                .collect();
+            let position = |name: &str| position.get(name).copied().unwrap_or(usize::MAX);
+            ingredients.sort_by(|a, b| position(a).cmp(&position(b)).then_with(|| compare(a, b)));
+        }
+    }
+    ingredients
+}
+
+/// Contents of the embedded `<style>` block, or `None` if there is nothing to embed
+pub(crate) fn stylesheet(options: &HtmlOptions) -> Option<String> {
+    let mut css = String::new();
+    if options.include_default_css {
+        match &options.theme {
+            Theme::Light => css.push_str(&root_rule(LIGHT_VARIABLES, "")),
+            Theme::Dark => css.push_str(&root_rule(DARK_VARIABLES, "")),
+            Theme::Auto => {
+                css.push_str(&root_rule(LIGHT_VARIABLES, ""));
+                css.push_str("        @media (prefers-color-scheme: dark) {\n");
+                css.push_str(&root_rule(DARK_VARIABLES, "    "));
+                css.push_str("        }\n");
+            }
+            Theme::Variables(variables) => {
+                css.push_str(&root_rule(LIGHT_VARIABLES, ""));
+                css.push_str(&root_rule(variables, ""));
+            }
+            Theme::Custom(custom) => push_block(&mut css, custom),
+        }
+        if !matches!(options.theme, Theme::Custom(_)) {
+            css.push_str(BASE_CSS);
+            if options.collapsible {
+                css.push_str(COLLAPSIBLE_CSS);
+            }
+            if options.show_context {
+                css.push_str(CONTEXT_CSS);
+            }
+            if options.tag_cloud {
+                css.push_str(TAG_CLOUD_CSS);
+            }
+        }
+        if options.print {
+            css.push_str(PRINT_CSS);
+        }
+    }
+    if let Some(extra) = &options.extra_css {
+        push_block(&mut css, extra);
+    }
+    (!css.is_empty()).then_some(css)
+}
+
+/// Collapses the whitespace of a stylesheet and drops its comments, leaving
+/// quoted strings alone
+fn minify_css(css: &str) -> String {
+    // No space is needed on either side of these; `:` is left out since a
+    // space before it matters in selectors such as `nav :hover`
+    let tight_before = |c: char| "{};,>".contains(c);
+    let tight_after = |c: char| "{};:,>".contains(c);
+
+    let mut out = String::with_capacity(css.len());
+    let mut chars = css.chars().peekable();
+    let mut space = false;
+    while let Some(c) = chars.next() {
+        match c {
+            '/' if chars.peek() == Some(&'*') => {
+                chars.next();
+                let mut prev = ' ';
+                for c in chars.by_ref() {
+                    if prev == '*' && c == '/' {
+                        break;
+                    }
+                    prev = c;
+                }
+                space = true;
+            }
+            c if c.is_whitespace() => space = true,
+            c => {
+                if space && !tight_before(c) && !out.ends_with(tight_after) && !out.is_empty() {
+                    out.push(' ');
+                }
+                space = false;
+                if c == '}' && out.ends_with(';') {
+                    out.pop();
+                }
+                out.push(c);
+                if c == '"' || c == '\'' {
+                    for quoted in chars.by_ref() {
+                        out.push(quoted);
+                        if quoted == c {
+                            break;
+                        }
+                    }
+                }
+            }
+        }
+    }
+    out
+}
+
+/// Appends a verbatim block of CSS or HTML, making sure it ends with a newline
+fn push_block(out: &mut String, block: &str) {
+    out.push_str(block);
+    if !block.ends_with('\n') {
+        out.push('\n');
+    }
+}
+
+/// Turns recipe paths into links, collecting warnings along the way
+pub(crate) struct Linker<'a> {
+    index: &'a IngredientIndex,
+    output_dir: &'a Path,
+    options: &'a HtmlOptions,
+    pub warnings: Vec<LinkWarning>,
+}
+
+impl<'a> Linker<'a> {
+    /// Links recipes of `index` from a page written to `output_dir`
+    pub(crate) fn new(index: &'a IngredientIndex, options: &'a HtmlOptions, output_dir: &'a Path) -> Self {
+        Self { index, output_dir, options, warnings: Vec::new() }
+    }
+
+    /// Links every recipe of one ingredient, leaving out the ones that are skipped
+    fn links(&mut self, ingredient: &str, recipes: &[&Path]) -> Vec<RecipeLink> {
+        let names = self.display_names(recipes);
+        let mut links: Vec<RecipeLink> = recipes
+            .iter()
+            .zip(names)
+            .filter_map(|(path, name)| {
+                let url = self.recipe_url(path)?;
+                let quantity = if self.options.show_quantities { self.quantity(ingredient, path) } else { None };
+                let context = if self.options.show_context { self.context(ingredient, path) } else { None };
+                Some(RecipeLink { name, url, path: path.to_path_buf(), quantity, context, folder: self.folder(path) })
+            })
+            .collect();
+
+        let by_name = |link: &RecipeLink| (link.name.to_lowercase(), link.name.clone(), link.path.clone());
+        match &self.options.recipe_sort {
+            RecipeSort::ByName => links.sort_by_cached_key(by_name),
+            RecipeSort::NewestFirst => links.sort_by_cached_key(|link| {
+                let modified = self.recipe(&link.path).and_then(|recipe| recipe.modified);
+                (Reverse(modified), by_name(link))
+            }),
+            RecipeSort::Key(key) => links.sort_by_cached_key(|link| {
+                (self.recipe(&link.path).map(|recipe| key.key(recipe)), link.path.clone())
+            }),
+        }
+        links
+    }
+
+    /// The indexed recipe at `path`
+    fn recipe(&self, path: &Path) -> Option<&Recipe> {
+        self.index.recipe(path)
+    }
+
+    /// How much of an ingredient a recipe uses, e.g. "2 cups"
+    ///
+    /// Amounts of the same unit are summed; anything else is listed
+    /// comma-separated. `None` if the recipe gives no quantity.
+    fn quantity(&self, ingredient: &str, path: &Path) -> Option<String> {
+        let recipe = self.recipe(path)?;
+        let mut total = ShoppingItem { name: ingredient.to_string(), amounts: Vec::new(), other: Vec::new() };
+        for used in recipe.ingredients.iter().filter(|used| used.name == ingredient) {
+            if let Some(amount) = &used.quantity {
+                total.add(amount);
+            }
+        }
+        let texts = total.amount_texts();
+        (!texts.is_empty()).then(|| texts.join(", "))
+    }
+
+    /// The text around a recipe's first use of an ingredient, escaped, with the
+    /// ingredient in a `<mark>` tag
+    fn context(&self, ingredient: &str, path: &Path) -> Option<String> {
+        let recipe = self.recipe(path)?;
+        let snippet = recipe
+            .ingredients
+            .iter()
+            .zip(&recipe.snippets)
+            .find_map(|(used, snippet)| (used.name == ingredient).then_some(snippet))?;
+        Some(format!(
+            "{}<mark>{}</mark>{}",
+            escape_html(&snippet.before),
+            escape_html(&snippet.mention),
+            escape_html(&snippet.after)
+        ))
+    }
+
+    /// The first folder of a recipe's path relative to the recipes directory
+    fn folder(&self, path: &Path) -> Option<String> {
+        let relative = strip_base_dir(path, self.index.root_of(path))?;
+        let mut components = relative.components().filter(|c| matches!(c, Component::Normal(_)));
+        let first = components.next()?;
+        // A single component is the file itself
+        components.next()?;
+        Some(first.as_os_str().to_string_lossy().into_owned())
+    }
+
+    /// Computes the link text for each recipe of one ingredient
+    ///
+    /// Recipes are named by their title, falling back to the file name. With
+    /// `disambiguate_names`, the whole list is grouped by name first: recipes
+    /// sharing a name get their parent folder appended, and those still sharing
+    /// a label after that get their relative path instead. A recipe at the top
+    /// level keeps the bare name while it is the only one there.
+    fn display_names(&self, recipes: &[&Path]) -> Vec<String> {
+        let names: Vec<String> = recipes
+            .iter()
+            .map(|path| self.recipe(path).map_or_else(|| recipe_display_name(path), recipe_name))
+            .collect();
+        if !self.options.disambiguate_names {
+            return names;
+        }
+
+        let relative: Vec<Cow<Path>> = recipes
+            .iter()
+            .map(|path| strip_base_dir(path, self.index.root_of(path)).unwrap_or(Cow::Borrowed(path)))
+            .collect();
+        let shared = |labels: &[String]| -> Vec<bool> {
+            let mut counts: HashMap<&str, usize> = HashMap::new();
+            for label in labels {
+                *counts.entry(label).or_default() += 1;
+            }
+            labels.iter().map(|label| counts[label.as_str()] > 1).collect()
+        };
+
+        let shared_names = shared(&names);
+        let labels: Vec<String> = names
+            .iter()
+            .zip(&relative)
+            .zip(&shared_names)
+            .map(|((name, path), &is_shared)| {
+                let folder = path.parent().and_then(Path::file_name);
+                match folder {
+                    Some(folder) if is_shared => format!("{} ({})", name, folder.to_string_lossy()),
+                    _ => name.clone(),
+                }
+            })
+            .collect();
+        let shared_labels = shared(&labels);
+        labels
+            .into_iter()
+            .zip(names.iter().zip(&relative))
+            .zip(shared_labels)
+            .map(|((label, (name, path)), is_shared)| {
+                if is_shared {
+                    format!("{} ({})", name, path.to_string_lossy())
+                } else {
+                    label
+                }
+            })
+            .collect()
+    }
+
+    /// Builds the link to a recipe, recording a warning if it cannot be built cleanly
+    pub(crate) fn recipe_url(&mut self, recipe_path: &Path) -> Option<String> {
+        let options = self.options;
+        let root = self.index.root_of(recipe_path);
+        let link = |lossy| match &options.base_url {
+            Some(base_url) => build_url(recipe_path, base_url, root, &options.url, lossy),
+            None => build_relative_url(recipe_path, root, self.output_dir, &options.url, lossy),
+        };
+        match link(false) {
+            Ok(url) => Some(url),
+            Err(error) => {
+                let warning = LinkWarning { path: recipe_path.to_path_buf(), error };
+                log::warn!("{}", warning);
+                self.warnings.push(warning);
+                match options.broken_links {
+                    BrokenLinks::Lossy => link(true).ok(),
+                    BrokenLinks::Skip => None,
+                }
+            }
+        }
+    }
+}
+
+/// Accumulates the HTML document one section at a time
+pub(crate) struct HtmlWriter<'a> {
+    pub index: &'a IngredientIndex,
+    pub options: &'a HtmlOptions,
+    pub html: String,
+    /// Where [`flush`](Self::flush) moves what `html` holds so far; without
+    /// one the whole document stays in `html`
+    pub out: Option<&'a mut dyn Write>,
+    /// Whether the next markup starts a line, whose indentation
+    /// [`HtmlOptions::minify`] drops
+    line_start: bool,
+}
+
+impl<'a> HtmlWriter<'a> {
+    /// A writer keeping the whole document in `html`
+    pub(crate) fn new(index: &'a IngredientIndex, options: &'a HtmlOptions) -> Self {
+        Self { index, options, html: String::new(), out: None, line_start: true }
+    }
+
+    /// Appends markup of our own; when minifying, its lines are joined and
+    /// stripped of their indentation
+    pub(crate) fn push(&mut self, markup: &str) {
+        if !self.options.minify {
+            self.html.push_str(markup);
+            return;
+        }
+        for (i, line) in markup.split('\n').enumerate() {
+            self.line_start |= i > 0;
+            let line = if self.line_start { line.trim_start() } else { line };
+            if !line.is_empty() {
+                self.html.push_str(line);
+                self.line_start = false;
+            }
+        }
+    }
+
+    /// Writes out and clears the markup accumulated so far, if streaming
+    pub(crate) fn flush(&mut self) -> io::Result<()> {
+        if let Some(out) = &mut self.out {
+            out.write_all(self.html.as_bytes())?;
+            self.html.clear();
+        }
+        Ok(())
+    }
+
+    /// Writes everything up to and including the page heading
+    pub(crate) fn write_head(&mut self) {
+        let title = escape_html(&self.options.title);
+        self.push(&format!(r#"<!DOCTYPE html>
+<html lang="{}">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{}</title>
+"#, escape_html(&self.options.lang), title));
+        if let Some(href) = &self.options.stylesheet_href {
+            self.push(&format!("    <link rel=\"stylesheet\" href=\"{}\">\n", escape_html(href)));
+        }
+        if let Some(css) = stylesheet(self.options) {
+            let css = if self.options.minify { minify_css(&css) } else { css };
+            self.push("    <style>\n");
+            self.push(&css);
+            self.line_start = true;
+            self.push("    </style>\n");
+        }
+        let heading = self.options.heading.as_deref().map_or(title, escape_html);
+        self.push(&format!(r#"</head>
+<body>
+<main>
+    <h1>{}</h1>
+"#, heading));
+        if let Some(intro) = &self.options.intro_html {
+            // Trusted markup is kept as written, even when minifying
+            push_block(&mut self.html, intro);
+            self.line_start = true;
+        }
+    }
+
+    /// Writes the index itself: the summary, tag cloud, navigation and filter
+    /// box when enabled, then every ingredient, inside the container div if any
+    ///
+    /// When streaming, each ingredient is written out as soon as it is complete.
+    pub(crate) fn write_index(
+        &mut self,
+        index: &IngredientIndex,
+        entries: &[IngredientEntry],
+        warnings: &[LinkWarning],
+    ) -> io::Result<()> {
+        let options = self.options;
+        if let Some(class) = &options.container_class {
+            self.push(&format!("<div class=\"{}\">\n", escape_html(class)));
+        }
+        if let Some(summary) = summary(index, entries, warnings, options) {
+            self.push(&summary);
+        }
+        if let Some(cloud) = tag_cloud(entries, options) {
+            self.push(&cloud);
+        }
+        if options.alphabet_nav {
+            self.write_alphabet_nav(entries, |id| format!("#{}", id));
+        }
+        if options.filter_box {
+            self.push(&filter_input(&options.labels));
+        }
+        for entry in entries {
+            self.write_ingredient(entry);
+            self.flush()?;
+        }
+        if options.container_class.is_some() {
+            self.push("</div>\n");
+        }
+        Ok(())
+    }
+
+    /// Writes the A–Z row linking to the first ingredient of each letter,
+    /// through the href `link` gives for its anchor id
+    pub(crate) fn write_alphabet_nav(&mut self, entries: &[IngredientEntry], link: impl Fn(&str) -> String) {
+        let label = escape_html(&self.options.labels.alphabet_nav);
+        self.push(&format!("    <nav class=\"alphabet-nav\" aria-label=\"{}\">\n", label));
+        for (label, id) in alphabet_links(entries) {
+            match id {
+                Some(id) => self.push(&format!("        <a href=\"{}\">{}</a>\n", link(&id), label)),
+                None => self.push(&format!("        <span>{}</span>\n", label)),
+            }
+        }
+        self.push("    </nav>\n");
+    }
+
+    /// Writes the navigation target and heading placed before the first
+    /// ingredient of a letter, if any
+    pub(crate) fn write_letter_markers(&mut self, entry: &IngredientEntry) {
+        if let Some(id) = &entry.letter_anchor {
+            self.push(&format!("<a class=\"letter-anchor\" id=\"{}\"></a>\n", id));
+        }
+        if let Some(label) = entry.letter_heading {
+            self.push(&format!("<h2 class=\"letter-heading\">{}</h2>\n", label));
+        }
+    }
+
+    /// The heading element of ingredient names, one level below the letter
+    /// headings when there are any
+    pub(crate) fn name_heading(&self) -> &'static str {
+        if self.options.group_by_letter { "h3" } else { "h2" }
+    }
+
+    /// Writes one ingredient section with the list of recipes using it
+    ///
+    /// Collapsible ingredients are `<details>` elements named by their summary,
+    /// the rest a `<section>` named by a heading.
+    fn write_ingredient(&mut self, entry: &IngredientEntry) {
+        self.write_letter_markers(entry);
+        let (element, name_element) = if self.options.collapsible {
+            ("details", "summary")
+        } else {
+            ("section", self.name_heading())
+        };
+        let open = if self.options.collapsible && self.options.open_by_default { " open" } else { "" };
+        self.push(&format!("<{} class=\"ingredient\" id=\"{}\"{}>\n", element, entry.anchor, open));
+        self.push(&format!(
+            "    <{} class=\"ingredient-name\"><a href=\"#{}\">{}</a>",
+            name_element,
+            entry.anchor,
+            escape_html(&entry.name)
+        ));
+        // The count is what a collapsed summary is for, so it is always shown there
+        if self.options.show_counts || self.options.collapsible {
+            let count = escape_html(&self.options.labels.recipe_count(entry.recipes.len()));
+            self.push(&format!(" <span class=\"ingredient-count\">({})</span>", count));
+        }
+        self.push(&format!("</{}>\n", name_element));
+        if self.options.group_recipes_by_dir {
+            self.write_recipes_by_folder(&entry.recipes);
+        } else {
+            self.write_recipe_list(&entry.recipes);
+        }
+        self.push(&format!("</{}>\n", element));
+    }
+
+    /// Writes one recipe list per top-level folder, each but the root one
+    /// under a folder label, sorted by recipe name within each folder
+    fn write_recipes_by_folder(&mut self, recipes: &[RecipeLink]) {
+        let mut sorted: Vec<&RecipeLink> = recipes.iter().collect();
+        sorted.sort_by_cached_key(|recipe| (recipe.folder.clone(), recipe.name.to_lowercase()));
+        for group in sorted.chunk_by(|a, b| a.folder == b.folder) {
+            if let Some(folder) = &group[0].folder {
+                self.push(&format!("    <div class=\"recipe-folder\">{}/</div>\n", escape_html(folder)));
+            }
+            self.write_recipe_list(group.iter().copied());
+        }
+    }
+
+    /// Writes the list of recipe links under an ingredient
+    pub(crate) fn write_recipe_list<'r>(&mut self, recipes: impl IntoIterator<Item = &'r RecipeLink>) {
+        self.push("    <ul class=\"recipe-list\">\n");
+        for recipe in recipes {
+            self.push(&format!(
+                "        <li><a href=\"{}\">{}</a>",
+                escape_html(&recipe.url),
+                escape_html(&recipe.name)
+            ));
+            if let Some(quantity) = &recipe.quantity {
+                self.push(&format!(" <span class=\"recipe-quantity\">— {}</span>", escape_html(quantity)));
+            }
+            if let Some(context) = &recipe.context {
+                self.push(&format!("<div class=\"recipe-context\">{}</div>", context));
+            }
+            self.push("</li>\n");
+        }
+        self.push("    </ul>\n");
+    }
+
+    /// Closes the main content and the document
+    pub(crate) fn write_tail(&mut self) {
+        self.push("</main>\n");
+        if let Some(footer) = footer(self.index, self.options) {
+            self.push(&footer);
+        }
+        if self.options.filter_box {
+            self.push(FILTER_SCRIPT);
+        }
+        self.push("</body>\n</html>");
+    }
+}
+
+/// The footer markup behind [`HtmlOptions::footer`], or `None` when there is none
+pub(crate) fn footer(index: &IngredientIndex, options: &HtmlOptions) -> Option<String> {
+    let generated_at = || format_utc(options.generated_at.unwrap_or_else(SystemTime::now));
+    let text = match &options.footer {
+        Footer::None => return None,
+        Footer::Auto => fill(
+            &options.labels.generated,
+            &[&generated_at(), concat!("cooklang-indexer ", env!("CARGO_PKG_VERSION"))],
+        ),
+        Footer::Stats => fill(
+            &options.labels.generated_stats,
+            &[
+                &generated_at(),
+                &options.labels.recipe_count(index.recipes.len()),
+                &options.labels.ingredient_count(index.index.len()),
+            ],
+        ),
+        Footer::Custom(text) => text.clone(),
+    };
+    Some(format!("    <footer class=\"page-footer\">{}</footer>\n", escape_html(&text)))
+}
+
+/// Formats a time as e.g. "2024-06-01 14:02 UTC"; times before 1970 show as the epoch
+fn format_utc(time: SystemTime) -> String {
+    let secs = time.duration_since(UNIX_EPOCH).map_or(0, |since| since.as_secs());
+    let (days, secs_of_day) = (secs / 86_400, secs % 86_400);
+
+    // Civil date from days since the epoch, after Howard Hinnant's `civil_from_days`
+    let z = days + 719_468;
+    let era = z / 146_097;
+    let doe = z % 146_097;
+    let yoe = (doe - doe / 1_460 + doe / 36_524 - doe / 146_096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let day = doy - (153 * mp + 2) / 5 + 1;
+    let month = if mp < 10 { mp + 3 } else { mp - 9 };
+    let year = yoe + era * 400 + u64::from(month <= 2);
+
+    format!(
+        "{:04}-{:02}-{:02} {:02}:{:02} UTC",
+        year,
+        month,
+        day,
+        secs_of_day / 3_600,
+        secs_of_day % 3_600 / 60
+    )
+}
+
+/// The name a recipe is listed under: its `>> title:`, or else its display name
+pub(crate) fn recipe_name(recipe: &Recipe) -> String {
+    recipe.title.clone().unwrap_or_else(|| recipe_display_name(&recipe.path))
+}
+
+/// Words kept lowercase by [`recipe_display_name`] unless they start the name
+const SMALL_WORDS: &[&str] = &["a", "an", "and", "as", "at", "by", "for", "in", "of", "on", "or", "the", "to", "with"];
+
+/// Derives the human-readable name of a recipe from its file name
+///
+/// Hyphens and underscores in the file stem become spaces and the words are
+/// title-cased, except small words such as "of" and "with" after the first
+/// one. Words already containing an uppercase letter are kept as written, and
+/// words starting with a digit are left alone. Every output (the HTML index,
+/// the site pages and templates) names recipes without a `>> title:` this way.
+///
+/// # Arguments
+/// * `path` - Path of the recipe file
+///
+/// # Returns
+/// * `String` - The display name, or "Unknown Recipe" for a path without a file name
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::recipe_display_name;
+/// use std::path::Path;
+///
+/// assert_eq!(recipe_display_name(Path::new("soups/beef-stew_with_dumplings.cook")), "Beef Stew with Dumplings");
+/// assert_eq!(recipe_display_name(Path::new("BBQ_ribs.cook")), "BBQ Ribs");
+/// ```
+pub fn recipe_display_name(path: &Path) -> String {
+    let Some(stem) = path.file_stem() else {
+        return "Unknown Recipe".to_string();
+    };
+    let stem = stem.to_string_lossy();
+    let words = stem.split(|c: char| c == '-' || c == '_' || c.is_whitespace()).filter(|word| !word.is_empty());
+    words
+        .enumerate()
+        .map(|(position, word)| {
+            if word.chars().any(char::is_uppercase) || (position > 0 && SMALL_WORDS.contains(&word)) {
+                return word.to_string();
+            }
+            let mut chars = word.chars();
+            match chars.next() {
+                Some(first) => first.to_uppercase().chain(chars).collect(),
+                None => String::new(),
+            }
+        })
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Escapes text for safe inclusion in HTML content and attribute values
+pub(crate) fn escape_html(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&#39;"),
+            _ => escaped.push(c),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,207 @@
+// File: src/json.rs
+
+//! JSON export of the index
+
+use crate::bucket::Bucket;
+use crate::html::recipe_name;
+use crate::url::export_path;
+use crate::{IndexOptions, Ingredient, IngredientIndex, Recipe};
+use anyhow::{Context, Result};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::path::PathBuf;
+
+#[derive(Serialize)]
+struct JsonIndex<'a> {
+    base_dir: String,
+    ingredients: Vec<JsonIngredient<'a>>,
+    recipes: Vec<JsonRecipe<'a>>,
+}
+
+#[derive(Serialize)]
+struct JsonIngredient<'a> {
+    name: &'a str,
+    recipes: Vec<String>,
+}
+
+#[derive(Serialize)]
+struct JsonRecipe<'a> {
+    path: String,
+    title: String,
+    tags: &'a [String],
+    ingredients: Vec<&'a str>,
+}
+
+/// A document written by [`IngredientIndex::generate_json`], read back
+#[derive(Deserialize)]
+struct Snapshot {
+    base_dir: PathBuf,
+    ingredients: Vec<SnapshotIngredient>,
+    recipes: Vec<SnapshotRecipe>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotIngredient {
+    name: String,
+    recipes: Vec<String>,
+}
+
+#[derive(Deserialize)]
+struct SnapshotRecipe {
+    path: String,
+    title: String,
+    tags: Vec<String>,
+    ingredients: Vec<String>,
+}
+
+impl IngredientIndex {
+    /// Generates the index as a JSON document, for tools that would rather
+    /// not scrape the HTML
+    ///
+    /// The document holds the `base_dir` of the recipes, the `ingredients` as
+    /// `{name, recipes}` in the order of [`ingredients`](Self::ingredients), and
+    /// the `recipes` as `{path, title, tags, ingredients}` sorted by path, with
+    /// each recipe's distinct ingredients in order of first use. Recipe paths
+    /// are relative to `base_dir` and use `/` on every platform.
+    ///
+    /// # Returns
+    /// * `Result<String>` - The pretty-printed JSON document
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// std::fs::write("index.json", index.generate_json().unwrap()).unwrap();
+    /// ```
+    pub fn generate_json(&self) -> Result<String> {
+        let document = JsonIndex {
+            base_dir: self.base_dir.display().to_string(),
+            ingredients: self.json_ingredients(),
+            recipes: self
+                .recipes
+                .iter()
+                .map(|recipe| {
+                    let mut seen = HashSet::new();
+                    JsonRecipe {
+                        path: export_path(&recipe.path, self.root_of(&recipe.path)),
+                        title: recipe_name(recipe),
+                        tags: &recipe.tags,
+                        ingredients: recipe
+                            .ingredients
+                            .iter()
+                            .map(|ingredient| ingredient.name.as_str())
+                            .filter(|name| seen.insert(*name))
+                            .collect(),
+                    }
+                })
+                .collect(),
+        };
+        Ok(serde_json::to_string_pretty(&document)?)
+    }
+
+    /// Generates the ingredients as newline-delimited JSON, one
+    /// `{"name": …, "recipes": […]}` object per line
+    ///
+    /// The objects are those of the `ingredients` of
+    /// [`generate_json`](Self::generate_json), in the same order, each on its
+    /// own line so that tools can process them one at a time.
+    ///
+    /// # Returns
+    /// * `Result<String>` - One line per ingredient, each ending with a newline
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// std::fs::write("ingredients.ndjson", index.generate_ndjson().unwrap()).unwrap();
+    /// ```
+    pub fn generate_ndjson(&self) -> Result<String> {
+        let mut ndjson = String::new();
+        for ingredient in self.json_ingredients() {
+            ndjson.push_str(&serde_json::to_string(&ingredient)?);
+            ndjson.push('\n');
+        }
+        Ok(ndjson)
+    }
+
+    /// Loads an index from a document written by
+    /// [`generate_json`](Self::generate_json), to query it without rescanning
+    /// the recipes
+    ///
+    /// The document only holds what was exported: recipes come back with their
+    /// title, tags and distinct ingredients, without amounts, snippets,
+    /// sections or other metadata. Recipe
+    /// paths are joined back onto `base_dir`, and the index uses the default
+    /// [`IndexOptions`].
+    ///
+    /// # Arguments
+    /// * `json` - The JSON document
+    ///
+    /// # Returns
+    /// * `Result<IngredientIndex>` - The index, or an error if the document is
+    ///   not an exported index
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let json = std::fs::read_to_string("index.json").unwrap();
+    /// let index = IngredientIndex::from_json(&json).unwrap();
+    /// println!("{} ingredients", index.ingredients().len());
+    /// ```
+    pub fn from_json(json: &str) -> Result<IngredientIndex> {
+        let snapshot: Snapshot = serde_json::from_str(json).context("not an exported ingredient index")?;
+        let base_dir = snapshot.base_dir;
+        let index: HashMap<String, Bucket> = snapshot
+            .ingredients
+            .into_iter()
+            .map(|ingredient| {
+                let mut paths: Vec<PathBuf> = ingredient.recipes.iter().map(|path| base_dir.join(path)).collect();
+                paths.sort();
+                (ingredient.name, Bucket::Owned(paths))
+            })
+            .collect();
+        let mut recipes: Vec<Recipe> = snapshot
+            .recipes
+            .into_iter()
+            .map(|recipe| Recipe {
+                path: base_dir.join(recipe.path),
+                metadata: BTreeMap::from([("title".to_string(), recipe.title.clone())]),
+                title: Some(recipe.title),
+                language: None,
+                modified: None,
+                tags: recipe.tags,
+                ingredients: recipe
+                    .ingredients
+                    .into_iter()
+                    .map(|name| Ingredient { name, ..Ingredient::default() })
+                    .collect(),
+                snippets: Vec::new(),
+                sections: Vec::new(),
+                references: Vec::new(),
+            })
+            .collect();
+        recipes.sort_by(|a, b| a.path.cmp(&b.path));
+        // Only recipes with ingredients are exported
+        let scanned = recipes.iter().map(|recipe| recipe.path.clone()).collect();
+        Ok(IngredientIndex {
+            index,
+            recipes,
+            scanned,
+            base_dir,
+            roots: HashMap::new(),
+            options: IndexOptions::default(),
+        })
+    }
+
+    /// Every ingredient with the recipes using it, as exported
+    fn json_ingredients(&self) -> Vec<JsonIngredient<'_>> {
+        self.ingredients()
+            .into_iter()
+            .map(|name| JsonIngredient {
+                name,
+                recipes: self.index[name].iter().map(|path| export_path(path, self.root_of(path))).collect(),
+            })
+            .collect()
+    }
+}
@@ -27,304 +27,1977 @@
 //! # }
 //! ```
 
-use std::collections::HashMap;
+mod atomic;
+mod bucket;
+#[cfg(feature = "collation")]
+mod collation;
+mod csv;
+#[cfg(feature = "gzip")]
+mod gzip;
+mod html;
+mod json;
+mod markdown;
+mod parse;
+mod search;
+#[cfg(feature = "serve")]
+mod serve;
+mod shopping;
+mod site;
+mod slug;
+mod spelling;
+#[cfg(feature = "sqlite")]
+mod sqlite;
+#[cfg(feature = "templates")]
+mod template;
+mod url;
+#[cfg(feature = "watch")]
+mod watch;
+
+pub use atomic::{write_atomically, write_atomically_with};
+pub use html::{
+    recipe_display_name, BrokenLinks, Footer, HtmlLabels, HtmlOptions, HtmlReport, IngredientSort, LinkWarning,
+    RecipeSort, RecipeSortKey, Theme,
+};
+pub use search::{Matching, SearchOptions};
+#[cfg(feature = "serve")]
+pub use serve::RecipeServer;
+pub use shopping::{Aisles, ShoppingAmount, ShoppingGroup, ShoppingItem, ShoppingList, ShoppingOptions};
+pub use site::SiteManifest;
+pub use slug::{slugify, SlugSet};
+#[cfg(feature = "templates")]
+pub use template::DEFAULT_TEMPLATE;
+pub use url::{
+    file_url, path_to_url, path_to_url_with, relative_url, try_path_to_url, try_path_to_url_with,
+    validate_base_url, DirBaseUrl, InvalidBaseUrl, UrlError, UrlExtension, UrlMapper, UrlMode,
+    UrlOptions,
+};
+#[cfg(feature = "watch")]
+pub use watch::{RecipeWatcher, WatchStopper};
+
+use std::cmp::Reverse;
+use std::collections::{BTreeMap, HashMap, HashSet};
+use std::fmt;
 use std::fs;
+use std::io::{self, Read};
 use std::path::{Path, PathBuf};
+use std::sync::Arc;
+use std::time::SystemTime;
+use unicode_normalization::UnicodeNormalization;
 use walkdir::WalkDir;
-use regex::Regex;
-use anyhow::{Result, Context};
+use anyhow::{Context, Result};
+use bucket::Bucket;
+use html::{generate_html_fragment, generate_html_index, write_html_index, generate_recipe_index, generate_tag_index, recipe_name};
+use parse::{looks_like_cooklang, parse_recipe, recipe_problems};
+
+/// Represents a single recipe file and its ingredients
+#[derive(Debug, Clone)]
+pub struct Recipe {
+    /// Path to the recipe file
+    pub path: PathBuf,
+    /// Title from the recipe's `>> title:` metadata, if it has one
+    pub title: Option<String>,
+    /// Language from the recipe's `>> language:` metadata, lowercased, e.g. `en`
+    /// or `es-mx`
+    pub language: Option<String>,
+    /// When the file was last modified, if the file system reports it
+    pub modified: Option<SystemTime>,
+    /// Tags from the recipe's comma-separated `>> tags:` metadata, as written,
+    /// without repeats differing only in case
+    pub tags: Vec<String>,
+    /// Every `>> key: value` metadata line, including `title` and `tags`,
+    /// keyed by the lowercased key; the last line wins for repeated keys
+    pub metadata: BTreeMap<String, String>,
+    /// Every use of an ingredient in the recipe, in order, with its quantity,
+    /// note and modifier
+    pub ingredients: Vec<Ingredient>,
+    /// The sentence around each entry of `ingredients`, in the same order, as
+    /// plain text of at most about 120 characters
+    pub snippets: Vec<Snippet>,
+    /// The recipe's `= Section` blocks with the ingredients used in each, in
+    /// order; ingredients before the first header belong to a section named `""`
+    pub sections: Vec<Section>,
+    /// Other recipes used as ingredients, as written: `./base-sauce` for
+    /// `@./base-sauce{}` or `recipe:base sauce` for `@recipe:base sauce`
+    ///
+    /// These are not listed in `ingredients`.
+    pub references: Vec<String>,
+}
+
+/// The quantity written in an ingredient's braces, e.g. `@flour{500%g}`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct IngredientAmount {
+    /// The part before `%`, e.g. `500`, or `None` if there are no braces or they are empty
+    pub quantity: Option<String>,
+    /// The part after `%`, e.g. `g`
+    pub unit: Option<String>,
+}
+
+/// One use of an ingredient in a recipe, e.g. `@?parmesan{50%g}(grated)`
+///
+/// Displays as its name, so it can stand in wherever the bare name was used.
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::IngredientIndex;
+///
+/// let index = IngredientIndex::new("./recipes").unwrap();
+/// for ingredient in &index.recipes()[0].ingredients {
+///     println!("{} ({:?})", ingredient, ingredient.note);
+/// }
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Ingredient {
+    /// Normalized name the ingredient is indexed under, e.g. `parmesan`
+    pub name: String,
+    /// Quantity and unit from the braces, or `None` if there are no braces or
+    /// they are empty
+    pub quantity: Option<IngredientAmount>,
+    /// The `(preparation)` note following the braces, e.g. `grated`
+    pub note: Option<String>,
+    /// The marker written between `@` and the name
+    pub modifier: Modifier,
+}
+
+impl fmt::Display for Ingredient {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.name)
+    }
+}
+
+/// Marker between `@` and an ingredient name, as in `@?parmesan{}`
+///
+/// The marker is not part of the indexed name.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Modifier {
+    /// No marker
+    #[default]
+    None,
+    /// `?`: the ingredient can be left out
+    Optional,
+    /// `-`: the ingredient is used but not listed
+    Hidden,
+    /// `&`: refers to an ingredient defined earlier in the recipe
+    Reference,
+    /// `+`: a new ingredient even if one with the same name came before
+    New,
+}
+
+impl Modifier {
+    /// Splits the marker, if any, off a raw ingredient name
+    pub(crate) fn split(name: &str) -> (Self, &str) {
+        let modifier = match name.chars().next() {
+            Some('?') => Self::Optional,
+            Some('-') => Self::Hidden,
+            Some('&') => Self::Reference,
+            Some('+') => Self::New,
+            _ => return (Self::None, name),
+        };
+        // Only a marker when the name follows directly, not in `@- {}`
+        match name[1..].chars().next() {
+            Some(next) if !next.is_whitespace() => (modifier, &name[1..]),
+            _ => (Self::None, name),
+        }
+    }
+}
+
+/// The text around one use of an ingredient in a recipe step
+///
+/// Recipe markup is removed, so `Whisk @flour{250%g} into @milk{}.` gives
+/// `Whisk `, `flour` and ` into milk.`
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Snippet {
+    /// Text leading up to the ingredient, starting with `…` when cut short
+    pub before: String,
+    /// The ingredient as written in the recipe
+    pub mention: String,
+    /// Text following the ingredient, ending with `…` when cut short
+    pub after: String,
+}
+
+impl fmt::Display for Snippet {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}{}{}", self.before, self.mention, self.after)
+    }
+}
+
+/// A `= Section` block of a recipe
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Section {
+    /// Section name without the surrounding `=` markers, `""` for the
+    /// unnamed block before the first header
+    pub name: String,
+    /// Ingredients used in this section, in order of appearance
+    pub ingredients: Vec<String>,
+}
+
+/// A function turning a raw ingredient name from a recipe into its index key
+///
+/// The default trims surrounding whitespace, composes accents (NFC) so that
+/// `CAFÉ` typed either way ends up as `café`, and lowercases the name using
+/// the locale-independent Unicode rules. Those rules map the Turkish dotted
+/// `İ` to `i` plus a combining dot and leave the dotless `ı` alone, so `İ`, `I`
+/// and `ı` give different keys; use [`Normalizer::case_fold`] (or
+/// [`IngredientIndexBuilder::case_fold`]) to merge them.
+#[derive(Clone)]
+pub struct Normalizer(Arc<NormalizerFn>);
+
+type NormalizerFn = dyn Fn(&str) -> String + Send + Sync;
+
+impl Normalizer {
+    /// Wraps a closure receiving the raw name as written in the recipe
+    pub fn new(normalizer: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(normalizer))
+    }
+
+    /// Applies the normalization to a raw ingredient name
+    pub fn normalize(&self, name: &str) -> String {
+        (self.0)(name)
+    }
+
+    /// A looser variant of the default that folds case the same way in every
+    /// language, for collections mixing spellings across locales
+    ///
+    /// On top of the default it treats all forms of `i` (`I`, `İ`, `ı`) as `i`,
+    /// `ß` as `ss` and the Greek final `ς` as `σ`. Accents are kept, so `café`
+    /// and `cafe` stay apart.
+    ///
+    /// # Example
+    /// ```
+    /// use cooklang_indexer::Normalizer;
+    ///
+    /// let fold = Normalizer::case_fold();
+    /// assert_eq!(fold.normalize("İNCİR"), fold.normalize("incir"));
+    /// assert_eq!(fold.normalize("Weißwurst"), "weisswurst");
+    /// ```
+    pub fn case_fold() -> Self {
+        Self::new(|name| {
+            let mut folded = String::with_capacity(name.len());
+            for c in name.trim().nfd() {
+                match c {
+                    // `İ` decomposes to `I` and U+0307; the dot goes, the `I` is lowercased
+                    '\u{0307}' if folded.ends_with('i') => {}
+                    'ı' => folded.push('i'),
+                    'ß' | 'ẞ' => folded.push_str("ss"),
+                    'ς' => folded.push('σ'),
+                    c => folded.extend(c.to_lowercase()),
+                }
+            }
+            folded.nfc().collect()
+        })
+    }
+}
+
+impl Default for Normalizer {
+    fn default() -> Self {
+        Self::new(|name| name.trim().nfc().collect::<String>().to_lowercase())
+    }
+}
+
+impl fmt::Debug for Normalizer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("Normalizer(..)")
+    }
+}
+
+/// How a standalone `&` and the word "and" are treated in ingredient names
+///
+/// Only whole words separated by spaces are rewritten, so glued forms such as
+/// `m&ms` or `sandwich` are left alone.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Ampersand {
+    /// Names are indexed as written (the default)
+    #[default]
+    Keep,
+    /// `salt & pepper` is indexed as `salt and pepper`
+    ToAnd,
+    /// `salt and pepper` is indexed as `salt & pepper`
+    ToAmpersand,
+}
+
+impl Ampersand {
+    /// Rewrites the standalone `&` or "and" words of a normalized name
+    pub fn apply(self, name: &str) -> String {
+        let (from, to) = match self {
+            Ampersand::Keep => return name.to_string(),
+            Ampersand::ToAnd => ("&", "and"),
+            Ampersand::ToAmpersand => ("and", "&"),
+        };
+        let words: Vec<&str> = name
+            .split(' ')
+            .map(|word| if word.eq_ignore_ascii_case(from) { to } else { word })
+            .collect();
+        words.join(" ")
+    }
+}
+
+/// Order of [`IngredientIndex::ingredients`], also followed by the HTML under
+/// the default [`IngredientSort::Index`]
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum IngredientOrder {
+    /// By name (the default)
+    #[default]
+    Alphabetical,
+    /// In the order ingredients first appear when reading the recipes one after
+    /// the other, by path, each from top to bottom
+    FirstSeen,
+    /// Used by the most recipes first, ties broken by name
+    ByFrequency,
+}
+
+/// Options controlling how recipes are scanned and ingredients extracted
+#[derive(Debug, Clone)]
+pub struct IndexOptions {
+    /// Ingredient names shorter than this many characters (after normalizing) are dropped
+    pub min_ingredient_len: usize,
+    /// Turns each extracted name into the key it is indexed under
+    pub normalizer: Normalizer,
+    /// Leading words removed from normalized names, e.g. `chopped` to index
+    /// `chopped onion` as `onion`; empty (the default) removes nothing
+    pub stripped_prefixes: Vec<String>,
+    /// Whether `&` and "and" are merged, applied after the normalizer
+    pub ampersand: Ampersand,
+    /// Order in which ingredients are listed
+    pub ingredient_order: IngredientOrder,
+    /// Correct spellings of ingredient names: a name missing from it but one
+    /// edit away from exactly one of its words is indexed under that word;
+    /// empty (the default) corrects nothing
+    pub spelling_dictionary: HashSet<String>,
+    /// Fail on malformed ingredients, empty names and unreadable files instead
+    /// of skipping them
+    pub strict: bool,
+    /// Aisles used to group shopping lists
+    pub aisles: Aisles,
+    /// Aisles, i.e. categories, whose ingredients are left out of the index,
+    /// matched ignoring case
+    pub excluded_categories: Vec<String>,
+    /// Builds recipe links when the HTML options don't set their own mapper
+    pub url_builder: Option<UrlMapper>,
+    /// Drop each recipe's snippets and sections, keeping its ingredients and
+    /// metadata, and share recipe paths between ingredients
+    pub low_memory: bool,
+    /// File extensions of recipe files, without the dot, matched case-sensitively
+    pub extensions: Vec<String>,
+    /// Whether symbolic links are followed while walking the recipes directory
+    pub follow_links: bool,
+    /// Index `@salt, pepper{}` as two ingredients, `salt` and `pepper`
+    pub split_on_comma: bool,
+    /// Also index files without any extension whose first bytes look like
+    /// cooklang
+    pub sniff_content: bool,
+}
+
+impl IndexOptions {
+    /// The key a raw ingredient name is indexed under
+    pub(crate) fn key(&self, name: &str) -> String {
+        let key = self.ampersand.apply(self.strip_prefixes(&self.normalizer.normalize(name)));
+        match spelling::correct(&self.spelling_dictionary, &key) {
+            Some(word) => word.clone(),
+            None => key,
+        }
+    }
+
+    /// A normalized name without the [stripped prefixes](Self::stripped_prefixes)
+    /// it starts with, as many as there are, but never down to nothing
+    fn strip_prefixes<'a>(&self, name: &'a str) -> &'a str {
+        let mut name = name;
+        while let Some(rest) = self.stripped_prefixes.iter().find_map(|prefix| {
+            let rest = name.strip_prefix(prefix.as_str())?;
+            // Whole words only, so `ground` leaves `groundnut` alone
+            rest.starts_with(char::is_whitespace).then(|| rest.trim_start()).filter(|rest| !rest.is_empty())
+        }) {
+            name = rest;
+        }
+        name
+    }
+
+    /// Whether an indexed name belongs to one of the excluded categories
+    pub(crate) fn is_excluded(&self, key: &str) -> bool {
+        self.aisles.aisle_of(key).is_some_and(|aisle| {
+            self.excluded_categories.iter().any(|excluded| excluded.eq_ignore_ascii_case(aisle))
+        })
+    }
+}
+
+impl Default for IndexOptions {
+    fn default() -> Self {
+        Self {
+            min_ingredient_len: 1,
+            normalizer: Normalizer::default(),
+            stripped_prefixes: Vec::new(),
+            ampersand: Ampersand::default(),
+            ingredient_order: IngredientOrder::default(),
+            spelling_dictionary: HashSet::new(),
+            strict: false,
+            aisles: Aisles::default(),
+            excluded_categories: Vec::new(),
+            url_builder: None,
+            low_memory: false,
+            extensions: vec!["cook".to_string(), "cooklang".to_string()],
+            follow_links: true,
+            split_on_comma: false,
+            sniff_content: false,
+        }
+    }
+}
+
+/// What [`IngredientIndex::refresh_files`] did
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct RefreshReport {
+    /// Recipe files read again, whether new or changed
+    pub updated: Vec<PathBuf>,
+    /// Recipes dropped because their file is gone
+    pub removed: Vec<PathBuf>,
+    /// Recipe files that could not be read or, in strict mode, have problems,
+    /// with the error; they are left out of the index
+    pub failed: Vec<(PathBuf, String)>,
+}
+
+impl RefreshReport {
+    /// Whether the index was left as it was
+    pub fn is_empty(&self) -> bool {
+        self.updated.is_empty() && self.removed.is_empty() && self.failed.is_empty()
+    }
+}
+
+/// Error returned by strict builds when recipes are not clean
+///
+/// See [`IngredientIndexBuilder::strict`].
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct RecipeProblems {
+    /// Everything found wrong, each prefixed with the offending path
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for RecipeProblems {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "found {} problem(s) in recipes:", self.problems.len())?;
+        for problem in &self.problems {
+            write!(f, "\n  {}", problem)?;
+        }
+        Ok(())
+    }
+}
+
+impl std::error::Error for RecipeProblems {}
+
+/// Builder for an [`IngredientIndex`] with non-default scanning options
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::IngredientIndex;
+///
+/// let index = IngredientIndex::builder("./recipes")
+///     .min_ingredient_len(2)
+///     .build()
+///     .unwrap();
+/// ```
+#[derive(Debug, Clone)]
+pub struct IngredientIndexBuilder {
+    recipes_dir: PathBuf,
+    options: IndexOptions,
+}
+
+impl IngredientIndexBuilder {
+    /// Creates a builder scanning the given directory with default options
+    pub fn new(recipes_dir: impl AsRef<Path>) -> Self {
+        Self {
+            recipes_dir: recipes_dir.as_ref().to_path_buf(),
+            options: IndexOptions::default(),
+        }
+    }
+
+    /// Drops extracted ingredient names shorter than `len` characters
+    ///
+    /// Single-character names are usually parse noise, e.g. a stray `@a`.
+    /// Defaults to 1, which only drops empty names.
+    pub fn min_ingredient_len(mut self, len: usize) -> Self {
+        self.options.min_ingredient_len = len;
+        self
+    }
+
+    /// Replaces the default trim+lowercase normalization of ingredient names
+    ///
+    /// The closure receives each name exactly as captured from the recipe and
+    /// returns the key it is indexed under, so names normalizing to the same
+    /// string are merged. It takes over trimming and lowercasing as well.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .normalizer(|name| name.trim().to_lowercase().replace("tomatoes", "tomato"))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn normalizer(mut self, normalizer: impl Fn(&str) -> String + Send + Sync + 'static) -> Self {
+        self.options.normalizer = Normalizer::new(normalizer);
+        self
+    }
+
+    /// Indexes names with [`Normalizer::case_fold`] instead of the default
+    /// lowercasing, so e.g. the Turkish `İ` and `ı` merge with `i`
+    ///
+    /// Like [`normalizer`](Self::normalizer), this replaces any earlier normalizer.
+    pub fn case_fold(mut self) -> Self {
+        self.options.normalizer = Normalizer::case_fold();
+        self
+    }
+
+    /// Strips descriptive words such as "chopped" or "fresh" from the start of
+    /// ingredient names, so that e.g. `chopped onion` merges with `onion`
+    ///
+    /// Only whole leading words are removed, any number of them, as in `fresh
+    /// chopped parsley`, and a name made of listed words alone keeps its last
+    /// one. The words go through the same normalization as recipe names, and
+    /// they are removed after the normalizer, whichever one is set. None are
+    /// removed by default.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .strip_prefixes(&["fresh", "chopped", "diced", "ground"])
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn strip_prefixes(mut self, prefixes: &[&str]) -> Self {
+        self.options.stripped_prefixes = prefixes.iter().map(|prefix| prefix.to_string()).collect();
+        self
+    }
+
+    /// Merges names spelled with a standalone `&` and with "and", e.g. `salt & pepper`
+    /// and `salt and pepper`, by rewriting one into the other
+    ///
+    /// The rewrite runs after the normalizer, whichever one is set.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{Ampersand, IngredientIndex};
+    ///
+    /// let index = IngredientIndex::builder("./recipes").ampersand(Ampersand::ToAnd).build().unwrap();
+    /// ```
+    pub fn ampersand(mut self, ampersand: Ampersand) -> Self {
+        self.options.ampersand = ampersand;
+        self
+    }
+
+    /// Sets the order of [`IngredientIndex::ingredients`] and, unless the HTML
+    /// options ask for another one, of the generated pages
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{IngredientIndex, IngredientOrder};
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .ingredient_order(IngredientOrder::FirstSeen)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn ingredient_order(mut self, order: IngredientOrder) -> Self {
+        self.options.ingredient_order = order;
+        self
+    }
+
+    /// Corrects ingredient names one typo away from a dictionary word, e.g.
+    /// `flur` to `flour`
+    ///
+    /// A name is corrected when it is not in the dictionary itself and a single
+    /// inserted, deleted or replaced character turns it into exactly one of its
+    /// words; names close to several words, or to none, are indexed as written.
+    /// Dictionary words go through the same normalization as recipe names, so
+    /// list every spelling you consider correct, e.g. both `pea` and `peas`.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let words = std::fs::read_to_string("./ingredients.txt").unwrap();
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .spelling_dictionary(words.lines().map(str::to_string).collect())
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn spelling_dictionary(mut self, dictionary: HashSet<String>) -> Self {
+        self.options.spelling_dictionary = dictionary;
+        self
+    }
+
+    /// Makes [`build`](Self::build) fail instead of skipping anything it cannot index
+    ///
+    /// Malformed ingredients such as a bare `@` or an unclosed `{`, names that
+    /// normalize to nothing, and unreadable files or directories are collected
+    /// and returned together as a [`RecipeProblems`] error. Useful in CI to keep
+    /// a recipe collection clean.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{IngredientIndex, RecipeProblems};
+    ///
+    /// if let Err(err) = IngredientIndex::builder("./recipes").strict(true).build() {
+    ///     if let Some(problems) = err.downcast_ref::<RecipeProblems>() {
+    ///         for problem in &problems.problems {
+    ///             eprintln!("{}", problem);
+    ///         }
+    ///     }
+    /// }
+    /// ```
+    pub fn strict(mut self, strict: bool) -> Self {
+        self.options.strict = strict;
+        self
+    }
+
+    /// Groups [shopping lists](IngredientIndex::shopping_list) by the given aisles
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{Aisles, IngredientIndex};
+    ///
+    /// let conf = std::fs::read_to_string("./recipes/config/aisle.conf").unwrap();
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .aisles(Aisles::parse(&conf))
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn aisles(mut self, aisles: Aisles) -> Self {
+        self.options.aisles = aisles;
+        self
+    }
+
+    /// Leaves the ingredients of some [aisles](Self::aisles) out of the index,
+    /// e.g. to drop water, salt and other pantry staples nobody looks up
+    ///
+    /// Categories are matched ignoring case. Excluded ingredients are dropped
+    /// from the recipes too, so they appear neither in the HTML nor in shopping
+    /// lists.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{Aisles, IngredientIndex};
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .aisles(Aisles::parse("[pantry staple]\nwater\nsalt\n"))
+    ///     .exclude_categories(&["pantry staple"])
+    ///     .build()
+    ///     .unwrap();
+    /// assert!(index.get_recipes_for_ingredient("water").is_none());
+    /// ```
+    pub fn exclude_categories(mut self, categories: &[&str]) -> Self {
+        self.options.excluded_categories = categories.iter().map(|category| category.trim().to_string()).collect();
+        self
+    }
+
+    /// Sets which file extensions mark recipe files, replacing the default of
+    /// `cook` and `cooklang`
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes").extensions(&["cook", "txt"]).build().unwrap();
+    /// ```
+    pub fn extensions(mut self, extensions: &[&str]) -> Self {
+        self.options.extensions = extensions.iter().map(|ext| ext.trim_start_matches('.').to_string()).collect();
+        self
+    }
+
+    /// Sets whether symbolic links to files and directories are followed while
+    /// scanning, as they are by default
+    pub fn follow_links(mut self, follow_links: bool) -> Self {
+        self.options.follow_links = follow_links;
+        self
+    }
+
+    /// Trades per-recipe detail for a smaller index, for very large collections
+    ///
+    /// Recipes are always read one file at a time and their text is dropped once
+    /// parsed. In low-memory mode each recipe also drops the text around each
+    /// ingredient and its sections, and the ingredients using a recipe share one
+    /// copy of its path rather than each holding their own; a copy is only made
+    /// for the ingredients looked up with
+    /// [`get_recipes_for_ingredient`](IngredientIndex::get_recipes_for_ingredient).
+    /// The ingredient index, the HTML pages, quantities and shopping lists stay
+    /// the same, but [`HtmlOptions::show_context`] has nothing to quote and
+    /// [`ingredients_in_section`](IngredientIndex::ingredients_in_section)
+    /// comes out empty.
+    pub fn low_memory(mut self, low_memory: bool) -> Self {
+        self.options.low_memory = low_memory;
+        self
+    }
+
+    /// Sets whether a name containing commas is indexed as several
+    /// ingredients, off by default
+    ///
+    /// Authors sometimes write `@salt, pepper{}` meaning two ingredients, but
+    /// commas also appear in single names like `@tomatoes, chopped{}`, so this
+    /// is only right for collections written that way. Any amount belongs to
+    /// the last name.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes").split_on_comma(true).build().unwrap();
+    /// assert!(index.get_recipes_for_ingredient("pepper").is_some());
+    /// ```
+    pub fn split_on_comma(mut self, split_on_comma: bool) -> Self {
+        self.options.split_on_comma = split_on_comma;
+        self
+    }
+
+    /// Sets whether files without an extension are indexed when they look
+    /// like cooklang, off by default
+    ///
+    /// The first kilobyte of each such file is read and checked for an
+    /// `@ingredient`, a `#cookware{}`, a `~{timer}` or a `>> key: value`
+    /// metadata line. Hidden files are never sniffed. Files with any extension
+    /// still need one of the [`extensions`](Self::extensions).
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes").sniff_content(true).build().unwrap();
+    /// ```
+    pub fn sniff_content(mut self, sniff_content: bool) -> Self {
+        self.options.sniff_content = sniff_content;
+        self
+    }
+
+    /// Takes over link generation for every HTML output of the index
+    ///
+    /// The closure receives the recipe path and the recipes directory and returns
+    /// the final href, e.g. to look names up in a slug map or to add query
+    /// parameters. Without it links are built by [`path_to_url`]. A mapper set
+    /// with [`HtmlOptions::url_mapper`] still takes precedence.
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .url_builder(|path, _recipes_dir| {
+    ///         format!("/view?recipe={}", path.file_stem().unwrap().to_string_lossy())
+    ///     })
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn url_builder(mut self, url_builder: impl Fn(&Path, &Path) -> String + Send + Sync + 'static) -> Self {
+        self.options.url_builder = Some(UrlMapper::new(url_builder));
+        self
+    }
+
+    /// Scans the recipe directory and builds the index
+    ///
+    /// # Returns
+    /// * `Result<IngredientIndex>` - The index if successful, or an error if the directory
+    ///   cannot be read or if there are issues parsing the files
+    pub fn build(mut self) -> Result<IngredientIndex> {
+        // Normalize the prefixes like the names they are stripped from
+        let prefixes = std::mem::take(&mut self.options.stripped_prefixes);
+        self.options.stripped_prefixes = prefixes
+            .iter()
+            .map(|prefix| self.options.normalizer.normalize(prefix).trim().to_string())
+            .filter(|prefix| !prefix.is_empty())
+            .collect();
+        // And then the dictionary like the names it is compared with, which
+        // strips those prefixes
+        let dictionary = std::mem::take(&mut self.options.spelling_dictionary);
+        self.options.spelling_dictionary = dictionary.iter().map(|word| self.options.key(word)).collect();
+
+        let (recipes, scanned) = index_recipes(&self.recipes_dir, &self.options)?;
+        Ok(IngredientIndex {
+            index: create_ingredient_index(&recipes, self.options.low_memory),
+            recipes,
+            scanned,
+            base_dir: self.recipes_dir,
+            roots: HashMap::new(),
+            options: self.options,
+        })
+    }
+}
+
+/// Main struct for managing ingredient indexing and HTML generation
+#[derive(Debug)]
+pub struct IngredientIndex {
+    index: HashMap<String, Bucket>,
+    /// Parsed recipes, sorted by path
+    recipes: Vec<Recipe>,
+    /// Paths of every recipe file read, sorted, including those without any
+    /// ingredient that `recipes` leaves out
+    scanned: Vec<PathBuf>,
+    base_dir: PathBuf,
+    /// Recipes directories of recipes merged in from another index, by recipe
+    /// path; every other recipe lives below `base_dir`
+    roots: HashMap<PathBuf, PathBuf>,
+    options: IndexOptions,
+}
+
+impl IngredientIndex {
+    /// Creates a new IngredientIndex by scanning the given directory for cooklang files
+    ///
+    /// # Arguments
+    /// * `recipes_dir` - Path to the directory containing cooklang recipe files
+    ///
+    /// # Returns
+    /// * `Result<IngredientIndex>` - The index if successful, or an error if the directory
+    ///   cannot be read or if there are issues parsing the files
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    /// 
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// ```
+    pub fn new(recipes_dir: impl AsRef<Path>) -> Result<Self> {
+        Self::builder(recipes_dir).build()
+    }
+
+    /// Returns a builder for configuring how the recipe directory is scanned
+    ///
+    /// # Arguments
+    /// * `recipes_dir` - Path to the directory containing cooklang recipe files
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let index = IngredientIndex::builder("./recipes")
+    ///     .min_ingredient_len(2)
+    ///     .build()
+    ///     .unwrap();
+    /// ```
+    pub fn builder(recipes_dir: impl AsRef<Path>) -> IngredientIndexBuilder {
+        IngredientIndexBuilder::new(recipes_dir)
+    }
+
+    /// Gets the options the index was built with, e.g. to report how it was produced
+    ///
+    /// The [spelling dictionary](IndexOptions::spelling_dictionary) is the
+    /// normalized one the names were checked against.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// let index = IngredientIndex::builder("./recipes").follow_links(false).build().unwrap();
+    /// assert!(!index.options().follow_links);
+    /// ```
+    pub fn options(&self) -> &IndexOptions {
+        &self.options
+    }
+
+    /// Gets the recipes directory the index was built from
+    pub fn base_dir(&self) -> &Path {
+        &self.base_dir
+    }
+
+    /// Gets the parsed recipes, sorted by path
+    ///
+    /// Recipes without any ingredient are not kept.
+    pub fn recipes(&self) -> &[Recipe] {
+        &self.recipes
+    }
+
+    /// Generates an HTML index of all ingredients and their recipes
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL where recipes will be hosted (e.g., "http://example.com/recipes")
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content as a string if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let html = index.generate_html("http://example.com/recipes").unwrap();
+    /// std::fs::write("index.html", html).unwrap();
+    /// ```
+    pub fn generate_html(&self, base_url: &str) -> Result<String> {
+        let options = HtmlOptions {
+            base_url: Some(base_url.to_string()),
+            ..HtmlOptions::default()
+        };
+        self.generate_html_with(&options)
+    }
+
+    /// Generates an HTML index of all ingredients using the given options
+    ///
+    /// The output is byte-for-byte reproducible for the same recipes and options,
+    /// so it can be content-hashed by static deploys.
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the generated HTML
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content as a string if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = HtmlOptions {
+    ///     base_url: Some("http://example.com/recipes".to_string()),
+    ///     title: "Pantry".to_string(),
+    ///     show_counts: true,
+    ///     ..HtmlOptions::default()
+    /// };
+    /// let html = index.generate_html_with(&options).unwrap();
+    /// ```
+    pub fn generate_html_with(&self, options: &HtmlOptions) -> Result<String> {
+        Ok(self.generate_html_report(options)?.html)
+    }
+
+    /// Writes the HTML index of [`generate_html_with`](Self::generate_html_with)
+    /// to `writer`, one ingredient at a time
+    ///
+    /// Unlike the string-returning methods, the markup is not collected into
+    /// one string, and it can go straight into e.g. a file or a gzip encoder.
+    /// The entries of all ingredients, with their recipe links, are still
+    /// worked out before the first byte is written, since the summary and the
+    /// A–Z navigation at the top depend on them, so memory use still grows
+    /// with the size of the index. Wrap unbuffered writers such as
+    /// [`File`](std::fs::File) in a [`BufWriter`](std::io::BufWriter).
+    ///
+    /// # Arguments
+    /// * `writer` - Destination of the HTML
+    /// * `options` - Options controlling the generated HTML
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success, or the first error of the writer or the options;
+    ///   the writer may have received part of the document by then
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # use std::{fs::File, io::BufWriter};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let file = BufWriter::new(File::create("index.html").unwrap());
+    /// index.write_html(file, &HtmlOptions::default()).unwrap();
+    /// ```
+    pub fn write_html(&self, mut writer: impl io::Write, options: &HtmlOptions) -> Result<()> {
+        write_html_index(self, options, &mut writer)?;
+        writer.flush().context("failed to write HTML")
+    }
+
+    /// Generates an HTML index listing ingredients in a caller-specified order
+    ///
+    /// Names in `order` are normalized like the recipes' ingredient names, so
+    /// they may be spelled as in the recipes. Ingredients missing from `order`
+    /// are appended alphabetically. See [`IngredientSort::Custom`].
+    ///
+    /// # Arguments
+    /// * `base_url` - Base URL where recipes will be hosted
+    /// * `order` - Ingredient names in the order they should be listed
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content as a string if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let order = vec!["flour".to_string(), "sugar".to_string(), "butter".to_string()];
+    /// let html = index.generate_html_with_order("http://example.com/recipes", &order).unwrap();
+    /// ```
+    pub fn generate_html_with_order(&self, base_url: &str, order: &[String]) -> Result<String> {
+        let order = order.iter().map(|name| self.options.key(name)).collect();
+        self.generate_html_with(&HtmlOptions {
+            base_url: Some(base_url.to_string()),
+            sort: IngredientSort::Custom(order),
+            ..HtmlOptions::default()
+        })
+    }
+
+    /// Generates an HTML index linking recipes by relative path, without a base URL
+    ///
+    /// Handy for opening the index straight from disk. Links are relative to
+    /// `output_dir`, the directory the HTML file will be written to.
+    ///
+    /// # Arguments
+    /// * `output_dir` - Directory the HTML will be written to
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content as a string if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let html = index.generate_html_relative("./recipes").unwrap();
+    /// std::fs::write("./recipes/index.html", html).unwrap();
+    /// ```
+    pub fn generate_html_relative(&self, output_dir: impl AsRef<Path>) -> Result<String> {
+        let options = HtmlOptions {
+            output_dir: Some(output_dir.as_ref().to_path_buf()),
+            ..HtmlOptions::default()
+        };
+        self.generate_html_with(&options)
+    }
+
+    /// Generates a page listing every recipe alphabetically by title
+    ///
+    /// Recipes are titled by their `>> title:` metadata or else their file name,
+    /// grouped under a heading per starting letter and linked like on the
+    /// ingredient index. Set `options.recipe_index_ingredients` to list the first
+    /// few ingredients of each recipe next to it. The page shares its head and
+    /// stylesheet with the ingredient index.
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the generated HTML
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = HtmlOptions {
+    ///     title: "All recipes".to_string(),
+    ///     recipe_index_ingredients: 3,
+    ///     ..HtmlOptions::default()
+    /// };
+    /// let html = index.generate_recipe_index_html(&options).unwrap();
+    /// ```
+    pub fn generate_recipe_index_html(&self, options: &HtmlOptions) -> Result<String> {
+        generate_recipe_index(self, options)
+    }
+
+    /// Generates a page with a section per recipe tag listing the recipes carrying it
+    ///
+    /// Tags come from the comma-separated `>> tags:` metadata of the recipes and
+    /// are grouped case-insensitively, shown as first spelled in the recipes.
+    /// The most used tags come first, ties sorted by name. Recipes without tags
+    /// are listed in a final "untagged" section unless `options.show_untagged`
+    /// is off.
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the generated HTML
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML content if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = HtmlOptions { title: "Recipes by tag".to_string(), ..HtmlOptions::default() };
+    /// let html = index.generate_tag_index_html(&options).unwrap();
+    /// ```
+    pub fn generate_tag_index_html(&self, options: &HtmlOptions) -> Result<String> {
+        generate_tag_index(self, options)
+    }
+
+    /// Generates an HTML index and reports recipes that could not be linked
+    ///
+    /// Recipes whose paths cannot be converted to a URL (see [`try_path_to_url`])
+    /// are skipped or linked lossily depending on `options.broken_links`; either
+    /// way they are listed in the returned warnings. An unusable base URL fails
+    /// up front with an [`InvalidBaseUrl`] error (see [`validate_base_url`]).
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the generated HTML
+    ///
+    /// # Returns
+    /// * `Result<HtmlReport>` - HTML content and any link warnings if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = HtmlOptions {
+    ///     base_url: Some("http://example.com/recipes".to_string()),
+    ///     ..HtmlOptions::default()
+    /// };
+    /// let report = index.generate_html_report(&options).unwrap();
+    /// for warning in &report.warnings {
+    ///     eprintln!("warning: {}", warning);
+    /// }
+    /// ```
+    pub fn generate_html_report(&self, options: &HtmlOptions) -> Result<HtmlReport> {
+        generate_html_index(self, options)
+    }
+
+    /// Generates only the index markup, without doctype, head or body, for
+    /// embedding in a page made by another tool
+    ///
+    /// This is exactly what the full page of
+    /// [`generate_html_with`](Self::generate_html_with) holds between its
+    /// heading and its footer, followed by the filter script when
+    /// [`HtmlOptions::filter_box`] is on. Set
+    /// [`HtmlOptions::container_class`] to wrap it all in a single `<div>`.
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the generated HTML; those about the
+    ///   page itself, such as the title and stylesheet, are ignored
+    ///
+    /// # Returns
+    /// * `Result<String>` - HTML fragment if successful
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = HtmlOptions {
+    ///     container_class: Some("ingredient-index".to_string()),
+    ///     ..HtmlOptions::default()
+    /// };
+    /// let fragment = index.generate_html_fragment(&options).unwrap();
+    /// ```
+    pub fn generate_html_fragment(&self, options: &HtmlOptions) -> Result<String> {
+        generate_html_fragment(self, options)
+    }
+
+    /// Generates the HTML index by rendering a Jinja template
+    ///
+    /// The template sees the same ingredients, anchors and links as
+    /// [`generate_html_with`](Self::generate_html_with) would produce for
+    /// `options`; see [`DEFAULT_TEMPLATE`] for the available context. Passing
+    /// `None` renders the built-in template, which matches the regular output.
+    ///
+    /// Requires the `templates` feature.
+    ///
+    /// # Arguments
+    /// * `template` - Template source, or `None` for [`DEFAULT_TEMPLATE`]
+    /// * `options` - Options controlling the links, title and stylesheet
+    ///
+    /// # Returns
+    /// * `Result<String>` - Rendered HTML, or an error naming the offending
+    ///   template line if the template is invalid
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let template = "{% for i in ingredients %}<h2 id=\"{{ i.anchor }}\">{{ i.name }}</h2>{% endfor %}";
+    /// let html = index.generate_html_from_template(Some(template), &HtmlOptions::default()).unwrap();
+    /// ```
+    #[cfg(feature = "templates")]
+    pub fn generate_html_from_template(&self, template: Option<&str>, options: &HtmlOptions) -> Result<String> {
+        template::render_template(self, template, options)
+    }
+
+    /// Renders a Jinja template like
+    /// [`generate_html_from_template`](Self::generate_html_from_template), writing
+    /// the page to `writer` as it is rendered instead of returning it
+    ///
+    /// The template engine writes many small pieces, so wrap files and sockets
+    /// in a [`BufWriter`](std::io::BufWriter). Requires the `templates` feature.
+    ///
+    /// # Arguments
+    /// * `writer` - Where to write the page
+    /// * `template` - Template source, or `None` for [`DEFAULT_TEMPLATE`]
+    /// * `options` - Options controlling the links, title and stylesheet
+    ///
+    /// # Returns
+    /// * `Result<()>` - Success, or an error naming the offending template line
+    ///   if the template is invalid, or the write that failed
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// # use std::{fs::File, io::BufWriter};
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let file = BufWriter::new(File::create("index.html").unwrap());
+    /// index.write_html_from_template(file, None, &HtmlOptions::default()).unwrap();
+    /// ```
+    #[cfg(feature = "templates")]
+    pub fn write_html_from_template(
+        &self,
+        mut writer: impl io::Write,
+        template: Option<&str>,
+        options: &HtmlOptions,
+    ) -> Result<()> {
+        template::write_template(self, template, options, &mut writer)?;
+        writer.flush().context("failed to write HTML")
+    }
+
+    /// Gets all recipes that contain a specific ingredient
+    ///
+    /// # Arguments
+    /// * `ingredient` - Name of the ingredient to search for
+    ///
+    /// # Returns
+    /// * `Option<&Vec<PathBuf>>` - Vector of paths to recipes containing the ingredient,
+    ///   or None if the ingredient isn't found
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// if let Some(recipes) = index.get_recipes_for_ingredient("chicken") {
+    ///     for recipe in recipes {
+    ///         println!("Found chicken recipe: {:?}", recipe);
+    ///     }
+    /// }
+    /// ```
+    pub fn get_recipes_for_ingredient(&self, ingredient: &str) -> Option<&Vec<PathBuf>> {
+        self.index.get(ingredient).map(Bucket::paths)
+    }
+
+    /// Gets all recipes that contain an ingredient, given as it might be
+    /// written in a recipe
+    ///
+    /// Unlike [`get_recipes_for_ingredient`](Self::get_recipes_for_ingredient),
+    /// which takes the name as indexed, the name is normalized like the names
+    /// in recipes first, so `"Flour"` finds the recipes using `@flour`.
+    ///
+    /// # Arguments
+    /// * `ingredient` - Name of the ingredient to search for
+    ///
+    /// # Returns
+    /// * `Option<&Vec<PathBuf>>` - Paths of the recipes containing the ingredient,
+    ///   or None if the ingredient isn't found
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// let recipes = index.find_recipes_for_ingredient(" Chicken ");
+    /// assert_eq!(recipes, index.get_recipes_for_ingredient("chicken"));
+    /// ```
+    pub fn find_recipes_for_ingredient(&self, ingredient: &str) -> Option<&Vec<PathBuf>> {
+        self.get_recipes_for_ingredient(&self.options.key(ingredient))
+    }
+
+    /// Finds recipes whose path cannot be turned into a URL
+    ///
+    /// These are the recipes [`try_path_to_url`] rejects, e.g. because a file
+    /// name is not valid UTF-8, which [`path_to_url`] could only link lossily or
+    /// to its `unknown` fallback and the HTML index leaves out with a
+    /// [`LinkWarning`]. Renaming them fixes their links.
+    /// With a custom [`url_builder`](IngredientIndexBuilder::url_builder), every
+    /// recipe is linkable.
+    ///
+    /// # Returns
+    /// * `Vec<&PathBuf>` - Paths of the unlinkable recipes, sorted
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for path in index.unlinkable_recipes() {
+    ///     eprintln!("rename {} to link it", path.display());
+    /// }
+    /// ```
+    pub fn unlinkable_recipes(&self) -> Vec<&PathBuf> {
+        let options = UrlOptions { mapper: self.options.url_builder.clone(), ..UrlOptions::default() };
+        self.recipes
+            .iter()
+            .map(|recipe| &recipe.path)
+            .filter(|path| try_path_to_url_with(path, "", self.root_of(path), &options).is_err())
+            .collect()
+    }
+
+    /// Gets the recipes that do not use an ingredient
+    ///
+    /// The complement of [`get_recipes_for_ingredient`](Self::get_recipes_for_ingredient),
+    /// e.g. to find recipes missing a staple. The name is normalized like the
+    /// names in recipes, so `"Salt"` finds the recipes without `@salt`.
+    ///
+    /// Recipe files without any ingredient are listed too, although they are
+    /// left out of the rest of the index.
+    ///
+    /// # Arguments
+    /// * `ingredient` - Name of the ingredient to look for
+    ///
+    /// # Returns
+    /// * `Vec<&PathBuf>` - Paths of the recipes not using the ingredient,
+    ///   sorted; every recipe if none uses it
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for recipe in index.recipes_without("salt") {
+    ///     println!("Unsalted: {}", recipe.display());
+    /// }
+    /// ```
+    pub fn recipes_without(&self, ingredient: &str) -> Vec<&PathBuf> {
+        let ingredient = self.options.key(ingredient);
+        self.scanned
+            .iter()
+            .filter(|path| {
+                !self.recipe(path).is_some_and(|recipe| recipe.ingredients.iter().any(|used| used.name == ingredient))
+            })
+            .collect()
+    }
 
-/// Represents a single recipe file and its ingredients
-#[derive(Debug)]
-pub struct Recipe {
-    /// Path to the recipe file
-    pub path: PathBuf,
-    /// List of ingredients found in the recipe
-    pub ingredients: Vec<String>,
-}
+    /// Finds ingredients listed more than once in the same recipe
+    ///
+    /// That can be a mistake, or legitimate when an ingredient is used in
+    /// several sections, e.g. butter for both dough and filling.
+    ///
+    /// # Returns
+    /// * `Vec<(PathBuf, String, usize)>` - Each recipe path, repeated ingredient
+    ///   and number of times it is listed, sorted by path and then ingredient
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for (path, ingredient, count) in index.intra_recipe_duplicates() {
+    ///     println!("{}: {} listed {} times", path.display(), ingredient, count);
+    /// }
+    /// ```
+    pub fn intra_recipe_duplicates(&self) -> Vec<(PathBuf, String, usize)> {
+        let mut duplicates = Vec::new();
+        for recipe in &self.recipes {
+            let mut counts: BTreeMap<&str, usize> = BTreeMap::new();
+            for ingredient in &recipe.ingredients {
+                *counts.entry(&ingredient.name).or_default() += 1;
+            }
+            duplicates.extend(
+                counts
+                    .into_iter()
+                    .filter(|&(_, count)| count > 1)
+                    .map(|(ingredient, count)| (recipe.path.clone(), ingredient.to_string(), count)),
+            );
+        }
+        duplicates
+    }
 
-/// Main struct for managing ingredient indexing and HTML generation
-#[derive(Debug)]
-pub struct IngredientIndex {
-    index: HashMap<String, Vec<PathBuf>>,
-    base_dir: PathBuf,
-}
+    /// Gets a list of all ingredients in the index
+    ///
+    /// # Returns
+    /// * `Vec<&String>` - Ingredient names, in the [`IngredientOrder`] the
+    ///   index was built with; alphabetical by default
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for ingredient in index.ingredients() {
+    ///     println!("Found ingredient: {}", ingredient);
+    /// }
+    /// ```
+    pub fn ingredients(&self) -> Vec<&String> {
+        match self.options.ingredient_order {
+            IngredientOrder::FirstSeen => {
+                let mut seen = HashSet::new();
+                self.recipes
+                    .iter()
+                    .flat_map(|recipe| &recipe.ingredients)
+                    .filter_map(|ingredient| self.index.get_key_value(&ingredient.name).map(|(key, _)| key))
+                    .filter(|key| seen.insert(*key))
+                    .collect()
+            }
+            IngredientOrder::Alphabetical => {
+                let mut ingredients: Vec<_> = self.index.keys().collect();
+                ingredients.sort();
+                ingredients
+            }
+            IngredientOrder::ByFrequency => {
+                let mut ingredients: Vec<_> = self.index.keys().collect();
+                ingredients.sort_by_key(|name| (Reverse(self.index[*name].len()), *name));
+                ingredients
+            }
+        }
+    }
 
-impl IngredientIndex {
-    /// Creates a new IngredientIndex by scanning the given directory for cooklang files
+    /// Finds recipes that share a display name
+    ///
+    /// The display name is the one used as link text in the HTML: the recipe's
+    /// `>> title:`, or else derived from the file name (e.g. `mains/curry.cook`
+    /// and `soups/curry.cook` are both "curry"). Such recipes are hard to tell apart in the generated index; see
+    /// [`HtmlOptions::disambiguate_names`].
+    ///
+    /// # Returns
+    /// * `Vec<(String, Vec<&PathBuf>)>` - Each shared name with the recipes using
+    ///   it, sorted by name
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for (name, paths) in index.duplicate_recipe_names() {
+    ///     println!("{} is used by {} recipes", name, paths.len());
+    /// }
+    /// ```
+    pub fn duplicate_recipe_names(&self) -> Vec<(String, Vec<&PathBuf>)> {
+        let mut by_name: HashMap<String, Vec<&PathBuf>> = HashMap::new();
+        for recipe in &self.recipes {
+            by_name.entry(recipe_name(recipe)).or_default().push(&recipe.path);
+        }
+        let mut duplicates: Vec<_> = by_name.into_iter().filter(|(_, paths)| paths.len() > 1).collect();
+        duplicates.sort();
+        duplicates
+    }
+
+    /// Lists the expected ingredients that no recipe uses
+    ///
+    /// Names are normalized like the recipes' ingredients before comparing, so
+    /// a curated pantry list can be spelled naturally.
     ///
     /// # Arguments
-    /// * `recipes_dir` - Path to the directory containing cooklang recipe files
+    /// * `expected` - The ingredient vocabulary to check against
     ///
     /// # Returns
-    /// * `Result<IngredientIndex>` - The index if successful, or an error if the directory
-    ///   cannot be read or if there are issues parsing the files
+    /// * `Vec<String>` - The unused entries of `expected`, as given and in order
     ///
     /// # Example
     /// ```no_run
-    /// use cooklang_indexer::IngredientIndex;
-    /// 
-    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for name in index.missing_from(&["flour", "saffron"]) {
+    ///     println!("no recipe uses {}", name);
+    /// }
     /// ```
-    pub fn new(recipes_dir: impl AsRef<Path>) -> Result<Self> {
-        let recipes = index_recipes(recipes_dir.as_ref())?;
-        Ok(Self {
-            index: create_ingredient_index(&recipes),
-            base_dir: recipes_dir.as_ref().to_path_buf(),
-        })
+    pub fn missing_from(&self, expected: &[&str]) -> Vec<String> {
+        expected
+            .iter()
+            .filter(|name| !self.index.contains_key(&self.options.key(name)))
+            .map(|name| name.to_string())
+            .collect()
     }
 
-    /// Generates an HTML index of all ingredients and their recipes
+    /// Lists the indexed ingredients that are not in the expected vocabulary
+    ///
+    /// The counterpart of [`missing_from`](Self::missing_from), useful to catch
+    /// typos and stray names in recipes.
     ///
     /// # Arguments
-    /// * `base_url` - Base URL where recipes will be hosted (e.g., "http://example.com/recipes")
+    /// * `expected` - The ingredient vocabulary to check against
     ///
     /// # Returns
-    /// * `Result<String>` - HTML content as a string if successful
+    /// * `Vec<String>` - Indexed ingredients missing from `expected`, sorted
+    pub fn unexpected(&self, expected: &[&str]) -> Vec<String> {
+        let expected: HashSet<String> = expected.iter().map(|name| self.options.key(name)).collect();
+        let mut unexpected: Vec<String> = self
+            .index
+            .keys()
+            .filter(|name| !expected.contains(*name))
+            .cloned()
+            .collect();
+        unexpected.sort();
+        unexpected
+    }
+
+    /// Finds the recipe using the most of the ingredients at hand, for "what
+    /// can I make" queries
+    ///
+    /// Among recipes using as many of them, the one needing the fewest
+    /// ingredients overall wins, as it leaves the least to buy; remaining ties
+    /// go to the first path.
+    ///
+    /// # Arguments
+    /// * `available` - The ingredients at hand, normalized like the indexed names
+    ///
+    /// # Returns
+    /// * `Option<(&PathBuf, usize, usize)>` - The recipe with how many of its
+    ///   distinct ingredients are available and how many it uses in total, or
+    ///   `None` if no recipe uses any of them
     ///
     /// # Example
     /// ```no_run
     /// # use cooklang_indexer::IngredientIndex;
     /// # let index = IngredientIndex::new("./recipes").unwrap();
-    /// let html = index.generate_html("http://example.com/recipes").unwrap();
-    /// std::fs::write("index.html", html).unwrap();
+    /// if let Some((path, matched, total)) = index.best_match(&["eggs", "milk", "flour"]) {
+    ///     println!("{} uses {} of its {} ingredients", path.display(), matched, total);
+    /// }
     /// ```
-    pub fn generate_html(&self, base_url: &str) -> Result<String> {
-        generate_html_index(&self.index, base_url, &self.base_dir)
+    pub fn best_match(&self, available: &[&str]) -> Option<(&PathBuf, usize, usize)> {
+        let available: HashSet<String> = available.iter().map(|name| self.options.key(name)).collect();
+        self.recipes
+            .iter()
+            .map(|recipe| {
+                let distinct: HashSet<&String> = recipe.ingredients.iter().map(|ingredient| &ingredient.name).collect();
+                let matched = distinct.iter().filter(|name| available.contains(**name)).count();
+                (&recipe.path, matched, distinct.len())
+            })
+            .filter(|&(_, matched, _)| matched > 0)
+            // Recipes are sorted by path, and min_by_key keeps the first of equals
+            .min_by_key(|&(_, matched, total)| (Reverse(matched), total))
     }
 
-    /// Gets all recipes that contain a specific ingredient
+    /// Gets the ingredients used by recipes in one language
+    ///
+    /// A recipe's language comes from its `>> language:` metadata; recipes
+    /// without one are left out. Languages match ignoring case, and a bare
+    /// language also matches its regional variants, so `en` covers `en-GB`.
     ///
     /// # Arguments
-    /// * `ingredient` - Name of the ingredient to search for
+    /// * `lang` - Language tag to look for, e.g. `es`
     ///
     /// # Returns
-    /// * `Option<&Vec<PathBuf>>` - Vector of paths to recipes containing the ingredient,
-    ///   or None if the ingredient isn't found
+    /// * `Vec<&String>` - The matching ingredients, in the order of
+    ///   [`ingredients`](Self::ingredients)
     ///
     /// # Example
     /// ```no_run
     /// # use cooklang_indexer::IngredientIndex;
     /// # let index = IngredientIndex::new("./recipes").unwrap();
-    /// if let Some(recipes) = index.get_recipes_for_ingredient("chicken") {
-    ///     for recipe in recipes {
-    ///         println!("Found chicken recipe: {:?}", recipe);
-    ///     }
+    /// for ingredient in index.ingredients_for_language("es") {
+    ///     println!("Ingrediente: {}", ingredient);
     /// }
     /// ```
-    pub fn get_recipes_for_ingredient(&self, ingredient: &str) -> Option<&Vec<PathBuf>> {
-        self.index.get(ingredient)
+    pub fn ingredients_for_language(&self, lang: &str) -> Vec<&String> {
+        let lang = lang.trim().to_lowercase();
+        let matches = |language: &str| {
+            language == lang || language.strip_prefix(lang.as_str()).is_some_and(|rest| rest.starts_with('-'))
+        };
+        let used: HashSet<&String> = self
+            .recipes
+            .iter()
+            .filter(|recipe| recipe.language.as_deref().is_some_and(matches))
+            .flat_map(|recipe| &recipe.ingredients)
+            .map(|ingredient| &ingredient.name)
+            .collect();
+        self.ingredients().into_iter().filter(|name| used.contains(name)).collect()
     }
 
-    /// Gets a sorted list of all ingredients in the index
+    /// Lists recipes by how many distinct ingredients they use, most first
+    ///
+    /// Handy for telling elaborate recipes from simple ones. Recipes with the
+    /// same count are ordered by path.
     ///
     /// # Returns
-    /// * `Vec<&String>` - Sorted vector of ingredient names
+    /// * `Vec<(&PathBuf, usize)>` - Each recipe with its number of distinct ingredients
     ///
     /// # Example
     /// ```no_run
     /// # use cooklang_indexer::IngredientIndex;
     /// # let index = IngredientIndex::new("./recipes").unwrap();
-    /// for ingredient in index.ingredients() {
-    ///     println!("Found ingredient: {}", ingredient);
+    /// if let Some((path, count)) = index.recipes_by_complexity().first() {
+    ///     println!("Most elaborate: {} ({} ingredients)", path.display(), count);
     /// }
     /// ```
-    pub fn ingredients(&self) -> Vec<&String> {
-        let mut ingredients: Vec<_> = self.index.keys().collect();
-        ingredients.sort();
+    pub fn recipes_by_complexity(&self) -> Vec<(&PathBuf, usize)> {
+        let mut recipes: Vec<_> = self
+            .recipes
+            .iter()
+            .map(|recipe| {
+                let distinct: HashSet<&String> = recipe.ingredients.iter().map(|ingredient| &ingredient.name).collect();
+                (&recipe.path, distinct.len())
+            })
+            .collect();
+        // Recipes are already sorted by path and the sort is stable
+        recipes.sort_by_key(|&(_, count)| Reverse(count));
+        recipes
+    }
+
+    /// Lists one page of ingredients by how many recipes use them, most first
+    ///
+    /// Ingredients used by the same number of recipes are ordered by name.
+    ///
+    /// # Arguments
+    /// * `offset` - Number of top-ranked ingredients to skip
+    /// * `limit` - Maximum number of ingredients to return
+    ///
+    /// # Returns
+    /// * `Vec<(&String, usize)>` - Each ingredient with its number of recipes,
+    ///   empty past the last page
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for (ingredient, count) in index.ranked_ingredients(0, 10) {
+    ///     println!("{}: {} recipes", ingredient, count);
+    /// }
+    /// ```
+    pub fn ranked_ingredients(&self, offset: usize, limit: usize) -> Vec<(&String, usize)> {
+        let mut ranked: Vec<_> = self.index.iter().map(|(name, recipes)| (name, recipes.len())).collect();
+        ranked.sort_by_key(|&(name, count)| (Reverse(count), name));
+        ranked.into_iter().skip(offset).take(limit).collect()
+    }
+
+    /// Gets the ingredients used in one section of a recipe
+    ///
+    /// Sections are introduced by cooklang `= Section` lines; ingredients before
+    /// the first header belong to the section named `""`. Each ingredient is
+    /// listed once, in order of first use.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the recipe file, as found by the scan
+    /// * `section` - Name of the section without the `=` markers
+    ///
+    /// # Returns
+    /// * `Vec<String>` - The section's ingredients, empty if the recipe or the
+    ///   section doesn't exist
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for ingredient in index.ingredients_in_section("./recipes/pie.cook", "Filling") {
+    ///     println!("Filling needs: {}", ingredient);
+    /// }
+    /// ```
+    pub fn ingredients_in_section(&self, path: impl AsRef<Path>, section: &str) -> Vec<String> {
+        let mut ingredients: Vec<String> = Vec::new();
+        let Some(recipe) = self.recipes.iter().find(|r| r.path == path.as_ref()) else {
+            return ingredients;
+        };
+        for found in recipe.sections.iter().filter(|s| s.name == section) {
+            for ingredient in &found.ingredients {
+                if !ingredients.contains(ingredient) {
+                    ingredients.push(ingredient.clone());
+                }
+            }
+        }
         ingredients
     }
-}
 
-/// Converts a file path to a URL using the provided base URL
-///
-/// # Arguments
-/// * `path` - Path to the recipe file
-/// * `base_url` - Base URL where recipes are hosted
-/// * `base_dir` - Base directory of recipes (used to create relative paths)
-///
-/// # Returns
-/// * `String` - Full URL to the recipe
-///
-/// # Example
-/// ```no_run
-/// use cooklang_indexer::path_to_url;
-/// use std::path::Path;
-/// 
-/// let url = path_to_url(
-///     Path::new("recipes/chicken_pasta.cook"),
-///     "http://example.com/recipes",
-///     Path::new("recipes")
-/// );
-/// assert_eq!(url, "http://example.com/recipes/chicken_pasta");
-/// ```
-pub fn path_to_url(path: &Path, base_url: &str, base_dir: &Path) -> String {
-    // Strip the base directory from the path to get the relative path
-    let relative_path = path.strip_prefix(base_dir)
-        .unwrap_or(path);  // Fallback to full path if strip fails
-    
-    // Get the stem (filename without extension)
-    let file_stem = relative_path
-        .file_stem()
-        .and_then(|s| s.to_str())
-        .unwrap_or("unknown");
-    
-    // Get the parent directory path if any, excluding the base directory
-    let parent_path = relative_path
-        .parent()
-        .and_then(|p| p.to_str())
-        .unwrap_or("");
-    
-    // Combine parent path and file stem, ensuring proper formatting
-    let final_path = if parent_path.is_empty() {
-        file_stem.to_string()
-    } else {
-        format!("{}/{}", parent_path, file_stem)
-    };
-    
-    // Construct the final URL, ensuring no double slashes
-    let base = base_url.trim_end_matches('/');
-    format!("{}/{}", base, urlencoding::encode(&final_path))
+    /// Gets the recipes a recipe uses as ingredients
+    ///
+    /// Path references such as `@./base-sauce{}` are resolved against the
+    /// recipe's own directory, trying the recipe file extensions when no
+    /// extension is given.
+    /// Name references such as `@recipe:base sauce` match the recipe whose
+    /// display name (its file name with `-` and `_` as spaces) is the same,
+    /// ignoring case. References to recipes that are not in the index are
+    /// skipped.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the recipe file, as found by the scan
+    ///
+    /// # Returns
+    /// * `Vec<PathBuf>` - Paths of the referenced recipes as found by the scan, in
+    ///   order of first reference; empty if the recipe doesn't exist
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// for dependency in index.recipe_dependencies("./recipes/lasagna.cook") {
+    ///     println!("Make first: {}", dependency.display());
+    /// }
+    /// ```
+    pub fn recipe_dependencies(&self, path: impl AsRef<Path>) -> Vec<PathBuf> {
+        let path = path.as_ref();
+        let Some(recipe) = self.recipes.iter().find(|r| r.path == path) else {
+            return Vec::new();
+        };
+
+        let mut dependencies: Vec<PathBuf> = Vec::new();
+        for reference in &recipe.references {
+            let found = match reference.strip_prefix("recipe:") {
+                Some(name) => {
+                    let name = name.trim().to_lowercase();
+                    self.recipes.iter().find(|r| recipe_display_name(&r.path).to_lowercase() == name)
+                }
+                None => {
+                    let target = url::normalize_path(&path.parent().unwrap_or(Path::new("")).join(reference));
+                    self.recipes.iter().find(|r| {
+                        let candidate = url::normalize_path(&r.path);
+                        candidate == target
+                            || (target.extension().is_none()
+                                && candidate.with_extension("") == target
+                                && candidate.extension().is_some_and(|ext| {
+                                    self.options.extensions.iter().any(|known| ext == known.as_str())
+                                }))
+                    })
+                }
+            };
+            match found {
+                Some(found) if !dependencies.contains(&found.path) => dependencies.push(found.path.clone()),
+                Some(_) => {}
+                None => log::debug!("{} references unknown recipe {}", path.display(), reference),
+            }
+        }
+        dependencies
+    }
+
+    /// Re-reads a single recipe file and replaces its contributions to the index
+    ///
+    /// Any ingredients previously recorded for `path` are removed before the fresh
+    /// ones are inserted, so this works for both new and changed files. The path
+    /// should be spelled the way the scan found it, i.e. below the recipes
+    /// directory the index was built from.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the recipe file
+    ///
+    /// # Returns
+    /// * `Result<()>` - An error if the file cannot be read, or if it has problems
+    ///   and the index was built in [strict](IngredientIndexBuilder::strict) mode;
+    ///   the index is left without the file's old entries in that case
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// let mut index = IngredientIndex::new("./recipes").unwrap();
+    /// index.upsert_file("./recipes/pancakes.cook").unwrap();
+    /// ```
+    pub fn upsert_file(&mut self, path: impl AsRef<Path>) -> Result<()> {
+        let path = path.as_ref();
+        self.remove_file(path);
+
+        let content = fs::read_to_string(path)
+            .with_context(|| format!("reading recipe {}", path.display()))?;
+        if self.options.strict {
+            let problems = recipe_problems(&content, &self.options);
+            if !problems.is_empty() {
+                let problems = problems
+                    .into_iter()
+                    .map(|problem| format!("{}: {}", path.display(), problem))
+                    .collect();
+                return Err(RecipeProblems { problems }.into());
+            }
+        }
+        let mut recipe = parse_recipe(path, &content, &self.options);
+        recipe.modified = fs::metadata(path).and_then(|metadata| metadata.modified()).ok();
+        log::debug!("re-parsed {}: {} ingredients", path.display(), recipe.ingredients.len());
+        let pos = self.scanned.partition_point(|scanned| scanned.as_path() < path);
+        self.scanned.insert(pos, path.to_path_buf());
+        if recipe.ingredients.is_empty() {
+            return Ok(());
+        }
+
+        let shared = Arc::from(path);
+        for ingredient in &recipe.ingredients {
+            let low_memory = self.options.low_memory;
+            self.index.entry(ingredient.name.clone()).or_insert_with(|| Bucket::new(low_memory)).insert(&shared);
+        }
+        let pos = self.recipes.partition_point(|r| r.path.as_path() < path);
+        self.recipes.insert(pos, recipe);
+        Ok(())
+    }
+
+    /// Removes a recipe file's contributions from the index
+    ///
+    /// Ingredients left without any recipe are dropped.
+    ///
+    /// # Arguments
+    /// * `path` - Path to the recipe file, spelled as in [`IngredientIndex::upsert_file`]
+    ///
+    /// # Returns
+    /// * `bool` - Whether the recipe was present in the index
+    pub fn remove_file(&mut self, path: impl AsRef<Path>) -> bool {
+        let path = path.as_ref();
+        let mut removed = false;
+        self.recipes.retain(|recipe| recipe.path != path);
+        self.scanned.retain(|scanned| scanned != path);
+        self.roots.remove(path);
+        self.index.retain(|_, paths| {
+            removed |= paths.remove(path);
+            !paths.is_empty()
+        });
+        removed
+    }
+
+    /// Brings the index up to date with files that were added, changed or
+    /// removed since it was built, e.g. as reported by a file watcher
+    ///
+    /// Recipe files are read again with [`IngredientIndex::upsert_file`] and
+    /// recipes whose file is gone are removed, as are all recipes below a
+    /// removed directory. A directory that exists has every recipe file below
+    /// it read again, which covers folders moved into the collection. Other
+    /// paths, e.g. editor swap files, are ignored. Unlike a new scan, only the
+    /// given paths are read.
+    ///
+    /// # Arguments
+    /// * `paths` - Files or directories that may have changed, spelled as in
+    ///   [`IngredientIndex::upsert_file`]
+    ///
+    /// # Returns
+    /// * `RefreshReport` - The recipes updated, removed or failing to be read
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    /// use std::path::PathBuf;
+    ///
+    /// let mut index = IngredientIndex::new("./recipes").unwrap();
+    /// let report = index.refresh_files(&[PathBuf::from("./recipes/pancakes.cook")]);
+    /// println!("{} updated, {} removed", report.updated.len(), report.removed.len());
+    /// ```
+    pub fn refresh_files(&mut self, paths: &[PathBuf]) -> RefreshReport {
+        let mut report = RefreshReport::default();
+        let mut files = Vec::new();
+        for path in paths {
+            if path.is_dir() {
+                let entries = WalkDir::new(path).follow_links(self.options.follow_links).into_iter();
+                files.extend(entries.filter_map(|entry| entry.ok()).map(walkdir::DirEntry::into_path));
+            } else if path.exists() {
+                files.push(path.clone());
+            } else {
+                let gone: Vec<PathBuf> = self
+                    .recipes
+                    .iter()
+                    .map(|recipe| recipe.path.clone())
+                    .filter(|recipe| recipe.starts_with(path))
+                    .collect();
+                for recipe in gone {
+                    self.remove_file(&recipe);
+                    report.removed.push(recipe);
+                }
+            }
+        }
+        files.retain(|path| is_recipe_file(path, &self.options));
+        files.sort();
+        files.dedup();
+
+        for path in files {
+            match self.upsert_file(&path) {
+                Ok(()) => report.updated.push(path),
+                Err(err) => report.failed.push((path, format!("{:#}", err))),
+            }
+        }
+        report.removed.sort();
+        report.removed.dedup();
+        report
+    }
+
+    /// Adds the recipes of another index to this one
+    ///
+    /// Useful for combining scans of different recipes directories, or for
+    /// adding a newly scanned folder to an existing index without rescanning
+    /// everything. Ingredient names are taken as `other` normalized them, and
+    /// a recipe present in both indexes is replaced by the one from `other`.
+    /// Recipes from a different recipes directory keep it as their root, so
+    /// their links and exported paths stay relative to where they were found.
+    /// This index's options are kept.
+    ///
+    /// # Arguments
+    /// * `other` - The index to merge into this one
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::IngredientIndex;
+    ///
+    /// let mut index = IngredientIndex::new("./recipes").unwrap();
+    /// index.merge(IngredientIndex::new("./family-recipes").unwrap());
+    /// ```
+    pub fn merge(&mut self, other: IngredientIndex) {
+        for path in &other.scanned {
+            self.remove_file(path);
+            let root = other.root_of(path);
+            if root != self.base_dir {
+                self.roots.insert(path.clone(), root.to_path_buf());
+            }
+        }
+        // Each recipe's path is shared by all its buckets, as in a new scan
+        let shared: HashMap<&Path, Arc<Path>> =
+            other.recipes.iter().map(|recipe| (recipe.path.as_path(), Arc::from(recipe.path.as_path()))).collect();
+        let low_memory = self.options.low_memory;
+        for (name, paths) in &other.index {
+            let bucket = self.index.entry(name.clone()).or_insert_with(|| Bucket::new(low_memory));
+            for path in paths.iter() {
+                bucket.insert(&shared.get(path).cloned().unwrap_or_else(|| Arc::from(path)));
+            }
+        }
+        self.recipes.extend(other.recipes);
+        self.recipes.sort_by(|a, b| a.path.cmp(&b.path));
+        self.scanned.extend(other.scanned);
+        self.scanned.sort();
+    }
+
+    /// The indexed recipe at `path`
+    pub(crate) fn recipe(&self, path: &Path) -> Option<&Recipe> {
+        let recipes = &self.recipes;
+        recipes.binary_search_by(|recipe| recipe.path.as_path().cmp(path)).ok().map(|pos| &recipes[pos])
+    }
+
+    /// The recipes directory a recipe was found in
+    pub(crate) fn root_of(&self, path: &Path) -> &Path {
+        self.roots.get(path).unwrap_or(&self.base_dir)
+    }
 }
 
 /// Creates the Ingredient-Recipe index
 ///
-/// Walks the provided directory, extracting cooklang ingredients
-fn index_recipes(dir: &Path) -> Result<Vec<Recipe>> {
+/// Walks the provided directory, extracting cooklang ingredients, and returns
+/// the recipes with ingredients along with the paths of all recipe files read
+fn index_recipes(dir: &Path, options: &IndexOptions) -> Result<(Vec<Recipe>, Vec<PathBuf>)> {
     let mut recipes = Vec::new();
-    let ingredient_regex = Regex::new(r"@([^{@\n]+)(?:\{[^}]*\})?").unwrap();
+    let mut scanned = Vec::new();
+    let mut skipped = 0;
+    let mut problems = Vec::new();
     
-    for entry in WalkDir::new(dir)
-        .follow_links(true)
-        .into_iter()
-        .filter_map(|e| e.ok()) {
+    for entry in WalkDir::new(dir).follow_links(options.follow_links) {
+            let entry = match entry {
+                Ok(entry) => entry,
+                Err(err) => {
+                    log::warn!("skipping unreadable directory entry: {}", err);
+                    problems.push(format!("unreadable directory entry: {}", err));
+                    skipped += 1;
+                    continue;
+                }
+            };
             let path = entry.path();
-            if path.extension().and_then(|s| s.to_str()) == Some("cook") {
-                let content = fs::read_to_string(path)?;
-                let ingredients: Vec<String> = ingredient_regex
-                    .captures_iter(&content)
-                    .map(|cap| cap[1].trim().to_lowercase())
-                    .collect();
+            if is_recipe_file(path, options) {
+                let content = match fs::read_to_string(path) {
+                    Ok(content) => content,
+                    Err(err) => {
+                        log::warn!("skipping {}: {}", path.display(), err);
+                        problems.push(format!("{}: {}", path.display(), err));
+                        skipped += 1;
+                        continue;
+                    }
+                };
+                if options.strict {
+                    problems.extend(
+                        recipe_problems(&content, options)
+                            .into_iter()
+                            .map(|problem| format!("{}: {}", path.display(), problem)),
+                    );
+                }
+                let mut recipe = parse_recipe(path, &content, options);
+                recipe.modified = entry.metadata().ok().and_then(|metadata| metadata.modified().ok());
+                log::debug!("parsed {}: {} ingredients", path.display(), recipe.ingredients.len());
+                scanned.push(path.to_path_buf());
                 
-                if !ingredients.is_empty() {
-                    recipes.push(Recipe {
-                        path: path.to_owned(),
-                        ingredients,
-                    });
+                if !recipe.ingredients.is_empty() {
+                    recipes.push(recipe);
                 }
             }
     }
     
-    Ok(recipes)
+    if options.strict && !problems.is_empty() {
+        return Err(RecipeProblems { problems }.into());
+    }
+    recipes.sort_by(|a, b| a.path.cmp(&b.path));
+    scanned.sort();
+    log::info!(
+        "indexed {} recipes from {} ({} skipped)",
+        recipes.len(),
+        dir.display(),
+        skipped
+    );
+    Ok((recipes, scanned))
 }
 
-/// Build an ingredient index out of the list of recipes and the ingredients they contain
-fn create_ingredient_index(recipes: &[Recipe]) -> HashMap<String, Vec<PathBuf>> {
-    let mut index: HashMap<String, Vec<PathBuf>> = HashMap::new();
-    
-    for recipe in recipes {
-        for ingredient in &recipe.ingredients {
-            index
-                .entry(ingredient.clone())
-                .or_default()
-                .push(recipe.path.clone());
+/// Whether a file is a recipe, by its extension or, when
+/// [sniffing](IndexOptions::sniff_content), by its first bytes
+fn is_recipe_file(path: &Path, options: &IndexOptions) -> bool {
+    match path.extension() {
+        Some(ext) => ext.to_str().is_some_and(|ext| options.extensions.iter().any(|known| known == ext)),
+        None => {
+            let hidden = path.file_name().is_some_and(|name| name.to_string_lossy().starts_with('.'));
+            options.sniff_content && !hidden && path.is_file() && sniff_file(path)
         }
     }
-    
-    // Sort the paths for each ingredient for consistent output
-    for paths in index.values_mut() {
-        paths.sort();
+}
+
+/// Whether the first kilobyte of a file looks like cooklang
+fn sniff_file(path: &Path) -> bool {
+    let mut head = Vec::with_capacity(1024);
+    match fs::File::open(path).and_then(|file| file.take(1024).read_to_end(&mut head)) {
+        Ok(_) => looks_like_cooklang(&head),
+        Err(err) => {
+            log::debug!("cannot sniff {}: {}", path.display(), err);
+            false
+        }
     }
-    
-    index
 }
 
-/// builds basic html with the list of ingredients and which recipes they 
-/// are included in.
-fn generate_html_index(
-    index: &HashMap<String, Vec<PathBuf>>, 
-    base_url: &str,
-    base_dir: &Path
-) -> Result<String> {
-    let mut ingredients: Vec<_> = index.keys().collect();
-    ingredients.sort();
+/// Build an ingredient index out of the list of recipes and the ingredients they contain
+fn create_ingredient_index(recipes: &[Recipe], low_memory: bool) -> HashMap<String, Bucket> {
+    let mut index: HashMap<String, Bucket> = HashMap::new();
     
-    let mut html = String::from(r#"<!DOCTYPE html>
-<html lang="en">
-<head>
-    <meta charset="UTF-8">
-    <meta name="viewport" content="width=device-width, initial-scale=1.0">
-    <title>Recipe Ingredient Index</title>
-    <style>
-        body {
-            font-family: -apple-system, BlinkMacSystemFont, "Segoe UI", Roboto, sans-serif;
-            max-width: 800px;
-            margin: 0 auto;
-            padding: 20px;
-            line-height: 1.6;
-        }
-        h1 {
-            color: #2c3e50;
-            border-bottom: 2px solid #eee;
-            padding-bottom: 10px;
-        }
-        .ingredient {
-            margin: 20px 0;
-        }
-        .ingredient-name {
-            font-weight: bold;
-            color: #34495e;
-            margin-bottom: 5px;
-        }
-        .recipe-list {
-            margin-left: 20px;
-            list-style-type: none;
-        }
-        .recipe-list li {
-            margin: 5px 0;
-        }
-        a {
-            color: #3498db;
-            text-decoration: none;
-        }
-        a:hover {
-            text-decoration: underline;
-        }
-    </style>
-</head>
-<body>
-    <h1>Recipe Ingredient Index</h1>
-"#);
-
-
-    for ingredient in ingredients {
-        html.push_str("<div class=\"ingredient\">\n");
-        html.push_str(&format!("    <div class=\"ingredient-name\">{}</div>\n", ingredient));
-        html.push_str("    <ul class=\"recipe-list\">\n");
-        
-        if let Some(recipes) = index.get(ingredient) {
-            for recipe_path in recipes {
-                let recipe_name = recipe_path
-                    .file_stem()
-                    .and_then(|s| s.to_str())
-                    .unwrap_or("Unknown Recipe")
-                    .replace("-", " ")
-                    .replace("_", " ");
-                
-                let url = path_to_url(recipe_path, base_url, base_dir);
-                
-                html.push_str(&format!(
-                    "        <li><a href=\"{}\">{}</a></li>\n",
-                    url,
-                    recipe_name
-                ));
-            }
+    // Buckets keep their paths sorted for consistent output, listing a recipe
+    // once even if it mentions the ingredient several times. In low-memory
+    // mode they all share one copy of each recipe's path.
+    for recipe in recipes {
+        let path = Arc::from(recipe.path.as_path());
+        for ingredient in &recipe.ingredients {
+            index.entry(ingredient.name.clone()).or_insert_with(|| Bucket::new(low_memory)).insert(&path);
         }
-        
-        html.push_str("    </ul>\n");
-        html.push_str("</div>\n");
     }
-    html.push_str("</body>\n</html>");
     
-    Ok(html)
+    index
 }
@@ -1,27 +1,871 @@
-use cooklang_indexer::IngredientIndex;
-use anyhow::{Context}; //required to compile even though it throws a warning
+use cooklang_indexer::{
+    validate_base_url, write_atomically, Aisles, Footer, HtmlOptions, IngredientIndex, InvalidBaseUrl, Matching,
+    SearchOptions, ShoppingOptions,
+};
+#[cfg(feature = "watch")]
+use cooklang_indexer::{RecipeWatcher, RefreshReport};
+use anyhow::{bail, Context};
+use clap::{Args, Parser, Subcommand, ValueEnum};
+use serde::Serialize;
+use std::fmt;
 use std::fs;
+use std::io::{self, Write};
+use std::path::{Path, PathBuf};
+use std::process::ExitCode;
+use std::str::FromStr;
+use std::time::{Duration, UNIX_EPOCH};
+#[cfg(feature = "watch")]
+use std::time::Instant;
+
+/// Index the ingredients of a cooklang recipe collection
+///
+/// Without a subcommand, runs `index` with the arguments given.
+#[derive(Parser)]
+#[command(
+    version,
+    args_conflicts_with_subcommands = true,
+    subcommand_negates_reqs = true,
+    arg_required_else_help = true,
+    after_help = EXAMPLES
+)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    index: IndexArgs,
+}
+
+const EXAMPLES: &str = "Examples:
+  cooklang-indexer ./recipes
+  cooklang-indexer ./recipes --base-url https://example.com/r/ --output public/index.html
+  cooklang-indexer index --dir ./recipes --format html --format json=public/index.json
+  cooklang-indexer query flour ./recipes
+  cooklang-indexer search ./recipes garlic ginger --all
+  cooklang-indexer stats ./recipes --top 20
+  cooklang-indexer shopping-list ./recipes mains/curry.cook soups/minestrone.cook --scale 2
+  cooklang-indexer site ./recipes --out public
+  cooklang-indexer watch ./recipes --output public/index.html";
+
+#[derive(Subcommand)]
+enum Command {
+    /// Write the ingredient index, linking every ingredient to the recipes
+    /// using it (the default command)
+    #[command(alias = "build")]
+    Index(IndexArgs),
+    /// Print the recipes using an ingredient, one path per line
+    Query {
+        /// Ingredient to look up, normalized like the names in recipes
+        ingredient: String,
+        /// Directory holding the recipes
+        #[arg(default_value = ".")]
+        recipes_dir: PathBuf,
+    },
+    /// Print the recipes using ingredients matching search terms, one path per
+    /// line, and exit with status 1 if there are none
+    Search(SearchArgs),
+    /// Print an overview of the collection: totals, the most used ingredients
+    /// and the largest and smallest recipes
+    Stats {
+        /// Directory holding the recipes
+        #[arg(default_value = ".")]
+        recipes_dir: PathBuf,
+        /// How many of the most used ingredients to list
+        #[arg(long, value_name = "N", default_value_t = 10)]
+        top: usize,
+        /// Print a JSON object instead
+        #[arg(long)]
+        json: bool,
+    },
+    /// Print the ingredients to buy for some recipes, with their quantities
+    /// summed and grouped by aisle
+    ShoppingList(ShoppingListArgs),
+    /// Serve the index, its JSON export and the recipe files over HTTP
+    #[cfg(feature = "serve")]
+    Serve(ServeArgs),
+    /// Write the index like `index`, then again whenever recipes change, until
+    /// interrupted
+    #[cfg(feature = "watch")]
+    Watch(IndexArgs),
+    /// Write a static site with a page per ingredient and per recipe, a
+    /// search index and, with `--base-url`, a sitemap
+    Site {
+        /// Directory holding the recipes
+        #[arg(default_value = ".")]
+        recipes_dir: PathBuf,
+        /// Directory to write the site to
+        #[arg(long)]
+        out: PathBuf,
+        /// URL the recipes and the site are served from
+        #[arg(long)]
+        base_url: Option<String>,
+        /// Fail when some recipes could not be parsed or linked
+        #[arg(long)]
+        strict: bool,
+        /// Add a footer to every page saying when and by which version it
+        /// was generated; set `SOURCE_DATE_EPOCH` to fix the time
+        #[arg(long)]
+        footer: bool,
+    },
+}
  
-fn main() -> anyhow::Result<()> { 
-    let recipes_dir = std::env::args()
-        .nth(1)
-        .context("Please provide the recipe directory path")?;
+fn main() -> ExitCode {
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or("info")).init();
+
+    match run(Cli::parse()) {
+        Ok(code) => code,
+        Err(err) => {
+            match err.downcast_ref::<InvalidBaseUrl>() {
+                Some(invalid) => eprintln!("error: {}", invalid),
+                None => eprintln!("error: {:#}", err),
+            }
+            ExitCode::FAILURE
+        }
+    }
+}
+
+/// Arguments of `index`, also taken when no subcommand is named
+#[derive(Args)]
+struct IndexArgs {
+    #[command(flatten)]
+    recipes: RecipesDir,
+    /// URL the recipes are served from; without one, recipes are linked
+    /// relative to the output file
+    #[arg(long)]
+    base_url: Option<String>,
+    /// File to write instead of `ingredient-index.<extension>` in the current
+    /// directory, e.g. `ingredient-index.md` for markdown, or `-` for standard
+    /// output
+    #[arg(short, long, value_name = "PATH")]
+    output: Option<PathBuf>,
+    /// Format to write, `html`, `json`, `csv`, `markdown` or `ndjson`,
+    /// optionally followed by `=PATH`
+    /// (`=-` for standard output);
+    /// repeat to write several, e.g. `--format html --format
+    /// json=public/index.json`
+    #[arg(long = "format", value_name = "FORMAT[=PATH]", default_value = "html")]
+    outputs: Vec<Output>,
+    /// Add a footer to the HTML saying when and by which version it was
+    /// generated; set `SOURCE_DATE_EPOCH` to fix the time
+    #[arg(long)]
+    footer: bool,
+}
+
+/// Arguments of `search`
+#[derive(Args)]
+#[command(override_usage = "cooklang-indexer search [OPTIONS] <RECIPES_DIR> <TERM>...\n       \
+                            cooklang-indexer search [OPTIONS] --index <FILE> <TERM>...")]
+struct SearchArgs {
+    /// The recipes directory followed by the ingredients to look for, or only
+    /// the ingredients with `--index`
+    #[arg(value_name = "ARGS", required = true)]
+    args: Vec<String>,
+    /// Search an index written with `--format json` instead of scanning a
+    /// recipes directory
+    #[arg(long, value_name = "FILE")]
+    index: Option<PathBuf>,
+    /// Match ingredients containing a term, e.g. `garlic` finds `garlic powder`
+    #[arg(long, conflicts_with = "fuzzy")]
+    substring: bool,
+    /// Match ingredients, or words of them, one typo away from a term
+    #[arg(long)]
+    fuzzy: bool,
+    /// Print recipes matching any of the terms (the default)
+    #[arg(long, conflicts_with = "all")]
+    any: bool,
+    /// Print only recipes matching all of the terms
+    #[arg(long)]
+    all: bool,
+    /// Print a JSON array of paths instead
+    #[arg(long)]
+    json: bool,
+}
+
+/// Arguments of `shopping-list`
+#[derive(Args)]
+struct ShoppingListArgs {
+    /// Directory holding the recipes
+    recipes_dir: PathBuf,
+    /// Recipes to shop for, relative to the recipes directory
+    #[arg(required = true)]
+    recipes: Vec<PathBuf>,
+    /// Group ingredients by the aisles of this cooklang `aisle.conf` file
+    #[arg(long, value_name = "FILE")]
+    aisle: Option<PathBuf>,
+    /// Leave off the ingredients listed in this file, one per line; `#` starts
+    /// a comment
+    #[arg(long, value_name = "FILE")]
+    staples: Option<PathBuf>,
+    /// Multiply quantities, e.g. `2` to cook twice as much
+    #[arg(long, value_name = "FACTOR", default_value_t = 1.0)]
+    scale: f64,
+    /// How to print the list
+    #[arg(long, value_enum, default_value_t = ListFormat::Text)]
+    format: ListFormat,
+}
+
+/// Arguments of `serve`
+#[cfg(feature = "serve")]
+#[derive(Args)]
+struct ServeArgs {
+    /// Directory holding the recipes
+    #[arg(default_value = ".")]
+    recipes_dir: PathBuf,
+    /// Port to listen on
+    #[arg(long, default_value_t = 8080)]
+    port: u16,
+    /// Address to listen on; `0.0.0.0` makes the recipes reachable from the
+    /// local network
+    #[arg(long, value_name = "ADDRESS", default_value = "127.0.0.1")]
+    bind: std::net::IpAddr,
+    /// Serve changes to the recipes without restarting
+    #[cfg(feature = "watch")]
+    #[arg(long)]
+    watch: bool,
+}
+
+/// The ways `shopping-list` can print the list
+#[derive(Clone, Copy, ValueEnum)]
+enum ListFormat {
+    /// One `- name: amounts` line per ingredient under `[aisle]` headers
+    Text,
+    /// The list as a JSON object
+    Json,
+    /// A cooklang text using each ingredient with its amounts
+    Cooklang,
+}
+
+/// The recipes directory, given as the first argument or with `--dir`
+#[derive(Args)]
+#[group(required = true, multiple = false)]
+struct RecipesDir {
+    /// Directory holding the recipes
+    #[arg(value_name = "RECIPES_DIR")]
+    recipes_dir: Option<PathBuf>,
+    /// Directory holding the recipes, for when it could be mistaken for a
+    /// subcommand
+    #[arg(long, value_name = "RECIPES_DIR")]
+    dir: Option<PathBuf>,
+}
 
-    let base_url = std::env::args()
-        .nth(2)
-        .unwrap_or_else(|| "http://localhost:8080/r".to_string());
+impl RecipesDir {
+    fn path(self) -> PathBuf {
+        // The argument group makes sure exactly one is set
+        self.recipes_dir.or(self.dir).unwrap_or_default()
+    }
+}
 
+fn run(cli: Cli) -> anyhow::Result<ExitCode> {
+    match cli.command.unwrap_or(Command::Index(cli.index)) {
+        Command::Index(args) => run_index(args)?,
+        Command::Query { ingredient, recipes_dir } => run_query(recipes_dir, &ingredient)?,
+        Command::Search(args) => return run_search(args),
+        Command::Stats { recipes_dir, top, json } => run_stats(recipes_dir, top, json)?,
+        Command::ShoppingList(args) => run_shopping_list(args)?,
+        #[cfg(feature = "serve")]
+        Command::Serve(args) => run_serve(args)?,
+        #[cfg(feature = "watch")]
+        Command::Watch(args) => run_watch(args)?,
+        Command::Site { recipes_dir, out, base_url, strict, footer } => {
+            run_site(recipes_dir, out, base_url, strict, footer)?
+        }
+    }
+    Ok(ExitCode::SUCCESS)
+}
+
+/// A file format the index can be written in
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+enum Format {
+    Html,
+    Json,
+    Csv,
+    Markdown,
+    Ndjson,
+}
+
+impl Format {
+    const ALL: [Format; 5] = [Format::Html, Format::Json, Format::Csv, Format::Markdown, Format::Ndjson];
+
+    fn name(self) -> &'static str {
+        match self {
+            Format::Html => "html",
+            Format::Json => "json",
+            Format::Csv => "csv",
+            Format::Markdown => "markdown",
+            Format::Ndjson => "ndjson",
+        }
+    }
+
+    /// Extension of the default output file
+    fn extension(self) -> &'static str {
+        match self {
+            Format::Markdown => "md",
+            format => format.name(),
+        }
+    }
+
+    /// Whether the format links to the recipes, so that `--base-url` matters
+    fn has_links(self) -> bool {
+        matches!(self, Format::Html | Format::Markdown)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Format {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Format::ALL.into_iter().find(|format| format.name() == name).ok_or_else(|| {
+            let names: Vec<_> = Format::ALL.iter().map(|format| format.name()).collect();
+            format!("unknown format {:?}, expected one of: {}", name, names.join(", "))
+        })
+    }
+}
+
+/// One `--format FORMAT[=PATH]` value
+#[derive(Clone, Debug)]
+struct Output {
+    format: Format,
+    path: Option<PathBuf>,
+}
+
+impl FromStr for Output {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.split_once('=') {
+            Some((format, "")) => Err(format!("missing path after {}=", format)),
+            Some((format, path)) => Ok(Output { format: format.parse()?, path: Some(PathBuf::from(path)) }),
+            None => Ok(Output { format: value.parse()?, path: None }),
+        }
+    }
+}
+
+/// `cooklang-indexer [index] <recipes dir> [--base-url <url>] [--output <path>] [--format <format>[=<path>]]... [--footer]`
+fn run_index(args: IndexArgs) -> anyhow::Result<()> {
+    let (recipes_dir, outputs) = plan_outputs(args)?;
     let index = IngredientIndex::new(recipes_dir)?;
 
     // Get all ingredients
     for ingredient in index.ingredients() {
-        println!("Found ingredient: {}", ingredient);
+        log::debug!("Found ingredient: {}", ingredient);
     }
 
-    //create an html version and write it out
-    let html = index.generate_html(&base_url)?;
-    fs::write("ingredient-index.html", html)?;
-    println!("Index generated at: ingredient-index.html");
+    write_outputs(&index, &outputs)
+}
+
+/// The files `index` and `watch` write
+struct Outputs {
+    files: Vec<(Format, PathBuf)>,
+    base_url: Option<String>,
+    footer: bool,
+}
+
+/// Checks the arguments of `index`, returning the recipes directory and the
+/// files to write
+fn plan_outputs(args: IndexArgs) -> anyhow::Result<(PathBuf, Outputs)> {
+    let base_url = args.base_url.map(|base_url| validate_base_url(&base_url, false)).transpose()?;
+    let unnamed = args.outputs.iter().filter(|output| output.path.is_none()).count();
+    if args.output.is_some() && unnamed > 1 {
+        bail!("--output names a single file, but {} formats have no path; give them as --format FORMAT=PATH", unnamed);
+    }
+
+    let outputs: Vec<(Format, PathBuf)> = args
+        .outputs
+        .iter()
+        .map(|output| {
+            let path = match (&output.path, &args.output) {
+                (Some(path), _) | (None, Some(path)) => path.clone(),
+                (None, None) => PathBuf::from(format!("ingredient-index.{}", output.format.extension())),
+            };
+            (output.format, path)
+        })
+        .collect();
+    // Checked before scanning, so mistakes show up at once
+    if outputs.iter().filter(|(_, path)| is_stdout(path)).count() > 1 {
+        bail!("only one output can go to standard output");
+    }
+    for (format, path) in &outputs {
+        if !is_stdout(path) && (path.is_dir() || path.to_string_lossy().ends_with(['/', std::path::MAIN_SEPARATOR])) {
+            bail!(
+                "{} is a directory; give the file to write, e.g. {}",
+                path.display(),
+                path.join(format!("ingredient-index.{}", format.extension())).display()
+            );
+        }
+    }
+    if base_url.is_some() && !outputs.iter().any(|(format, _)| format.has_links()) {
+        bail!("--base-url only applies to the html and markdown formats, which link to the recipes");
+    }
+    Ok((args.recipes.path(), Outputs { files: outputs, base_url, footer: args.footer }))
+}
+
+/// Writes the index in each of the formats asked for
+fn write_outputs(index: &IngredientIndex, outputs: &Outputs) -> anyhow::Result<()> {
+    let base_url = &outputs.base_url;
+    for (format, path) in &outputs.files {
+        let content = match format {
+            Format::Html => {
+                let options = HtmlOptions {
+                    // Without a base URL, link recipes relative to the page
+                    output_dir: Some(output_dir(path)),
+                    base_url: base_url.clone(),
+                    ..html_options(outputs.footer)?
+                };
+                index.generate_html_with(&options)?
+            }
+            Format::Markdown => {
+                let options = HtmlOptions {
+                    output_dir: Some(output_dir(path)),
+                    base_url: base_url.clone(),
+                    ..HtmlOptions::default()
+                };
+                index.generate_markdown(&options)?
+            }
+            Format::Json => index.generate_json()?,
+            Format::Csv => index.generate_csv(),
+            Format::Ndjson => index.generate_ndjson()?,
+        };
+        if is_stdout(path) {
+            write_stdout(content.as_bytes())?;
+            // Logs go to stderr, so this stays out of the pipe
+            log::info!("Index written to standard output");
+            continue;
+        }
+        let dir = output_dir(path);
+        fs::create_dir_all(&dir).with_context(|| format!("failed to create {}", dir.display()))?;
+        // Replaced in one step, so a web server never serves a truncated page
+        write_atomically(path, content)?;
+        log::info!("Index generated at: {}", path.display());
+    }
 
     Ok(())
 }
+
+/// Whether an output path is `-`, standing for standard output
+fn is_stdout(path: &Path) -> bool {
+    path.as_os_str() == "-"
+}
+
+/// Writes to standard output, stopping quietly when the reader has gone away,
+/// e.g. when piped into `head`
+fn write_stdout(bytes: &[u8]) -> anyhow::Result<()> {
+    let mut stdout = io::stdout().lock();
+    match stdout.write_all(bytes).and_then(|()| stdout.flush()) {
+        Err(err) if err.kind() != io::ErrorKind::BrokenPipe => Err(err).context("failed to write to standard output"),
+        _ => Ok(()),
+    }
+}
+
+/// Directory of an output file, `.` for a bare file name or standard output
+fn output_dir(path: &Path) -> PathBuf {
+    match path.parent() {
+        Some(dir) if !dir.as_os_str().is_empty() => dir.to_path_buf(),
+        _ => PathBuf::from("."),
+    }
+}
+
+/// `cooklang-indexer query <ingredient> [<recipes dir>]`
+///
+/// Prints the paths of the recipes using the ingredient, sorted, and fails if
+/// no recipe uses it.
+fn run_query(recipes_dir: PathBuf, ingredient: &str) -> anyhow::Result<()> {
+    let index = IngredientIndex::new(recipes_dir)?;
+    let recipes = index
+        .find_recipes_for_ingredient(ingredient)
+        .with_context(|| format!("no recipe uses {:?}", ingredient))?;
+    for recipe in recipes {
+        println!("{}", recipe.display());
+    }
+    Ok(())
+}
+
+/// `cooklang-indexer search (<recipes dir> | --index <file>) <term>... [--substring | --fuzzy] [--any | --all] [--json]`
+///
+/// Prints the paths of the matching recipes relative to the recipes
+/// directory, sorted, and exits with status 1 if none match.
+fn run_search(args: SearchArgs) -> anyhow::Result<ExitCode> {
+    let (index, terms) = match &args.index {
+        Some(path) => {
+            let json = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+            let index = IngredientIndex::from_json(&json).with_context(|| format!("failed to load {}", path.display()))?;
+            (index, &args.args[..])
+        }
+        None => match &args.args[..] {
+            [recipes_dir, terms @ ..] if !terms.is_empty() => (IngredientIndex::new(recipes_dir)?, terms),
+            _ => bail!("missing the ingredients to search for after the recipes directory"),
+        },
+    };
+
+    let matching = if args.fuzzy {
+        Matching::Fuzzy
+    } else if args.substring {
+        Matching::Substring
+    } else {
+        Matching::Exact
+    };
+    let terms: Vec<&str> = terms.iter().map(String::as_str).collect();
+    let recipes = index.search(&terms, &SearchOptions { matching, all: args.all });
+    let paths: Vec<String> = recipes
+        .iter()
+        .map(|path| relative_path(&index, path))
+        .collect();
+
+    let out = if args.json {
+        serde_json::to_string(&paths)? + "\n"
+    } else {
+        paths.iter().map(|path| format!("{}\n", path)).collect()
+    };
+    write_stdout(out.as_bytes())?;
+    Ok(if paths.is_empty() { ExitCode::FAILURE } else { ExitCode::SUCCESS })
+}
+
+/// A recipe path as shown to the user, relative to the recipes directory
+fn relative_path(index: &IngredientIndex, path: &Path) -> String {
+    path.strip_prefix(index.base_dir()).unwrap_or(path).display().to_string()
+}
+
+/// Output of `stats --json`
+#[derive(Serialize)]
+struct Stats<'a> {
+    recipes: usize,
+    ingredients: usize,
+    average_ingredients_per_recipe: f64,
+    /// Ingredients used by a single recipe
+    single_use_ingredients: usize,
+    top_ingredients: Vec<RankedIngredient<'a>>,
+    largest_recipe: Option<RecipeSize>,
+    smallest_recipe: Option<RecipeSize>,
+}
+
+#[derive(Serialize)]
+struct RankedIngredient<'a> {
+    name: &'a str,
+    recipes: usize,
+}
+
+#[derive(Serialize)]
+struct RecipeSize {
+    path: String,
+    ingredients: usize,
+}
+
+/// `cooklang-indexer stats [<recipes dir>] [--top <n>] [--json]`
+///
+/// Prints an overview of the collection: totals, the most used ingredients and
+/// the recipes with the most and the fewest ingredients.
+fn run_stats(recipes_dir: PathBuf, top: usize, json: bool) -> anyhow::Result<()> {
+    let index = IngredientIndex::new(recipes_dir)?;
+    let recipes = index.recipes_by_complexity();
+    let ranked = index.ranked_ingredients(0, usize::MAX);
+    let uses: usize = recipes.iter().map(|&(_, count)| count).sum();
+    let average = if recipes.is_empty() { 0.0 } else { uses as f64 / recipes.len() as f64 };
+    let single_use = ranked.iter().filter(|&&(_, count)| count == 1).count();
+    let largest = recipes.first();
+    // Ties are ordered by path, so take the first of the smallest
+    let smallest = recipes.last().and_then(|&(_, fewest)| recipes.iter().find(|&&(_, count)| count == fewest));
+    let top = &ranked[..top.min(ranked.len())];
+
+    if json {
+        let recipe = |recipe: Option<&(&PathBuf, usize)>| {
+            recipe.map(|&(path, count)| RecipeSize { path: relative_path(&index, path), ingredients: count })
+        };
+        let stats = Stats {
+            recipes: recipes.len(),
+            ingredients: ranked.len(),
+            average_ingredients_per_recipe: average,
+            single_use_ingredients: single_use,
+            top_ingredients: top.iter().map(|&(name, count)| RankedIngredient { name, recipes: count }).collect(),
+            largest_recipe: recipe(largest),
+            smallest_recipe: recipe(smallest),
+        };
+        return write_stdout(format!("{}\n", serde_json::to_string_pretty(&stats)?).as_bytes());
+    }
+
+    let recipe = |recipe: Option<&(&PathBuf, usize)>| match recipe {
+        Some(&(path, 1)) => format!("{} (1 ingredient)", relative_path(&index, path)),
+        Some(&(path, count)) => format!("{} ({} ingredients)", relative_path(&index, path), count),
+        None => "-".to_string(),
+    };
+    let mut out = String::new();
+    for (label, value) in [
+        ("Recipes", recipes.len().to_string()),
+        ("Ingredients", ranked.len().to_string()),
+        ("Ingredients per recipe", format!("{:.1}", average)),
+        ("Used by one recipe", single_use.to_string()),
+        ("Largest recipe", recipe(largest)),
+        ("Smallest recipe", recipe(smallest)),
+    ] {
+        out.push_str(&format!("{:<24}{}\n", format!("{}:", label), value));
+    }
+    if !top.is_empty() {
+        out.push_str(&format!("\nTop {} ingredients:\n", top.len()));
+        let name_width = top.iter().map(|(name, _)| name.chars().count()).max().unwrap_or(0);
+        let rank_width = top.len().to_string().len();
+        let count_width = top[0].1.to_string().len();
+        for (rank, (name, count)) in top.iter().enumerate() {
+            out.push_str(&format!(
+                "{:>rank_width$}. {:<name_width$}  {:>count_width$}\n",
+                rank + 1,
+                name,
+                count,
+            ));
+        }
+    }
+    write_stdout(out.as_bytes())
+}
+
+/// `cooklang-indexer shopping-list <recipes dir> <recipe>... [--aisle <file>] [--staples <file>] [--scale <factor>] [--format <format>]`
+///
+/// Prints the shopping list, failing on recipes missing from the index rather
+/// than leaving them off.
+fn run_shopping_list(args: ShoppingListArgs) -> anyhow::Result<()> {
+    if !(args.scale.is_finite() && args.scale > 0.0) {
+        bail!("--scale must be a positive number, not {}", args.scale);
+    }
+    let mut builder = IngredientIndex::builder(&args.recipes_dir);
+    if let Some(path) = &args.aisle {
+        let conf = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+        builder = builder.aisles(Aisles::parse(&conf));
+    }
+    let staples = match &args.staples {
+        Some(path) => {
+            let staples = fs::read_to_string(path).with_context(|| format!("failed to read {}", path.display()))?;
+            staples
+                .lines()
+                .map(|line| line.split('#').next().unwrap_or_default().trim())
+                .filter(|line| !line.is_empty())
+                .map(str::to_string)
+                .collect()
+        }
+        None => Vec::new(),
+    };
+    let index = builder.build()?;
+
+    let mut recipes = Vec::with_capacity(args.recipes.len());
+    let mut unknown = Vec::new();
+    for wanted in &args.recipes {
+        let path = args.recipes_dir.join(wanted);
+        if index.recipes().iter().any(|recipe| recipe.path == path) {
+            recipes.push(path);
+            continue;
+        }
+        let mut message = format!("no recipe {} in {}", wanted.display(), args.recipes_dir.display());
+        let similar = similar_recipes(&index, wanted);
+        if !similar.is_empty() {
+            message.push_str(&format!(", did you mean {}?", similar.join(" or ")));
+        }
+        unknown.push(message);
+    }
+    if !unknown.is_empty() {
+        bail!("{}", unknown.join("\n"));
+    }
+
+    let list = index.shopping_list_with(&recipes, &ShoppingOptions { scale: args.scale, staples });
+    let out = match args.format {
+        ListFormat::Text => list.to_string(),
+        ListFormat::Json => serde_json::to_string_pretty(&list)? + "\n",
+        ListFormat::Cooklang => list.to_cooklang(),
+    };
+    write_stdout(out.as_bytes())
+}
+
+/// Up to three recipes whose file name looks like the one of `wanted`,
+/// closest first
+fn similar_recipes(index: &IngredientIndex, wanted: &Path) -> Vec<String> {
+    let stem = |path: &Path| path.file_stem().map(|stem| stem.to_string_lossy().to_lowercase()).unwrap_or_default();
+    let wanted = stem(wanted);
+    let mut similar: Vec<(usize, String)> = index
+        .recipes()
+        .iter()
+        .filter_map(|recipe| {
+            let name = stem(&recipe.path);
+            let distance = edit_distance(&name, &wanted);
+            let close = distance <= (wanted.chars().count() / 3).max(1);
+            (close || name.contains(&wanted) || wanted.contains(&name))
+                .then(|| (distance, relative_path(index, &recipe.path)))
+        })
+        .collect();
+    similar.sort();
+    similar.into_iter().take(3).map(|(_, path)| path).collect()
+}
+
+/// Number of inserted, deleted or replaced characters turning `a` into `b`
+fn edit_distance(a: &str, b: &str) -> usize {
+    let b: Vec<char> = b.chars().collect();
+    let mut row: Vec<usize> = (0..=b.len()).collect();
+    for (i, x) in a.chars().enumerate() {
+        let mut diagonal = row[0];
+        row[0] = i + 1;
+        for (j, &y) in b.iter().enumerate() {
+            let replaced = diagonal + usize::from(x != y);
+            diagonal = row[j + 1];
+            row[j + 1] = replaced.min(row[j] + 1).min(diagonal + 1);
+        }
+    }
+    row[b.len()]
+}
+
+/// `cooklang-indexer serve [<recipes dir>] [--port <port>] [--bind <address>]`
+///
+/// Serves until interrupted, logging every request.
+#[cfg(feature = "serve")]
+fn run_serve(args: ServeArgs) -> anyhow::Result<()> {
+    let addr = std::net::SocketAddr::new(args.bind, args.port);
+    #[cfg(feature = "watch")]
+    // Watching before the first scan, so no change goes unnoticed
+    let watcher = args.watch.then(|| RecipeWatcher::new(&args.recipes_dir)).transpose()?;
+    let index = IngredientIndex::new(&args.recipes_dir)?;
+    let server = cooklang_indexer::RecipeServer::bind(index, addr, &html_options(false)?)?;
+    log::info!("serving {} at http://{}/", args.recipes_dir.display(), server.local_addr().unwrap_or(addr));
+
+    #[cfg(feature = "watch")]
+    if let Some(watcher) = watcher {
+        stop_on_ctrl_c(&watcher)?;
+        return std::thread::scope(|scope| {
+            let serving = scope.spawn(|| server.run());
+            while let Some(paths) = watcher.wait() {
+                let started = Instant::now();
+                match server.refresh_files(&paths) {
+                    Ok(report) if !report.is_empty() => {
+                        println!("{}", refresh_summary(&args.recipes_dir, &report, started));
+                    }
+                    Ok(_) => {}
+                    Err(err) => log::error!("failed to refresh the index: {:#}", err),
+                }
+            }
+            server.unblock();
+            let _ = serving.join();
+            Ok(())
+        });
+    }
+    server.run();
+    Ok(())
+}
+
+/// `cooklang-indexer watch <recipes dir> [--base-url <url>] [--output <path>] [--format <format>[=<path>]]... [--footer]`
+///
+/// Writes the index, then again after every burst of changes to the recipes,
+/// printing what changed, until interrupted.
+#[cfg(feature = "watch")]
+fn run_watch(args: IndexArgs) -> anyhow::Result<()> {
+    let (recipes_dir, outputs) = plan_outputs(args)?;
+    if outputs.files.iter().any(|(_, path)| is_stdout(path)) {
+        bail!("watch keeps rewriting its outputs; give files to write instead of -");
+    }
+    // Watching before the first scan, so no change goes unnoticed
+    let watcher = RecipeWatcher::new(&recipes_dir)?;
+    let mut index = IngredientIndex::new(&recipes_dir)?;
+    write_outputs(&index, &outputs)?;
+    stop_on_ctrl_c(&watcher)?;
+    log::info!("watching {} for changes, press Ctrl-C to stop", recipes_dir.display());
+
+    while let Some(paths) = watcher.wait() {
+        let started = Instant::now();
+        let report = index.refresh_files(&paths);
+        if report.is_empty() {
+            continue;
+        }
+        // A failed write is retried on the next change rather than ending the watch
+        match write_outputs(&index, &outputs) {
+            Ok(()) => println!("{}", refresh_summary(&recipes_dir, &report, started)),
+            Err(err) => log::error!("{:#}", err),
+        }
+    }
+    log::info!("stopped watching {}", recipes_dir.display());
+    Ok(())
+}
+
+/// Makes Ctrl-C stop the watcher, and with it the command
+#[cfg(feature = "watch")]
+fn stop_on_ctrl_c(watcher: &RecipeWatcher) -> anyhow::Result<()> {
+    let stopper = watcher.stopper();
+    ctrlc::set_handler(move || stopper.stop()).context("failed to handle Ctrl-C")
+}
+
+/// One line telling what a refresh changed and how long it took, e.g.
+/// `updated mains/curry.cook, removed soups/old.cook in 12ms`
+///
+/// Why recipes could not be read is logged separately.
+#[cfg(feature = "watch")]
+fn refresh_summary(recipes_dir: &Path, report: &RefreshReport, started: Instant) -> String {
+    let failed: Vec<PathBuf> = report.failed.iter().map(|(path, _)| path.clone()).collect();
+    for (_, err) in &report.failed {
+        log::warn!("{}", err);
+    }
+    let parts: Vec<String> = [("updated", &report.updated), ("removed", &report.removed), ("failed to read", &failed)]
+        .into_iter()
+        .filter(|(_, paths)| !paths.is_empty())
+        .map(|(verb, paths)| match paths.len() {
+            1..=3 => {
+                let names: Vec<String> = paths
+                    .iter()
+                    .map(|path| path.strip_prefix(recipes_dir).unwrap_or(path).display().to_string())
+                    .collect();
+                format!("{} {}", verb, names.join(", "))
+            }
+            count => format!("{} {} recipes", verb, count),
+        })
+        .collect();
+    format!("{} in {}ms", parts.join(", "), started.elapsed().as_millis())
+}
+
+/// `cooklang-indexer site [<recipes dir>] --out <dir> [--base-url <url>] [--strict] [--footer]`
+///
+/// Writes the whole static site and prints what was done.
+fn run_site(
+    recipes_dir: PathBuf,
+    out: PathBuf,
+    base_url: Option<String>,
+    strict: bool,
+    footer: bool,
+) -> anyhow::Result<()> {
+    let base_url = base_url.map(|base_url| validate_base_url(&base_url, false)).transpose()?;
+
+    // With --strict, malformed recipes fail the run as well as unlinkable ones
+    let index = IngredientIndex::builder(recipes_dir).strict(strict).build()?;
+    let options = HtmlOptions { base_url, ..html_options(footer)? };
+    let manifest = index.generate_site(&out, &options)?;
+
+    println!(
+        "wrote {} files to {} ({} recipes, {} ingredients)",
+        manifest.files().count(),
+        out.display(),
+        manifest.recipe_pages.len(),
+        manifest.ingredient_pages.len()
+    );
+    if !manifest.removed.is_empty() {
+        println!("removed {} stale pages", manifest.removed.len());
+    }
+    println!("{} warnings", manifest.warnings.len());
+    for warning in &manifest.warnings {
+        println!("  {}", warning);
+    }
+    if strict && !manifest.warnings.is_empty() {
+        bail!("{} warnings with --strict", manifest.warnings.len());
+    }
+    Ok(())
+}
+
+/// Options shared by every command: with `footer`, a footer saying when the
+/// pages were generated, fixed by `SOURCE_DATE_EPOCH` for reproducible builds
+///
+/// The footer is opt-in so that running the same command twice writes the
+/// same bytes.
+fn html_options(footer: bool) -> anyhow::Result<HtmlOptions> {
+    if !footer {
+        return Ok(HtmlOptions::default());
+    }
+    let generated_at = match std::env::var("SOURCE_DATE_EPOCH") {
+        Ok(epoch) => {
+            let secs = epoch.trim().parse().context("SOURCE_DATE_EPOCH must be a number of seconds")?;
+            Some(UNIX_EPOCH + Duration::from_secs(secs))
+        }
+        Err(_) => None,
+    };
+    Ok(HtmlOptions { footer: Footer::Auto, generated_at, ..HtmlOptions::default() })
+}
@@ -0,0 +1,66 @@
+// File: src/markdown.rs
+
+//! Markdown export of the index, for wikis and note-taking apps
+
+use crate::html::{collect_entries, resolve_options};
+use crate::{HtmlOptions, IngredientIndex};
+use anyhow::Result;
+
+impl IngredientIndex {
+    /// Generates the index as a Markdown document
+    ///
+    /// The document has the same ingredients, order and recipe links as
+    /// [`generate_html_with`](Self::generate_html_with) produces for `options`:
+    /// a level-one heading with the title, then a level-two heading per
+    /// ingredient over a list of links to its recipes. Options only affecting
+    /// the look of the HTML page are ignored.
+    ///
+    /// # Arguments
+    /// * `options` - Options controlling the links, order and title
+    ///
+    /// # Returns
+    /// * `Result<String>` - The Markdown document
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let markdown = index.generate_markdown(&HtmlOptions::default()).unwrap();
+    /// std::fs::write("ingredients.md", markdown).unwrap();
+    /// ```
+    pub fn generate_markdown(&self, options: &HtmlOptions) -> Result<String> {
+        let options = resolve_options(self, options)?;
+        let (entries, _) = collect_entries(self, &options);
+
+        let heading = options.heading.as_deref().unwrap_or(&options.title);
+        let mut markdown = format!("# {}\n", escape_markdown(heading));
+        for entry in &entries {
+            markdown.push_str(&format!("\n## {}\n\n", escape_markdown(&entry.name)));
+            for recipe in &entry.recipes {
+                markdown.push_str(&format!("- [{}]({})\n", escape_markdown(&recipe.name), link_destination(&recipe.url)));
+            }
+        }
+        Ok(markdown)
+    }
+}
+
+/// Backslash-escapes the characters Markdown could read as formatting
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '|') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// A link destination, in angle brackets when it has spaces or parentheses
+fn link_destination(url: &str) -> String {
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
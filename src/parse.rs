@@ -0,0 +1,384 @@
+// File: src/parse.rs
+
+//! Extraction of ingredients and sections from cooklang source
+
+use crate::{IndexOptions, Ingredient, IngredientAmount, Modifier, Recipe, Section, Snippet};
+use regex::Regex;
+use std::collections::BTreeMap;
+use std::ops::Range;
+use std::path::Path;
+use std::sync::OnceLock;
+
+/// Parses a recipe's content into its ingredients and sections
+pub(crate) fn parse_recipe(path: &Path, content: &str, options: &IndexOptions) -> Recipe {
+    let mut ingredients: Vec<Ingredient> = Vec::new();
+    let mut snippets = Vec::new();
+    let mut sections = vec![Section { name: String::new(), ingredients: Vec::new() }];
+    let mut title = None;
+    let mut language = None;
+    let mut tags: Vec<String> = Vec::new();
+    let mut metadata = BTreeMap::new();
+    let mut references = Vec::new();
+
+    for line in content.lines() {
+        if let Some((key, value)) = metadata_line(line) {
+            metadata.insert(key.to_lowercase(), value.to_string());
+            if key.eq_ignore_ascii_case("title") && !value.is_empty() {
+                title = Some(value.to_string());
+            } else if key.eq_ignore_ascii_case("language") && !value.is_empty() {
+                language = Some(value.to_lowercase());
+            } else if key.eq_ignore_ascii_case("tags") {
+                for tag in value.split(',').map(str::trim).filter(|tag| !tag.is_empty()) {
+                    if !tags.iter().any(|known| known.to_lowercase() == tag.to_lowercase()) {
+                        tags.push(tag.to_string());
+                    }
+                }
+            }
+            continue;
+        }
+        if let Some(name) = section_header(line) {
+            sections.push(Section { name, ingredients: Vec::new() });
+            continue;
+        }
+        references.extend(scan_line(line).into_iter().filter_map(|token| match token {
+            Token::Ingredient { name, .. } => recipe_reference(name).map(str::to_string),
+            Token::BareAt => None,
+        }));
+        for mention in parse_ingredients(line, options) {
+            if !options.low_memory {
+                snippets.push(snippet(line, &mention));
+                sections.last_mut().unwrap().ingredients.push(mention.key.clone());
+            }
+            ingredients.push(Ingredient {
+                name: mention.key,
+                quantity: Some(mention.amount).filter(|amount| *amount != IngredientAmount::default()),
+                note: mention.note.map(str::to_string),
+                modifier: mention.modifier,
+            });
+        }
+    }
+
+    // Drop the implicit leading section when the recipe starts with a header
+    if sections.len() > 1 && sections[0].ingredients.is_empty() {
+        sections.remove(0);
+    }
+    if options.low_memory {
+        sections.clear();
+        ingredients.shrink_to_fit();
+    }
+
+    Recipe {
+        path: path.to_owned(),
+        title,
+        language,
+        modified: None,
+        tags,
+        metadata,
+        ingredients,
+        snippets,
+        sections,
+        references,
+    }
+}
+
+/// Returns the key and value of a `>> key: value` metadata line
+fn metadata_line(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.trim().strip_prefix(">>")?.split_once(':')?;
+    Some((key.trim(), value.trim()))
+}
+
+/// Returns the section name if the line is a `= Section` (or `== Section ==`) header
+fn section_header(line: &str) -> Option<String> {
+    let line = line.trim();
+    if !line.starts_with('=') {
+        return None;
+    }
+    Some(line.trim_matches('=').trim().to_string())
+}
+
+/// Whether the start of a file looks like cooklang: text with an ingredient,
+/// cookware with braces, a timer or a metadata line
+///
+/// `@` must start a word, so that e-mail addresses do not count, and `#`
+/// needs braces, so that Markdown headings and hashtags do not either.
+pub(crate) fn looks_like_cooklang(head: &[u8]) -> bool {
+    static MARKER: OnceLock<Regex> = OnceLock::new();
+    let marker = MARKER.get_or_init(|| {
+        Regex::new(r"(?m)(?:^|[^\w])@[^\s@]|#[^\s#@~{][^#@~{\n]*\{[^}\n]*\}|~[^#@~{\n]*\{[^}\n]+\}|^\s*>>\s*[^:\n]+:").unwrap()
+    });
+    let head = String::from_utf8_lossy(head);
+    !head.contains('\0') && marker.is_match(&head)
+}
+
+/// Lists the anomalies the lenient parser silently skips, for strict mode
+///
+/// Reports `@` markers without a name, quantities missing their closing `}`
+/// and names that normalize to nothing, each prefixed with its line number.
+pub(crate) fn recipe_problems(content: &str, options: &IndexOptions) -> Vec<String> {
+    let mut problems = Vec::new();
+    for (number, line) in content.lines().enumerate() {
+        let number = number + 1;
+        for token in scan_line(line) {
+            match token {
+                Token::BareAt => problems.push(format!("line {}: `@` without an ingredient name", number)),
+                Token::Ingredient { name, unclosed, .. } => {
+                    if options.key(name).trim().is_empty() {
+                        problems.push(format!("line {}: empty ingredient name", number));
+                    }
+                    if unclosed {
+                        problems.push(format!("line {}: unclosed `{{` after @{}", number, name.trim()));
+                    }
+                }
+            }
+        }
+    }
+    problems
+}
+
+/// An `@` marker found while scanning a line
+#[derive(Debug)]
+enum Token<'a> {
+    /// An ingredient reference with its raw name and the contents of its braces
+    Ingredient {
+        name: &'a str,
+        /// Where the whole reference is in the line, from the `@` on
+        span: Range<usize>,
+        braces: Option<&'a str>,
+        /// Contents of the `(preparation)` note after the braces, trimmed
+        note: Option<&'a str>,
+        /// Whether the braces were opened but never closed on the line
+        unclosed: bool,
+    },
+    /// An `@` not followed by a name
+    BareAt,
+}
+
+/// Finds the ingredient references in one line of recipe text
+///
+/// A name runs up to a `{`, another `@` or the end of the line. Braces are
+/// matched with nesting, so `@x{a{b}c}` has the amount `a{b}c`. Anything
+/// directly after the closing brace that belongs to the reference, i.e. extra
+/// brace groups as in `@x{}{}` and a `(preparation)` note as in
+/// `@cheese{1%cup}(grated)`, is skipped, and so is any `@` inside it.
+fn scan_line(line: &str) -> Vec<Token<'_>> {
+    let bytes = line.as_bytes();
+    let mut tokens = Vec::new();
+    let mut pos = 0;
+    while let Some(offset) = line[pos..].find('@') {
+        let at = pos + offset;
+        let start = at + 1;
+        // `\r` ends names too, so stray carriage returns don't leak into them
+        let end = line[start..].find(['{', '@', '\r']).map_or(line.len(), |offset| start + offset);
+        let name = &line[start..end];
+        pos = end;
+        if name.is_empty() {
+            tokens.push(Token::BareAt);
+            continue;
+        }
+
+        let mut braces = None;
+        let mut note = None;
+        let mut unclosed = false;
+        if bytes.get(end) == Some(&b'{') {
+            match closing(line, end, b'{', b'}') {
+                Some(close) => {
+                    braces = Some(&line[end + 1..close]);
+                    pos = close + 1;
+                    while bytes.get(pos) == Some(&b'{') {
+                        match closing(line, pos, b'{', b'}') {
+                            Some(close) => pos = close + 1,
+                            None => break,
+                        }
+                    }
+                    if bytes.get(pos) == Some(&b'(') {
+                        if let Some(close) = closing(line, pos, b'(', b')') {
+                            note = Some(line[pos + 1..close].trim()).filter(|note| !note.is_empty());
+                            pos = close + 1;
+                        }
+                    }
+                }
+                None => unclosed = true,
+            }
+        }
+        tokens.push(Token::Ingredient { name, span: at..pos, unclosed, braces, note });
+    }
+    tokens
+}
+
+/// Position of the delimiter closing the one opened at `open_pos`, honoring nesting
+fn closing(line: &str, open_pos: usize, open: u8, close: u8) -> Option<usize> {
+    let mut depth = 0;
+    for (pos, &byte) in line.as_bytes().iter().enumerate().skip(open_pos) {
+        if byte == open {
+            depth += 1;
+        } else if byte == close {
+            depth -= 1;
+            if depth == 0 {
+                return Some(pos);
+            }
+        }
+    }
+    None
+}
+
+/// One use of an ingredient in a line of recipe text
+struct Mention<'a> {
+    /// Normalized name the ingredient is indexed under
+    key: String,
+    /// Amount given in the braces
+    amount: IngredientAmount,
+    /// The `(preparation)` note after the braces
+    note: Option<&'a str>,
+    /// The marker between `@` and the name
+    modifier: Modifier,
+    /// Name as written
+    name: &'a str,
+    /// Where the whole reference is in the line
+    span: Range<usize>,
+}
+
+/// Extracts the ingredients used in one line of recipe text
+fn parse_ingredients<'a>(line: &'a str, options: &IndexOptions) -> Vec<Mention<'a>> {
+    scan_line(line)
+        .into_iter()
+        .flat_map(|token| match token {
+            Token::Ingredient { name, .. } if recipe_reference(name).is_some() => Vec::new(),
+            Token::Ingredient { name, braces, note, span, .. } => {
+                let (modifier, name) = Modifier::split(name);
+                let names: Vec<&str> = if options.split_on_comma { name.split(',').collect() } else { vec![name] };
+                let last = names.len() - 1;
+                let amount = braces.map_or_else(IngredientAmount::default, parse_amount);
+                names
+                    .into_iter()
+                    .enumerate()
+                    .map(|(i, name)| {
+                        // The braces and the note follow the last name
+                        let (amount, note) =
+                            if i == last { (amount.clone(), note) } else { (IngredientAmount::default(), None) };
+                        Mention { key: options.key(name), amount, note, modifier, name, span: span.clone() }
+                    })
+                    .collect()
+            }
+            Token::BareAt => Vec::new(),
+        })
+        // Blank names, e.g. from `@{2%cups}` or a normalizer that does not trim,
+        // are never indexed, whatever the minimum length
+        .filter(|mention| {
+            !mention.key.trim().is_empty()
+                && mention.key.chars().count() >= options.min_ingredient_len
+                && !options.is_excluded(&mention.key)
+        })
+        .collect()
+}
+
+/// Longest snippet kept around a mention, in characters
+const SNIPPET_LEN: usize = 120;
+
+/// The sentence around a mention as plain text, shortened to about
+/// [`SNIPPET_LEN`] characters around the mention
+fn snippet(line: &str, mention: &Mention) -> Snippet {
+    static SENTENCE_END: OnceLock<Regex> = OnceLock::new();
+    let sentence_end = SENTENCE_END.get_or_init(|| Regex::new(r"[.!?](\s|$)").unwrap());
+
+    let before = plain_text(&line[..mention.span.start]);
+    let after = plain_text(&line[mention.span.end..]);
+    let before = match sentence_end.find_iter(&before).last() {
+        Some(end) => &before[end.end()..],
+        None => before.trim_start(),
+    };
+    let after = match sentence_end.find(&after) {
+        Some(end) => after[..end.end()].trim_end(),
+        None => after.trim_end(),
+    };
+
+    let mention = mention.name.trim();
+    let budget = SNIPPET_LEN.saturating_sub(mention.chars().count());
+    let before_budget = (budget / 2).max(budget.saturating_sub(after.chars().count()));
+    let before = shorten_start(before, before_budget);
+    let after = shorten_end(after, budget.saturating_sub(before.chars().count()));
+    Snippet { before, mention: mention.to_string(), after }
+}
+
+/// Recipe text with its markup removed: ingredients and cookware become their
+/// names and timers their duration, with runs of whitespace collapsed
+fn plain_text(text: &str) -> String {
+    static MARKUP: OnceLock<Regex> = OnceLock::new();
+    let markup = MARKUP.get_or_init(|| {
+        Regex::new(r"#([^#@~{]+)\{[^}]*\}|#([^\s#@~{]+)|~[^#@~{]*\{([^}]*)\}").unwrap()
+    });
+
+    let mut plain = String::with_capacity(text.len());
+    let mut pos = 0;
+    for token in scan_line(text) {
+        if let Token::Ingredient { name, span, .. } = token {
+            plain.push_str(&text[pos..span.start]);
+            plain.push_str(Modifier::split(name).1.trim());
+            pos = span.end;
+        }
+    }
+    plain.push_str(&text[pos..]);
+    let plain = markup.replace_all(&plain, |cap: &regex::Captures| {
+        match (cap.get(1).or(cap.get(2)), cap.get(3)) {
+            (Some(name), _) => name.as_str().trim().to_string(),
+            (None, Some(duration)) => duration.as_str().replace('%', " ").trim().to_string(),
+            (None, None) => String::new(),
+        }
+    });
+    let mut collapsed = String::with_capacity(plain.len());
+    for c in plain.chars() {
+        if !c.is_whitespace() {
+            collapsed.push(c);
+        } else if !collapsed.ends_with(' ') {
+            collapsed.push(' ');
+        }
+    }
+    collapsed
+}
+
+/// Keeps at most `max` characters of the end of `text`, starting at a word
+/// and marking the cut with `…`
+fn shorten_start(text: &str, max: usize) -> String {
+    let len = text.chars().count();
+    if len <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().skip(len - max.saturating_sub(1)).collect();
+    let kept = kept.split_once(' ').map_or("", |(_, rest)| rest);
+    format!("…{}", kept)
+}
+
+/// Keeps at most `max` characters of the start of `text`, ending at a word
+/// and marking the cut with `…`
+fn shorten_end(text: &str, max: usize) -> String {
+    if text.chars().count() <= max {
+        return text.to_string();
+    }
+    let kept: String = text.chars().take(max.saturating_sub(1)).collect();
+    let kept = kept.rsplit_once(' ').map_or("", |(rest, _)| rest);
+    format!("{}…", kept)
+}
+
+/// The trimmed reference if an ingredient name points at another recipe,
+/// either by path as in `@./base-sauce{}` or by name as in `@recipe:base sauce`
+fn recipe_reference(name: &str) -> Option<&str> {
+    let name = name.trim();
+    (name.starts_with("./") || name.starts_with("../") || name.starts_with("recipe:")).then_some(name)
+}
+
+/// Splits the contents of an ingredient's braces into quantity and unit
+///
+/// Without a `%`, a unit glued to a number as in `200ml` is split off as well.
+fn parse_amount(braces: &str) -> IngredientAmount {
+    static GLUED_UNIT_REGEX: OnceLock<Regex> = OnceLock::new();
+    let glued_unit_regex = GLUED_UNIT_REGEX
+        .get_or_init(|| Regex::new(r"^(\d+(?:[.,]\d+)?|\d+/\d+)(\p{L}+\.?)$").unwrap());
+
+    let non_empty = |text: &str| Some(text.trim().to_string()).filter(|text| !text.is_empty());
+    if let Some((quantity, unit)) = braces.split_once('%') {
+        return IngredientAmount { quantity: non_empty(quantity), unit: non_empty(unit) };
+    }
+    match glued_unit_regex.captures(braces.trim()) {
+        Some(cap) => IngredientAmount { quantity: Some(cap[1].to_string()), unit: Some(cap[2].to_string()) },
+        None => IngredientAmount { quantity: non_empty(braces), unit: None },
+    }
+}
@@ -0,0 +1,110 @@
+// File: src/search.rs
+
+//! Looking up recipes by loosely matched ingredient names
+
+use crate::spelling::within_one_edit;
+use crate::IngredientIndex;
+use std::collections::BTreeSet;
+use std::path::PathBuf;
+
+/// How a search term is compared with the indexed ingredient names
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Matching {
+    /// The name the term is indexed under, e.g. `Garlic` finds `garlic`
+    #[default]
+    Exact,
+    /// Names starting with the term, e.g. `gar` finds `garlic` and `garlic powder`
+    Prefix,
+    /// Names containing the term, e.g. `garlic` finds `garlic powder`
+    Substring,
+    /// Names, or single words of names, at most one typo away from the term,
+    /// e.g. `garlc` finds `garlic` and `garlic powder`
+    Fuzzy,
+}
+
+/// Options for [`IngredientIndex::search`]
+#[derive(Debug, Clone, Default)]
+pub struct SearchOptions {
+    /// How each term is matched
+    pub matching: Matching,
+    /// Keep only recipes matching every term rather than any of them
+    pub all: bool,
+}
+
+impl IngredientIndex {
+    /// Finds the ingredients a search term matches
+    ///
+    /// The term is normalized like the indexed names first.
+    ///
+    /// # Arguments
+    /// * `term` - Ingredient name, or part of one, to look for
+    /// * `matching` - How the term is compared with the names
+    ///
+    /// # Returns
+    /// * `Vec<&String>` - The matching names, sorted
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{IngredientIndex, Matching};
+    ///
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// println!("{:?}", index.matching_ingredients("pepper", Matching::Substring));
+    /// ```
+    pub fn matching_ingredients(&self, term: &str, matching: Matching) -> Vec<&String> {
+        let term = self.options.key(term);
+        let mut names: Vec<&String> = match matching {
+            Matching::Exact => self.index.get_key_value(&term).map(|(name, _)| name).into_iter().collect(),
+            Matching::Prefix => self.index.keys().filter(|name| name.starts_with(&term)).collect(),
+            Matching::Substring => self.index.keys().filter(|name| name.contains(&term)).collect(),
+            Matching::Fuzzy => self
+                .index
+                .keys()
+                .filter(|name| {
+                    within_one_edit(name, &term) || name.split_whitespace().any(|word| within_one_edit(word, &term))
+                })
+                .collect(),
+        };
+        names.sort();
+        names
+    }
+
+    /// Finds the recipes using ingredients that match search terms
+    ///
+    /// A recipe matches a term when it uses any of the ingredients
+    /// [`matching_ingredients`](Self::matching_ingredients) returns for it.
+    ///
+    /// # Arguments
+    /// * `terms` - Ingredient names, or parts of them, to look for
+    /// * `options` - How terms are matched and combined
+    ///
+    /// # Returns
+    /// * `Vec<&PathBuf>` - Paths of the matching recipes, sorted; empty without terms
+    ///
+    /// # Example
+    /// ```no_run
+    /// use cooklang_indexer::{IngredientIndex, Matching, SearchOptions};
+    ///
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = SearchOptions { matching: Matching::Fuzzy, all: true };
+    /// for path in index.search(&["garlic", "ginger"], &options) {
+    ///     println!("{}", path.display());
+    /// }
+    /// ```
+    pub fn search(&self, terms: &[&str], options: &SearchOptions) -> Vec<&PathBuf> {
+        let mut found: Option<BTreeSet<&PathBuf>> = None;
+        for term in terms {
+            let recipes: BTreeSet<&PathBuf> = self
+                .matching_ingredients(term, options.matching)
+                .into_iter()
+                .flat_map(|name| self.index[name].iter())
+                .filter_map(|path| self.recipe(path).map(|recipe| &recipe.path))
+                .collect();
+            found = Some(match found {
+                None => recipes,
+                Some(found) if options.all => found.intersection(&recipes).copied().collect(),
+                Some(found) => found.union(&recipes).copied().collect(),
+            });
+        }
+        found.unwrap_or_default().into_iter().collect()
+    }
+}
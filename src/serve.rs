@@ -0,0 +1,391 @@
+// File: src/serve.rs
+
+//! A small HTTP server for browsing a recipe collection through its index
+
+use crate::html::recipe_name;
+use crate::url::export_path;
+use crate::{
+    relative_url, HtmlOptions, IngredientIndex, Matching, RefreshReport, SearchOptions, UrlMapper, UrlOptions,
+};
+use anyhow::{anyhow, Context, Result};
+use serde::Serialize;
+use std::collections::HashSet;
+use std::fs::{self, File};
+use std::net::{SocketAddr, ToSocketAddrs};
+use std::path::{Component, Path, PathBuf};
+use std::sync::{PoisonError, RwLock, RwLockReadGuard};
+use tiny_http::{Header, Method, Request, Response, ResponseBox, Server};
+
+/// URL path the recipe files are served under
+const RECIPES_PATH: &str = "/r/";
+
+/// Serves the HTML index at `/`, the JSON export at `/index.json`, the files
+/// of the recipes directory under `/r/`, which is where the index links, and
+/// a JSON query API under `/api/`
+///
+/// The API answers:
+/// * `GET /api/ingredients` - every ingredient as `{name, recipes}`, with the
+///   number of recipes using it, in index order
+/// * `GET /api/ingredients/{name}/recipes` - `{ingredient, recipes}` with each
+///   recipe as `{path, title, url}`
+/// * `GET /api/search?q=…&mode=exact|prefix|substring|fuzzy&all=true` -
+///   `{query, mode, ingredients, recipes}`, `q` being repeatable and `mode`
+///   `exact` by default
+/// * `GET /api/recipes/{path}/ingredients` - `{path, title, ingredients}` with
+///   the recipe's distinct ingredients in order of first use
+///
+/// Unknown ingredients and recipes get `404 Not Found` and malformed queries
+/// `400 Bad Request`, both with an `{error}` object.
+///
+/// The index is rendered when the server is bound, and again on
+/// [`refresh_files`](Self::refresh_files). Every request is logged at info
+/// level. Paths leading out of the recipes directory, whether
+/// through `..` or a symbolic link, are answered with `404 Not Found`.
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{HtmlOptions, IngredientIndex, RecipeServer};
+///
+/// let index = IngredientIndex::new("./recipes").unwrap();
+/// let server = RecipeServer::bind(index, "127.0.0.1:8080", &HtmlOptions::default()).unwrap();
+/// server.run();
+/// ```
+pub struct RecipeServer {
+    server: Server,
+    /// The recipes directory, canonicalized
+    root: PathBuf,
+    /// How the page is rendered, linking to the served files
+    options: HtmlOptions,
+    content: RwLock<Content>,
+}
+
+/// The index with its renderings, replaced together on refresh
+struct Content {
+    index: IngredientIndex,
+    html: String,
+    json: String,
+}
+
+#[derive(Serialize)]
+struct ApiIngredient<'a> {
+    name: &'a str,
+    recipes: usize,
+}
+
+#[derive(Serialize)]
+struct ApiRecipe {
+    path: String,
+    title: String,
+    url: String,
+}
+
+#[derive(Serialize)]
+struct ApiIngredientRecipes<'a> {
+    ingredient: &'a str,
+    recipes: Vec<ApiRecipe>,
+}
+
+#[derive(Serialize)]
+struct ApiSearch<'a> {
+    query: Vec<String>,
+    mode: &'a str,
+    ingredients: Vec<&'a str>,
+    recipes: Vec<ApiRecipe>,
+}
+
+#[derive(Serialize)]
+struct ApiRecipeIngredients<'a> {
+    path: String,
+    title: String,
+    ingredients: Vec<&'a str>,
+}
+
+#[derive(Serialize)]
+struct ApiError {
+    error: String,
+}
+
+impl RecipeServer {
+    /// Renders the index and starts listening, without answering requests yet
+    ///
+    /// # Arguments
+    /// * `index` - The index to serve, along with its recipes directory
+    /// * `addr` - Address to listen on, e.g. `"0.0.0.0:8080"` to be reachable
+    ///   from the local network, or port `0` for any free port
+    /// * `options` - How to render the page; recipe links always point to the
+    ///   served files
+    ///
+    /// # Returns
+    /// * `Result<RecipeServer>` - The listening server, or an error if the
+    ///   address cannot be bound or the page not rendered
+    pub fn bind(index: IngredientIndex, addr: impl ToSocketAddrs, options: &HtmlOptions) -> Result<Self> {
+        let root = fs::canonicalize(&index.base_dir)
+            .with_context(|| format!("failed to open {}", index.base_dir.display()))?;
+        let mut options = options.clone();
+        options.url.mapper = Some(UrlMapper::new(recipe_url));
+        let html = index.generate_html_with(&options)?;
+        let json = index.generate_json()?;
+        let server = Server::http(addr).map_err(|err| anyhow!(err).context("failed to start the server"))?;
+        Ok(Self { server, root, options, content: RwLock::new(Content { index, html, json }) })
+    }
+
+    /// Applies changed files to the served index and renders it again, so
+    /// requests see the new content without restarting the server
+    ///
+    /// # Arguments
+    /// * `paths` - Files or directories that may have changed, as for
+    ///   [`IngredientIndex::refresh_files`]
+    ///
+    /// # Returns
+    /// * `Result<RefreshReport>` - What changed in the index, or an error if
+    ///   it could not be rendered, in which case the previous pages are still served
+    pub fn refresh_files(&self, paths: &[PathBuf]) -> Result<RefreshReport> {
+        let mut content = self.content.write().unwrap_or_else(PoisonError::into_inner);
+        let report = content.index.refresh_files(paths);
+        if !report.is_empty() {
+            content.html = content.index.generate_html_with(&self.options)?;
+            content.json = content.index.generate_json()?;
+        }
+        Ok(report)
+    }
+
+    /// The address the server listens on, e.g. to learn the port picked for port `0`
+    pub fn local_addr(&self) -> Option<SocketAddr> {
+        self.server.server_addr().to_ip()
+    }
+
+    /// Answers requests until [`unblock`](Self::unblock) is called
+    pub fn run(&self) {
+        for request in self.server.incoming_requests() {
+            self.respond(request);
+        }
+    }
+
+    /// Makes [`run`](Self::run) return, e.g. from another thread
+    pub fn unblock(&self) {
+        self.server.unblock();
+    }
+
+    fn respond(&self, request: Request) {
+        let response = if matches!(request.method(), Method::Get | Method::Head) {
+            let url = request.url().split('#').next().unwrap_or_default();
+            let (path, query) = url.split_once('?').unwrap_or((url, ""));
+            self.route(path, query)
+        } else {
+            Response::from_string("method not allowed").with_status_code(405).boxed()
+        };
+
+        let status = response.status_code().0;
+        let remote = request.remote_addr().map_or_else(|| "-".to_string(), ToString::to_string);
+        log::info!("{} \"{} {}\" {}", remote, request.method(), request.url(), status);
+        if let Err(err) = request.respond(response) {
+            log::debug!("failed to answer {}: {}", remote, err);
+        }
+    }
+
+    fn content(&self) -> RwLockReadGuard<'_, Content> {
+        // A panicking refresh leaves the previous content, still worth serving
+        self.content.read().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn route(&self, path: &str, query: &str) -> ResponseBox {
+        if path == "/" || path == "/index.html" {
+            return text(&self.content().html, "text/html; charset=utf-8");
+        }
+        if path == "/index.json" {
+            return text(&self.content().json, "application/json");
+        }
+        if let Some(api_path) = path.strip_prefix("/api/") {
+            return api(&self.content().index, api_path, query);
+        }
+        match path.strip_prefix(RECIPES_PATH).and_then(|path| self.recipe_file(path)) {
+            Some((file, content_type)) => Response::from_file(file).with_header(header(content_type)).boxed(),
+            None => Response::from_string("not found").with_status_code(404).boxed(),
+        }
+    }
+
+    /// Opens a file of the recipes directory from its percent-encoded path
+    /// below [`RECIPES_PATH`], refusing anything outside the directory
+    fn recipe_file(&self, url_path: &str) -> Option<(File, &'static str)> {
+        let decoded = decode_path(url_path);
+        let relative = plain_relative_path(&decoded)?;
+        // Symbolic links may still point elsewhere
+        let path = fs::canonicalize(self.root.join(relative)).ok()?;
+        if !path.starts_with(&self.root) || !path.is_file() {
+            return None;
+        }
+        let file = File::open(&path).ok()?;
+        Some((file, self.content_type(&path)))
+    }
+
+    fn content_type(&self, path: &Path) -> &'static str {
+        let extension = path.extension().map(|ext| ext.to_string_lossy().to_lowercase()).unwrap_or_default();
+        match extension.as_str() {
+            _ if self.content().index.options.extensions.iter().any(|known| known.eq_ignore_ascii_case(&extension)) => {
+                "text/plain; charset=utf-8"
+            }
+            "jpg" | "jpeg" => "image/jpeg",
+            "png" => "image/png",
+            "webp" => "image/webp",
+            "txt" | "conf" => "text/plain; charset=utf-8",
+            _ => "application/octet-stream",
+        }
+    }
+}
+
+/// Answers a request below `/api/`, given the rest of its path
+fn api(index: &IngredientIndex, path: &str, query: &str) -> ResponseBox {
+    if path == "ingredients" {
+        let ingredients: Vec<ApiIngredient> = index
+            .ingredients()
+            .into_iter()
+            .map(|name| ApiIngredient { name, recipes: index.index[name].len() })
+            .collect();
+        return json(200, &ingredients);
+    }
+    if path == "search" {
+        return api_search(index, query);
+    }
+    if let Some(name) = path.strip_prefix("ingredients/").and_then(|path| path.strip_suffix("/recipes")) {
+        let name = decode_path(name);
+        return match index.matching_ingredients(&name, Matching::Exact).first() {
+            Some(ingredient) => {
+                let recipes = api_recipes(index, index.index[*ingredient].iter());
+                json(200, &ApiIngredientRecipes { ingredient, recipes })
+            }
+            None => error(404, format!("unknown ingredient {:?}", name)),
+        };
+    }
+    if let Some(recipe_path) = path.strip_prefix("recipes/").and_then(|path| path.strip_suffix("/ingredients")) {
+        let recipe_path = decode_path(recipe_path);
+        // Merged recipes are relative to their own root rather than the base directory
+        let recipe = plain_relative_path(&recipe_path).and_then(|relative| {
+            index.recipes.iter().find(|recipe| {
+                recipe.path.strip_prefix(index.root_of(&recipe.path)).is_ok_and(|path| path == relative)
+            })
+        });
+        return match recipe {
+            Some(recipe) => {
+                let mut seen = HashSet::new();
+                json(
+                    200,
+                    &ApiRecipeIngredients {
+                        path: export_path(&recipe.path, index.root_of(&recipe.path)),
+                        title: recipe_name(recipe),
+                        ingredients: recipe
+                            .ingredients
+                            .iter()
+                            .map(|ingredient| ingredient.name.as_str())
+                            .filter(|name| seen.insert(*name))
+                            .collect(),
+                    },
+                )
+            }
+            None => error(404, format!("unknown recipe {:?}", recipe_path)),
+        };
+    }
+    error(404, format!("unknown endpoint /api/{}", path))
+}
+
+fn api_search(index: &IngredientIndex, query: &str) -> ResponseBox {
+    let mut terms = Vec::new();
+    let mut mode = "exact";
+    let mut all = false;
+    let parameters: Vec<(String, String)> = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            (decode_query(key), decode_query(value))
+        })
+        .collect();
+    for (key, value) in &parameters {
+        match key.as_str() {
+            "q" => terms.push(value.clone()),
+            "mode" => mode = value,
+            "all" => all = value == "true" || value == "1",
+            _ => {}
+        }
+    }
+    let matching = match mode {
+        "exact" => Matching::Exact,
+        "prefix" => Matching::Prefix,
+        "substring" => Matching::Substring,
+        "fuzzy" => Matching::Fuzzy,
+        _ => return error(400, format!("unknown mode {:?}, expected exact, prefix, substring or fuzzy", mode)),
+    };
+    terms.retain(|term| !term.trim().is_empty());
+    if terms.is_empty() {
+        return error(400, "missing search terms, e.g. ?q=garlic".to_string());
+    }
+
+    let mut ingredients: Vec<&str> = terms
+        .iter()
+        .flat_map(|term| index.matching_ingredients(term, matching))
+        .map(String::as_str)
+        .collect();
+    ingredients.sort_unstable();
+    ingredients.dedup();
+    let term_refs: Vec<&str> = terms.iter().map(String::as_str).collect();
+    let found = index.search(&term_refs, &SearchOptions { matching, all });
+    let recipes = api_recipes(index, found.into_iter().map(PathBuf::as_path));
+    json(200, &ApiSearch { query: terms, mode, ingredients, recipes })
+}
+
+/// Describes recipes the way the API lists them
+fn api_recipes<'a>(index: &IngredientIndex, paths: impl Iterator<Item = &'a Path>) -> Vec<ApiRecipe> {
+    paths
+        .filter_map(|path| index.recipe(path))
+        .map(|recipe| {
+            let root = index.root_of(&recipe.path);
+            ApiRecipe {
+                path: export_path(&recipe.path, root),
+                title: recipe_name(recipe),
+                url: recipe_url(&recipe.path, root),
+            }
+        })
+        .collect()
+}
+
+/// Where the server serves a recipe file
+fn recipe_url(path: &Path, base_dir: &Path) -> String {
+    let url = relative_url(path, base_dir, base_dir, &UrlOptions::default()).unwrap_or_default();
+    format!("{}{}", RECIPES_PATH, url)
+}
+
+/// The path, if it only names entries below a directory: no `..`, no root
+/// and no drive prefix
+fn plain_relative_path(path: &str) -> Option<&Path> {
+    let path = Path::new(path);
+    path.components()
+        .all(|component| matches!(component, Component::Normal(_) | Component::CurDir))
+        .then_some(path)
+}
+
+/// Percent-decodes a path segment, where `+` stands for itself
+fn decode_path(text: &str) -> String {
+    urlencoding::decode(text).map_or_else(|_| text.to_string(), |decoded| decoded.into_owned())
+}
+
+/// Percent-decodes a query key or value, reading `+` as a space
+fn decode_query(text: &str) -> String {
+    decode_path(&text.replace('+', " "))
+}
+
+fn text(body: &str, content_type: &str) -> ResponseBox {
+    Response::from_string(body).with_header(header(content_type)).boxed()
+}
+
+fn json(status: u16, value: &impl Serialize) -> ResponseBox {
+    // Serializing these plain structs cannot fail
+    let body = serde_json::to_string(value).unwrap_or_default();
+    Response::from_string(body).with_status_code(status).with_header(header("application/json")).boxed()
+}
+
+fn error(status: u16, error: String) -> ResponseBox {
+    json(status, &ApiError { error })
+}
+
+fn header(content_type: &str) -> Header {
+    Header::from_bytes("Content-Type", content_type).expect("content types are valid header values")
+}
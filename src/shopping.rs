@@ -0,0 +1,359 @@
+// File: src/shopping.rs
+
+//! Shopping lists merging the ingredients of several recipes
+
+use crate::{IngredientAmount, IngredientIndex};
+use serde::Serialize;
+use std::collections::{HashMap, HashSet};
+use std::fmt;
+use std::path::PathBuf;
+
+/// Maps ingredient names to the aisle they are found in
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::Aisles;
+///
+/// let aisles = Aisles::parse("[baking]\nflour\nsugar|caster sugar\n\n[dairy]\nmilk\n");
+/// assert_eq!(aisles.aisle_of("Caster Sugar"), Some("baking"));
+/// assert_eq!(aisles.aisle_of("eggs"), None);
+/// ```
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct Aisles {
+    /// Aisle names in the order they were defined
+    order: Vec<String>,
+    /// Lowercased ingredient name to aisle name
+    aisles: HashMap<String, String>,
+}
+
+impl Aisles {
+    /// Creates an empty mapping
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Parses the cooklang `aisle.conf` format
+    ///
+    /// Each `[aisle]` header is followed by one ingredient per line, where
+    /// `|` separates alternative spellings. Blank lines are ignored.
+    pub fn parse(conf: &str) -> Self {
+        let mut aisles = Self::new();
+        let mut current = None;
+        for line in conf.lines().map(str::trim).filter(|line| !line.is_empty()) {
+            if let Some(aisle) = line.strip_prefix('[').and_then(|line| line.strip_suffix(']')) {
+                current = Some(aisle.trim().to_string());
+                continue;
+            }
+            if let Some(aisle) = &current {
+                for name in line.split('|') {
+                    aisles.insert(name, aisle);
+                }
+            }
+        }
+        aisles
+    }
+
+    /// Files an ingredient under an aisle, replacing any previous aisle
+    pub fn insert(&mut self, ingredient: &str, aisle: &str) {
+        if !self.order.iter().any(|known| known == aisle) {
+            self.order.push(aisle.to_string());
+        }
+        self.aisles.insert(ingredient.trim().to_lowercase(), aisle.to_string());
+    }
+
+    /// The aisle an ingredient is found in, matched case-insensitively
+    pub fn aisle_of(&self, ingredient: &str) -> Option<&str> {
+        self.aisles.get(&ingredient.trim().to_lowercase()).map(String::as_str)
+    }
+
+    /// Whether no ingredient has been assigned an aisle
+    pub fn is_empty(&self) -> bool {
+        self.aisles.is_empty()
+    }
+}
+
+/// Options for [`IngredientIndex::shopping_list_with`]
+#[derive(Debug, Clone)]
+pub struct ShoppingOptions {
+    /// Factor numeric quantities are multiplied by, e.g. `2.0` to cook twice
+    /// as much; quantities that are not numbers are kept as written
+    pub scale: f64,
+    /// Ingredients already at hand, left off the list whatever the recipes
+    /// need, matched like the indexed names
+    pub staples: Vec<String>,
+}
+
+impl Default for ShoppingOptions {
+    fn default() -> Self {
+        Self { scale: 1.0, staples: Vec::new() }
+    }
+}
+
+/// Ingredients needed for a set of recipes, grouped by aisle
+///
+/// The [`Display`](fmt::Display) implementation prints a plain-text list,
+/// [`to_cooklang`](Self::to_cooklang) a cooklang one, and the list serializes
+/// to `{"groups": [{"aisle", "items": [{"name", "amounts": [{"quantity", "unit"}], "other"}]}]}`.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct ShoppingList {
+    /// Groups in aisle definition order; ingredients without an aisle come last
+    pub groups: Vec<ShoppingGroup>,
+}
+
+/// The shopping list items found in one aisle
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingGroup {
+    /// Aisle name, or `None` for ingredients without a configured aisle
+    pub aisle: Option<String>,
+    /// Items sorted by ingredient name
+    pub items: Vec<ShoppingItem>,
+}
+
+/// One ingredient on the shopping list
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingItem {
+    /// Normalized ingredient name
+    pub name: String,
+    /// Numeric quantities summed per unit, in order of first appearance
+    pub amounts: Vec<ShoppingAmount>,
+    /// Quantities that are not plain numbers, kept as written (e.g. "a pinch")
+    pub other: Vec<String>,
+}
+
+/// A summed quantity of one unit
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct ShoppingAmount {
+    /// Total quantity
+    pub quantity: f64,
+    /// Unit shared by all summed quantities, if any
+    pub unit: Option<String>,
+}
+
+impl ShoppingItem {
+    /// Adds one amount, summing it into an existing one of the same unit if possible
+    pub(crate) fn add(&mut self, amount: &IngredientAmount) {
+        let Some(quantity) = &amount.quantity else { return };
+        match parse_number(quantity) {
+            Some(value) => match self.amounts.iter_mut().find(|a| a.unit == amount.unit) {
+                Some(existing) => existing.quantity += value,
+                None => self.amounts.push(ShoppingAmount { quantity: value, unit: amount.unit.clone() }),
+            },
+            None => self.other.push(match &amount.unit {
+                Some(unit) => format!("{} {}", quantity, unit),
+                None => quantity.clone(),
+            }),
+        }
+    }
+
+    /// All amounts as text, summed ones first
+    pub(crate) fn amount_texts(&self) -> Vec<String> {
+        self.amounts.iter().map(ToString::to_string).chain(self.other.iter().cloned()).collect()
+    }
+}
+
+impl ShoppingAmount {
+    /// The quantity as printed, rounded to remove float noise from summing
+    /// fractions such as 1/3
+    fn rounded(&self) -> f64 {
+        (self.quantity * 1000.0).round() / 1000.0
+    }
+}
+
+impl fmt::Display for ShoppingAmount {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let quantity = self.rounded();
+        match &self.unit {
+            Some(unit) => write!(f, "{} {}", quantity, unit),
+            None => write!(f, "{}", quantity),
+        }
+    }
+}
+
+impl fmt::Display for ShoppingItem {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let amounts = self.amount_texts();
+        if amounts.is_empty() {
+            write!(f, "{}", self.name)
+        } else {
+            write!(f, "{}: {}", self.name, amounts.join(" + "))
+        }
+    }
+}
+
+impl fmt::Display for ShoppingList {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let headed = self.groups.iter().any(|group| group.aisle.is_some());
+        for group in &self.groups {
+            if headed {
+                writeln!(f, "[{}]", group.aisle.as_deref().unwrap_or("other"))?;
+            }
+            for item in &group.items {
+                writeln!(f, "- {}", item)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+impl ShoppingList {
+    /// Writes the list as cooklang text, to import into cooklang tools
+    ///
+    /// Each item is a line using the ingredient once per amount, e.g.
+    /// `@flour{700%g} @flour{0.5%kg}`, or once with empty braces when no amount
+    /// is known. When aisles are configured, each group starts with an
+    /// `= aisle` section line, `= other` for ingredients without one.
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # use std::path::PathBuf;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let list = index.shopping_list(&[PathBuf::from("./recipes/pancakes.cook")]);
+    /// std::fs::write("shopping.cook", list.to_cooklang()).unwrap();
+    /// ```
+    pub fn to_cooklang(&self) -> String {
+        let headed = self.groups.iter().any(|group| group.aisle.is_some());
+        let mut cooklang = String::new();
+        for group in &self.groups {
+            if headed {
+                cooklang.push_str(&format!("= {}\n", group.aisle.as_deref().unwrap_or("other")));
+            }
+            for item in &group.items {
+                let mut amounts: Vec<String> = item
+                    .amounts
+                    .iter()
+                    .map(|amount| match &amount.unit {
+                        Some(unit) => format!("{}%{}", amount.rounded(), unit),
+                        None => amount.rounded().to_string(),
+                    })
+                    .chain(item.other.iter().cloned())
+                    .collect();
+                if amounts.is_empty() {
+                    amounts.push(String::new());
+                }
+                let mentions: Vec<String> =
+                    amounts.iter().map(|amount| format!("@{}{{{}}}", item.name, amount)).collect();
+                cooklang.push_str(&mentions.join(" "));
+                cooklang.push('\n');
+            }
+        }
+        cooklang
+    }
+}
+
+impl IngredientIndex {
+    /// Builds a shopping list for the given recipes
+    ///
+    /// Ingredients used by several recipes are merged into one item. Numeric
+    /// quantities (including fractions like `1/2`) are summed when their units
+    /// match; anything else is kept as written. Items are grouped by the aisles
+    /// configured with [`IngredientIndexBuilder::aisles`](crate::IngredientIndexBuilder::aisles).
+    ///
+    /// # Arguments
+    /// * `recipes` - Paths of the recipes to shop for, spelled as in the index;
+    ///   unknown paths are skipped
+    ///
+    /// # Returns
+    /// * `ShoppingList` - The merged ingredients
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # use std::path::PathBuf;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let list = index.shopping_list(&[PathBuf::from("./recipes/pancakes.cook")]);
+    /// print!("{}", list);
+    /// ```
+    pub fn shopping_list(&self, recipes: &[PathBuf]) -> ShoppingList {
+        self.shopping_list_with(recipes, &ShoppingOptions::default())
+    }
+
+    /// Builds a shopping list for the given recipes, scaled and without the
+    /// staples at hand
+    ///
+    /// Like [`shopping_list`](Self::shopping_list) otherwise.
+    ///
+    /// # Arguments
+    /// * `recipes` - Paths of the recipes to shop for, spelled as in the index;
+    ///   unknown paths are skipped
+    /// * `options` - Scale and staples
+    ///
+    /// # Returns
+    /// * `ShoppingList` - The merged ingredients
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{IngredientIndex, ShoppingOptions};
+    /// # use std::path::PathBuf;
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let options = ShoppingOptions { scale: 2.0, staples: vec!["salt".to_string()] };
+    /// print!("{}", index.shopping_list_with(&[PathBuf::from("./recipes/pancakes.cook")], &options));
+    /// ```
+    pub fn shopping_list_with(&self, recipes: &[PathBuf], options: &ShoppingOptions) -> ShoppingList {
+        let staples: HashSet<String> = options.staples.iter().map(|name| self.options.key(name)).collect();
+        let mut items: Vec<ShoppingItem> = Vec::new();
+        for path in recipes {
+            let Some(recipe) = self.recipes.iter().find(|recipe| &recipe.path == path) else {
+                log::warn!("not in the index, left off the shopping list: {}", path.display());
+                continue;
+            };
+            for ingredient in &recipe.ingredients {
+                let name = &ingredient.name;
+                if staples.contains(name) {
+                    continue;
+                }
+                let pos = match items.iter().position(|item| &item.name == name) {
+                    Some(pos) => pos,
+                    None => {
+                        items.push(ShoppingItem { name: name.clone(), amounts: Vec::new(), other: Vec::new() });
+                        items.len() - 1
+                    }
+                };
+                if let Some(amount) = &ingredient.quantity {
+                    items[pos].add(amount);
+                }
+            }
+        }
+        items.sort_by(|a, b| a.name.cmp(&b.name));
+        for amount in items.iter_mut().flat_map(|item| &mut item.amounts) {
+            amount.quantity *= options.scale;
+        }
+
+        let aisles = &self.options.aisles;
+        let mut groups: Vec<ShoppingGroup> = aisles
+            .order
+            .iter()
+            .map(|aisle| ShoppingGroup { aisle: Some(aisle.clone()), items: Vec::new() })
+            .collect();
+        let mut unfiled = Vec::new();
+        for item in items {
+            match aisles.aisle_of(&item.name) {
+                Some(aisle) => {
+                    let group = groups.iter_mut().find(|group| group.aisle.as_deref() == Some(aisle)).unwrap();
+                    group.items.push(item);
+                }
+                None => unfiled.push(item),
+            }
+        }
+        groups.retain(|group| !group.items.is_empty());
+        if !unfiled.is_empty() {
+            groups.push(ShoppingGroup { aisle: None, items: unfiled });
+        }
+        ShoppingList { groups }
+    }
+}
+
+/// Parses a plain number, a fraction like `1/2` or a mixed number like `1 1/2`
+fn parse_number(text: &str) -> Option<f64> {
+    let fraction = |text: &str| match text.split_once('/') {
+        Some((num, den)) => {
+            let den: f64 = den.trim().parse().ok()?;
+            (den != 0.0).then_some(num.trim().parse::<f64>().ok()? / den)
+        }
+        None => text.trim().parse().ok(),
+    };
+    match text.trim().split_once(' ') {
+        Some((whole, rest)) => Some(whole.parse::<f64>().ok()? + fraction(rest)?),
+        None => fraction(text),
+    }
+}
@@ -0,0 +1,441 @@
+// File: src/site.rs
+
+//! Multi-page output: a page per ingredient and per recipe plus an index
+//! linking to the ingredient pages
+
+use crate::html::{
+    collect_entries, escape_html, fill, filter_input, recipe_name, resolve_options, HtmlWriter, IngredientEntry, Linker,
+};
+use crate::url::strip_base_dir;
+use crate::{write_atomically, HtmlOptions, IngredientIndex, LinkWarning, Recipe, UrlMapper};
+use anyhow::{Context, Result};
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+use std::fs;
+use std::path::{Component, Path, PathBuf};
+use walkdir::WalkDir;
+
+/// Directory below the output directory holding the ingredient pages
+const INGREDIENTS_DIR: &str = "ingredients";
+/// Directory below the output directory holding the recipe pages
+const RECIPES_DIR: &str = "recipes";
+
+/// Files written by [`IngredientIndex::generate_site`]
+#[derive(Debug, Clone)]
+pub struct SiteManifest {
+    /// Path of the `index.html` linking to every ingredient page, or to those
+    /// of the first page when [`HtmlOptions::ingredients_per_page`] is set
+    pub index: PathBuf,
+    /// Every page of the ingredient index in order, `index` first
+    pub index_pages: Vec<PathBuf>,
+    /// Path of the `recipes.html` linking to every recipe page
+    pub recipe_index: PathBuf,
+    /// Each ingredient with the path of its page, in listing order
+    pub ingredient_pages: Vec<(String, PathBuf)>,
+    /// Each recipe file with the path of its page, sorted by recipe path
+    pub recipe_pages: Vec<(PathBuf, PathBuf)>,
+    /// Path of the `search-index.json` holding [`generate_json`](IngredientIndex::generate_json)
+    pub search_index: PathBuf,
+    /// Path of the `sitemap.xml` listing every page, written only when there
+    /// is a base URL to build its absolute URLs from
+    pub sitemap: Option<PathBuf>,
+    /// Pages left over from a previous run that were deleted
+    pub removed: Vec<PathBuf>,
+    /// Recipes that were skipped or linked lossily
+    pub warnings: Vec<LinkWarning>,
+}
+
+impl SiteManifest {
+    /// Every file written, the two index pages, the search index and any
+    /// sitemap first and then any further pages of the ingredient index
+    pub fn files(&self) -> impl Iterator<Item = &Path> {
+        [self.index.as_path(), self.recipe_index.as_path(), self.search_index.as_path()]
+            .into_iter()
+            .chain(self.sitemap.as_deref())
+            .chain(self.index_pages.iter().skip(1).map(PathBuf::as_path))
+            .chain(self.ingredient_pages.iter().map(|(_, path)| path.as_path()))
+            .chain(self.recipe_pages.iter().map(|(_, path)| path.as_path()))
+    }
+}
+
+impl IngredientIndex {
+    /// Writes a small static site: an `index.html` listing the ingredients, a
+    /// `recipes.html` listing the recipes (see
+    /// [`generate_recipe_index_html`](Self::generate_recipe_index_html)), an
+    /// `ingredients/<slug>.html` page per ingredient listing its recipes, a
+    /// `recipes/<path>.html` page per recipe listing its ingredients, a
+    /// `search-index.json` (see [`generate_json`](Self::generate_json)) and,
+    /// with a base URL, a `sitemap.xml`
+    ///
+    /// Ingredient pages are named after the ingredient anchors of the single-page
+    /// index, so a slug shared by several ingredients gets `-2`, `-3`… appended
+    /// in listing order. Recipe pages mirror the layout of the recipes
+    /// directory, e.g. `soups/tomato-soup.cook` gets `recipes/soups/tomato-soup.html`,
+    /// and are headed with the recipe's `>> title:` if it has one. Ingredient
+    /// and recipe pages link to each other with relative links, so the site
+    /// works wherever it is served from.
+    ///
+    /// Each recipe page also links to the `.cook` file, built from the URL
+    /// options like the links of the single-page index; without a base URL
+    /// that link is relative to the recipe page.
+    ///
+    /// The sitemap lists every page written, below `options.base_url`. Sitemaps
+    /// need absolute URLs, so without a base URL none is written.
+    ///
+    /// Any other `.html` file below `ingredients/` and `recipes/`, e.g. the page
+    /// of an ingredient that no longer exists, is deleted and reported in
+    /// [`SiteManifest::removed`], as is a `sitemap.xml` left from a run with a
+    /// base URL; nothing else in `output_dir` is touched.
+    /// `options.output_dir` is ignored in favor of `output_dir`.
+    ///
+    /// # Arguments
+    /// * `output_dir` - Directory to write the site to, created if missing
+    /// * `options` - Options for the pages; `collapsible` has no effect
+    ///
+    /// # Returns
+    /// * `Result<SiteManifest>` - The files written and removed, or an error
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    /// let index = IngredientIndex::new("./recipes").unwrap();
+    /// let manifest = index.generate_site("public", &HtmlOptions::default()).unwrap();
+    /// println!("wrote {} pages", manifest.files().count());
+    /// ```
+    pub fn generate_site(&self, output_dir: impl AsRef<Path>, options: &HtmlOptions) -> Result<SiteManifest> {
+        let output_dir = output_dir.as_ref();
+        let options = resolve_options(self, options)?;
+
+        // Ingredient pages link to the recipe pages rather than the recipe files
+        let mut page_options = options.clone().into_owned();
+        page_options.url.mapper = Some(UrlMapper::new(|path, base_dir| {
+            format!("../{}", recipe_page_url(&recipe_page_path(path, base_dir)))
+        }));
+        let (entries, _) = collect_entries(self, &page_options);
+        let anchors: HashMap<&str, &str> =
+            entries.iter().map(|entry| (entry.name.as_str(), entry.anchor.as_str())).collect();
+
+        let mut ingredient_pages = Vec::with_capacity(entries.len());
+        for entry in &entries {
+            let path = output_dir.join(INGREDIENTS_DIR).join(format!("{}.html", entry.anchor));
+            write_page(&path, &ingredient_page(self, entry, &page_options))?;
+            ingredient_pages.push((entry.name.clone(), path));
+        }
+
+        let mut recipe_pages = Vec::with_capacity(self.recipes.len());
+        let mut warnings = Vec::new();
+        for recipe in &self.recipes {
+            let page = recipe_page_path(&recipe.path, self.root_of(&recipe.path));
+            let path = output_dir.join(RECIPES_DIR).join(&page);
+            let page_dir = path.parent().unwrap_or(output_dir);
+            let mut linker = Linker::new(self, &options, page_dir);
+            let source_url = linker.recipe_url(&recipe.path);
+            warnings.append(&mut linker.warnings);
+            let html = recipe_page(self, recipe, &page, &anchors, source_url.as_deref(), &options);
+            write_page(&path, &html)?;
+            recipe_pages.push((recipe.path.clone(), path));
+        }
+
+        // Plan the pages first, so the A–Z navigation of each one can link to
+        // the page every letter starts on
+        let mut pages: Vec<&[IngredientEntry]> = match options.ingredients_per_page {
+            0 => vec![&entries],
+            per_page => entries.chunks(per_page).collect(),
+        };
+        if pages.is_empty() {
+            pages.push(&[]);
+        }
+        let letter_pages: HashMap<&str, String> = pages
+            .iter()
+            .enumerate()
+            .flat_map(|(number, page)| {
+                page.iter()
+                    .filter_map(move |entry| Some((entry.letter_anchor.as_deref()?, index_page_name(number + 1))))
+            })
+            .collect();
+        let mut index_pages = Vec::with_capacity(pages.len());
+        for (number, page) in pages.iter().enumerate() {
+            let path = output_dir.join(index_page_name(number + 1));
+            let pagination = Pagination { number: number + 1, count: pages.len(), letter_pages: &letter_pages };
+            write_page(&path, &index_page(self, &entries, page, &pagination, &options))?;
+            index_pages.push(path);
+        }
+        let index = index_pages[0].clone();
+
+        let recipe_index = output_dir.join("recipes.html");
+        let labels = &options.labels;
+        let mut recipe_index_options = HtmlOptions {
+            title: format!("{} - {}", labels.all_recipes, options.title),
+            heading: Some(labels.all_recipes.clone()),
+            intro_html: Some(format!(
+                "    <p><a href=\"index.html\">{}</a></p>\n",
+                escape_html(&labels.all_ingredients)
+            )),
+            filter_box: false,
+            ..options.clone().into_owned()
+        };
+        recipe_index_options.url.mapper =
+            Some(UrlMapper::new(|path, base_dir| recipe_page_url(&recipe_page_path(path, base_dir))));
+        write_page(&recipe_index, &self.generate_recipe_index_html(&recipe_index_options)?)?;
+
+        let search_index = output_dir.join("search-index.json");
+        write_page(&search_index, &self.generate_json()?)?;
+
+        let mut removed = Vec::new();
+        let sitemap_path = output_dir.join("sitemap.xml");
+        let sitemap = match options.base_url.as_deref() {
+            Some(base_url) => {
+                let mut pages: Vec<String> = (1..=index_pages.len()).map(index_page_name).collect();
+                pages.push("recipes.html".to_string());
+                pages.extend(entries.iter().map(|entry| format!("{}/{}.html", INGREDIENTS_DIR, entry.anchor)));
+                pages.extend(recipe_pages.iter().map(|(recipe, _)| {
+                    recipe_page_url(&recipe_page_path(recipe, self.root_of(recipe)))
+                }));
+                let locations: Vec<String> = pages.iter().map(|page| site_url(base_url, page)).collect();
+                write_page(&sitemap_path, &sitemap_xml(&locations))?;
+                Some(sitemap_path)
+            }
+            None => {
+                if sitemap_path.is_file() {
+                    fs::remove_file(&sitemap_path)
+                        .with_context(|| format!("failed to remove {}", sitemap_path.display()))?;
+                    removed.push(sitemap_path);
+                }
+                None
+            }
+        };
+
+        let written: HashSet<&Path> = ingredient_pages
+            .iter()
+            .map(|(_, path)| path.as_path())
+            .chain(recipe_pages.iter().map(|(_, path)| path.as_path()))
+            .collect();
+        // Further pages of a longer index from a previous run
+        for number in index_pages.len() + 1.. {
+            let path = output_dir.join(index_page_name(number));
+            if !path.is_file() {
+                break;
+            }
+            fs::remove_file(&path).with_context(|| format!("failed to remove {}", path.display()))?;
+            removed.push(path);
+        }
+        for dir in [INGREDIENTS_DIR, RECIPES_DIR] {
+            for dir_entry in WalkDir::new(output_dir.join(dir)) {
+                let dir_entry = dir_entry?;
+                let path = dir_entry.path();
+                if dir_entry.file_type().is_file()
+                    && path.extension().is_some_and(|ext| ext == "html")
+                    && !written.contains(path)
+                {
+                    fs::remove_file(path).with_context(|| format!("failed to remove {}", path.display()))?;
+                    removed.push(path.to_path_buf());
+                }
+            }
+        }
+        removed.sort();
+
+        Ok(SiteManifest {
+            index,
+            index_pages,
+            recipe_index,
+            ingredient_pages,
+            recipe_pages,
+            search_index,
+            sitemap,
+            removed,
+            warnings,
+        })
+    }
+}
+
+/// File name of a page of the ingredient index, numbered from 1
+fn index_page_name(number: usize) -> String {
+    match number {
+        1 => "index.html".to_string(),
+        number => format!("index-{}.html", number),
+    }
+}
+
+/// Where a page of the ingredient index sits among the others
+struct Pagination<'a> {
+    /// Number of the page, from 1
+    number: usize,
+    /// Number of pages
+    count: usize,
+    /// File name of the page each letter anchor is on
+    letter_pages: &'a HashMap<&'a str, String>,
+}
+
+/// A page of the top-level index: every ingredient of `page` linking to its
+/// own page, under the A–Z navigation of all `entries`
+fn index_page(
+    index: &IngredientIndex,
+    entries: &[IngredientEntry],
+    page: &[IngredientEntry],
+    pagination: &Pagination,
+    options: &HtmlOptions,
+) -> String {
+    let mut writer = HtmlWriter::new(index, options);
+    writer.write_head();
+    let all_recipes = escape_html(&options.labels.all_recipes);
+    writer.push(&format!("    <p><a href=\"recipes.html\">{}</a></p>\n", all_recipes));
+    if options.alphabet_nav {
+        let this_page = index_page_name(pagination.number);
+        writer.write_alphabet_nav(entries, |id| match pagination.letter_pages.get(id) {
+            Some(page) if *page != this_page => format!("{}#{}", page, id),
+            _ => format!("#{}", id),
+        });
+    }
+    if options.filter_box {
+        writer.push(&filter_input(&options.labels));
+    }
+    for entry in page {
+        writer.write_letter_markers(entry);
+        let heading = writer.name_heading();
+        writer.push(&format!("<section class=\"ingredient\" id=\"{}\">\n", entry.anchor));
+        writer.push(&format!(
+            "    <{} class=\"ingredient-name\"><a href=\"{}/{}.html\">{}</a>",
+            heading,
+            INGREDIENTS_DIR,
+            entry.anchor,
+            escape_html(&entry.name)
+        ));
+        if options.show_counts {
+            let count = escape_html(&options.labels.recipe_count(entry.recipes.len()));
+            writer.push(&format!(" <span class=\"ingredient-count\">({})</span>", count));
+        }
+        writer.push(&format!("</{}>\n</section>\n", heading));
+    }
+    if pagination.count > 1 {
+        write_pagination(&mut writer, pagination);
+    }
+    writer.write_tail();
+    writer.html
+}
+
+/// Writes the links to the previous and next pages of the ingredient index
+fn write_pagination(writer: &mut HtmlWriter, pagination: &Pagination) {
+    let labels = &writer.options.labels;
+    let previous = (pagination.number > 1).then(|| {
+        let href = index_page_name(pagination.number - 1);
+        format!("        <a href=\"{}\" rel=\"prev\">{}</a>\n", href, escape_html(&labels.previous_page))
+    });
+    let position = fill(&labels.page_position, &[&pagination.number.to_string(), &pagination.count.to_string()]);
+    let next = (pagination.number < pagination.count).then(|| {
+        let href = index_page_name(pagination.number + 1);
+        format!("        <a href=\"{}\" rel=\"next\">{}</a>\n", href, escape_html(&labels.next_page))
+    });
+    writer.push(&format!("    <nav class=\"pagination\" aria-label=\"{}\">\n", escape_html(&labels.pagination)));
+    writer.push(&previous.unwrap_or_default());
+    writer.push(&format!("        <span>{}</span>\n", escape_html(&position)));
+    writer.push(&next.unwrap_or_default());
+    writer.push("    </nav>\n");
+}
+
+/// The page of one ingredient, listing its recipes
+fn ingredient_page(index: &IngredientIndex, entry: &IngredientEntry, options: &HtmlOptions) -> String {
+    let options = HtmlOptions {
+        title: format!("{} - {}", entry.name, options.title),
+        heading: Some(entry.name.clone()),
+        intro_html: None,
+        filter_box: false,
+        ..options.clone()
+    };
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.write_head();
+    let all_ingredients = escape_html(&options.labels.all_ingredients);
+    writer.push(&format!("    <p><a href=\"../index.html\">{}</a></p>\n", all_ingredients));
+    writer.write_recipe_list(&entry.recipes);
+    writer.write_tail();
+    writer.html
+}
+
+/// The page of one recipe, listing its ingredients and linking to its source
+///
+/// `page` is the path of the page below the recipes directory of the site.
+fn recipe_page(
+    index: &IngredientIndex,
+    recipe: &Recipe,
+    page: &Path,
+    anchors: &HashMap<&str, &str>,
+    source_url: Option<&str>,
+    options: &HtmlOptions,
+) -> String {
+    let name = recipe_name(recipe);
+    let options = HtmlOptions {
+        title: format!("{} - {}", name, options.title),
+        heading: Some(name),
+        intro_html: None,
+        filter_box: false,
+        ..options.clone()
+    };
+    let root = "../".repeat(page.components().count());
+    let mut writer = HtmlWriter::new(index, &options);
+    writer.write_head();
+    let all_ingredients = escape_html(&options.labels.all_ingredients);
+    writer.push(&format!("    <p><a href=\"{}index.html\">{}</a></p>\n", root, all_ingredients));
+    writer.push("    <ul class=\"ingredient-list\">\n");
+    let mut seen = HashSet::new();
+    for ingredient in recipe.ingredients.iter().map(|ingredient| &ingredient.name).filter(|name| seen.insert(*name)) {
+        writer.push(&format!(
+            "        <li><a href=\"{}{}/{}.html\">{}</a></li>\n",
+            root,
+            INGREDIENTS_DIR,
+            anchors[ingredient.as_str()],
+            escape_html(ingredient)
+        ));
+    }
+    writer.push("    </ul>\n");
+    if let Some(url) = source_url {
+        writer.push(&format!(
+            "    <p class=\"recipe-source\"><a href=\"{}\">{}</a></p>\n",
+            escape_html(url),
+            escape_html(&options.labels.source)
+        ));
+    }
+    writer.write_tail();
+    writer.html
+}
+
+/// Path of a recipe's page below the recipes directory of the site, mirroring
+/// the recipe's place below `base_dir`
+fn recipe_page_path(path: &Path, base_dir: &Path) -> PathBuf {
+    let relative = strip_base_dir(path, base_dir).unwrap_or(Cow::Borrowed(path));
+    let mut page: PathBuf = relative.components().filter(|c| matches!(c, Component::Normal(_))).collect();
+    page.set_extension("html");
+    page
+}
+
+/// Relative URL of a recipe page from the site root, e.g. `recipes/soups/tomato-soup.html`
+fn recipe_page_url(page: &Path) -> String {
+    let mut url = RECIPES_DIR.to_string();
+    for component in page.components() {
+        url.push('/');
+        url.push_str(&urlencoding::encode(&component.as_os_str().to_string_lossy()));
+    }
+    url
+}
+
+/// Absolute URL of a page of the site served from `base_url`
+fn site_url(base_url: &str, page: &str) -> String {
+    format!("{}/{}", base_url.trim_end_matches('/'), page)
+}
+
+/// A sitemap listing `locations` in order
+fn sitemap_xml(locations: &[String]) -> String {
+    let mut xml = String::from("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+    xml.push_str("<urlset xmlns=\"http://www.sitemaps.org/schemas/sitemap/0.9\">\n");
+    for location in locations {
+        xml.push_str(&format!("    <url><loc>{}</loc></url>\n", escape_html(location)));
+    }
+    xml.push_str("</urlset>\n");
+    xml
+}
+
+/// Writes one page, creating its directory and naming the file in any error
+fn write_page(path: &Path, html: &str) -> Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir).with_context(|| format!("failed to create {}", dir.display()))?;
+    }
+    write_atomically(path, html)
+}
@@ -0,0 +1,123 @@
+// File: src/slug.rs
+
+//! Slug generation shared by anchors, page filenames and external tools
+//!
+//! Everything the indexer names after an ingredient (HTML anchors, per-ingredient
+//! pages) goes through [`slugify`], so other tools can reproduce the same names.
+
+use std::collections::HashSet;
+use unicode_normalization::char::is_combining_mark;
+use unicode_normalization::UnicodeNormalization;
+
+/// Turns a name into a URL- and filename-safe slug
+///
+/// The name is lowercased, common accents are transliterated (`é` -> `e`,
+/// `ß` -> `ss`), whitespace, underscores and hyphens become single hyphens and
+/// any other punctuation is dropped. Leading and trailing hyphens are trimmed,
+/// so the result may be empty for names made only of punctuation.
+///
+/// # Arguments
+/// * `name` - Name to convert, e.g. an ingredient
+///
+/// # Returns
+/// * `String` - The slug
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::slugify;
+///
+/// assert_eq!(slugify("Crème Fraîche"), "creme-fraiche");
+/// assert_eq!(slugify("salt & pepper"), "salt-pepper");
+/// assert_eq!(slugify("baker's_yeast"), "bakers-yeast");
+/// ```
+pub fn slugify(name: &str) -> String {
+    let mut slug = String::with_capacity(name.len());
+    let mut pending_hyphen = false;
+
+    for c in name.nfd().filter(|c| !is_combining_mark(*c)) {
+        for lower in c.to_lowercase() {
+            if let Some(replacement) = transliterate(lower) {
+                push_word(&mut slug, replacement, &mut pending_hyphen);
+            } else if lower.is_alphanumeric() {
+                let mut buf = [0; 4];
+                push_word(&mut slug, lower.encode_utf8(&mut buf), &mut pending_hyphen);
+            } else if lower.is_whitespace() || lower == '_' || lower == '-' {
+                pending_hyphen = true;
+            }
+        }
+    }
+
+    slug
+}
+
+/// Appends a word fragment, emitting a separating hyphen if one is pending
+fn push_word(slug: &mut String, word: &str, pending_hyphen: &mut bool) {
+    if *pending_hyphen && !slug.is_empty() {
+        slug.push('-');
+    }
+    *pending_hyphen = false;
+    slug.push_str(word);
+}
+
+/// Letters that do not decompose into a base letter plus accents
+fn transliterate(c: char) -> Option<&'static str> {
+    Some(match c {
+        'ß' => "ss",
+        'æ' => "ae",
+        'œ' => "oe",
+        'ø' => "o",
+        'đ' | 'ð' => "d",
+        'ł' => "l",
+        'þ' => "th",
+        'ı' => "i",
+        _ => return None,
+    })
+}
+
+/// Hands out unique slugs, appending `-2`, `-3`, … on collisions
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::SlugSet;
+///
+/// let mut slugs = SlugSet::new();
+/// assert_eq!(slugs.insert("Basil"), "basil");
+/// assert_eq!(slugs.insert("basil!"), "basil-2");
+/// assert_eq!(slugs.insert("???"), "untitled");
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct SlugSet {
+    used: HashSet<String>,
+}
+
+impl SlugSet {
+    /// Creates an empty set
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Slugifies `name` and returns a slug not handed out before
+    ///
+    /// Names whose slug is empty are given the slug `untitled`. Results depend on
+    /// insertion order, so insert names in a stable order for stable output.
+    pub fn insert(&mut self, name: &str) -> String {
+        let mut base = slugify(name);
+        if base.is_empty() {
+            base = "untitled".to_string();
+        }
+
+        let mut slug = base.clone();
+        let mut n = 2;
+        while self.used.contains(&slug) {
+            slug = format!("{}-{}", base, n);
+            n += 1;
+        }
+        self.used.insert(slug.clone());
+        slug
+    }
+
+    /// Returns true if `slug` has already been handed out
+    pub fn contains(&self, slug: &str) -> bool {
+        self.used.contains(slug)
+    }
+}
@@ -0,0 +1,43 @@
+// File: src/spelling.rs
+
+//! Correction of misspelled ingredient names against a dictionary
+
+use std::collections::HashSet;
+
+/// Finds the dictionary spelling of a misspelled name
+///
+/// # Arguments
+/// * `dictionary` - Correctly spelled names, normalized like the index keys
+/// * `name` - Normalized name to check
+///
+/// # Returns
+/// * `Option<&String>` - The only dictionary word one edit away from `name`,
+///   or `None` if `name` is in the dictionary, no word is that close, or
+///   several are
+pub(crate) fn correct<'a>(dictionary: &'a HashSet<String>, name: &str) -> Option<&'a String> {
+    if dictionary.is_empty() || dictionary.contains(name) {
+        return None;
+    }
+    let mut candidates = dictionary.iter().filter(|word| within_one_edit(word, name));
+    let word = candidates.next()?;
+    candidates.next().is_none().then_some(word)
+}
+
+/// Whether one inserted, deleted or replaced character turns `a` into `b`
+pub(crate) fn within_one_edit(a: &str, b: &str) -> bool {
+    if a.len().abs_diff(b.len()) > 4 {
+        return false;
+    }
+    let (a, b): (Vec<char>, Vec<char>) = (a.chars().collect(), b.chars().collect());
+    let (short, long) = if a.len() <= b.len() { (&a, &b) } else { (&b, &a) };
+    if long.len() - short.len() > 1 {
+        return false;
+    }
+    let prefix = short.iter().zip(long.iter()).take_while(|(x, y)| x == y).count();
+    if prefix == long.len() {
+        return true;
+    }
+    // Past the first difference, skip the replaced or inserted character
+    let rest = if short.len() == long.len() { prefix + 1 } else { prefix };
+    short[rest..] == long[prefix + 1..]
+}
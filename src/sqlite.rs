@@ -0,0 +1,90 @@
+// File: src/sqlite.rs
+
+//! SQLite export of the ingredient index (requires the `sqlite` feature)
+
+use crate::url::strip_base_dir;
+use crate::IngredientIndex;
+use anyhow::{Context, Result};
+use rusqlite::{params, Connection};
+use std::borrow::Cow;
+use std::path::Path;
+
+const SCHEMA: &str = "
+    DROP TABLE IF EXISTS recipe_ingredients;
+    DROP TABLE IF EXISTS ingredients;
+    DROP TABLE IF EXISTS recipes;
+
+    CREATE TABLE ingredients (
+        id INTEGER PRIMARY KEY,
+        name TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE recipes (
+        id INTEGER PRIMARY KEY,
+        path TEXT NOT NULL UNIQUE
+    );
+    CREATE TABLE recipe_ingredients (
+        recipe_id INTEGER NOT NULL REFERENCES recipes(id),
+        ingredient_id INTEGER NOT NULL REFERENCES ingredients(id),
+        PRIMARY KEY (recipe_id, ingredient_id)
+    );
+    CREATE INDEX idx_recipe_ingredients_ingredient ON recipe_ingredients(ingredient_id);
+";
+
+impl IngredientIndex {
+    /// Exports the index to a SQLite database file
+    ///
+    /// Creates (or replaces) three tables: `ingredients (id, name)`,
+    /// `recipes (id, path)` with paths relative to the recipes directory, and the
+    /// join table `recipe_ingredients (recipe_id, ingredient_id)`, indexed for
+    /// lookups in both directions.
+    ///
+    /// # Arguments
+    /// * `db_path` - Path of the database file, created if missing
+    ///
+    /// # Example
+    /// ```no_run
+    /// # use cooklang_indexer::IngredientIndex;
+    /// # let index = IngredientIndex::new("./recipes").unwrap();
+    /// index.export_sqlite("ingredients.db").unwrap();
+    /// ```
+    pub fn export_sqlite(&self, db_path: impl AsRef<Path>) -> Result<()> {
+        let db_path = db_path.as_ref();
+        let mut conn = Connection::open(db_path)
+            .with_context(|| format!("opening SQLite database {}", db_path.display()))?;
+        self.write_sqlite(&mut conn)
+    }
+
+    /// Writes the index into an open SQLite connection
+    ///
+    /// Same schema as [`IngredientIndex::export_sqlite`]; useful with in-memory
+    /// databases or connections the caller manages.
+    pub fn write_sqlite(&self, conn: &mut Connection) -> Result<()> {
+        let tx = conn.transaction()?;
+        tx.execute_batch(SCHEMA)?;
+        {
+            let mut insert_ingredient =
+                tx.prepare("INSERT INTO ingredients (name) VALUES (?1)")?;
+            let mut insert_recipe =
+                tx.prepare("INSERT OR IGNORE INTO recipes (path) VALUES (?1)")?;
+            let mut recipe_id = tx.prepare("SELECT id FROM recipes WHERE path = ?1")?;
+            let mut link = tx.prepare(
+                "INSERT OR IGNORE INTO recipe_ingredients (recipe_id, ingredient_id) VALUES (?1, ?2)",
+            )?;
+
+            for ingredient in self.ingredients() {
+                insert_ingredient.execute(params![ingredient])?;
+                let ingredient_id = tx.last_insert_rowid();
+
+                for path in self.index[ingredient].iter() {
+                    let relative = strip_base_dir(path, self.root_of(path)).unwrap_or(Cow::Borrowed(path));
+                    let relative = relative.to_string_lossy();
+                    insert_recipe.execute(params![relative])?;
+                    let id: i64 = recipe_id.query_row(params![relative], |row| row.get(0))?;
+                    link.execute(params![id, ingredient_id])?;
+                }
+            }
+        }
+        tx.commit()?;
+        Ok(())
+    }
+}
@@ -0,0 +1,253 @@
+// File: src/template.rs
+
+//! Rendering the ingredient index through Jinja templates
+
+use crate::html::{
+    alphabet_links, collect_entries, escape_html, filter_input, footer, resolve_options, stylesheet, summary, tag_cloud,
+    IngredientEntry, FILTER_SCRIPT,
+};
+use crate::{HtmlLabels, HtmlOptions, IngredientIndex};
+use anyhow::{anyhow, Result};
+use minijinja::{Environment, Error, ErrorKind, Output, State, Value};
+use serde::Serialize;
+use std::io::Write;
+use std::time::SystemTime;
+
+/// The built-in template, reproducing the layout of [`IngredientIndex::generate_html_with`](crate::IngredientIndex::generate_html_with)
+///
+/// Useful as a starting point for your own templates. It is rendered with the
+/// following context:
+///
+/// * `lang` - the page language from [`HtmlOptions::lang`]
+/// * `labels` - every field of [`HtmlOptions::labels`], e.g. `labels.no_matches`
+/// * `title` - the page title from [`HtmlOptions::title`]
+/// * `heading` - text of the main heading, the title unless [`HtmlOptions::heading`] is set
+/// * `intro_html` - trusted markup to show below the heading, if any
+/// * `container_class` - class of the `<div>` wrapping the index, if any
+/// * `summary` - the summary paragraph when [`HtmlOptions::show_summary`] is on
+/// * `tag_cloud` - the ingredient cloud when [`HtmlOptions::tag_cloud`] is on
+/// * `stylesheet_href` - URL of the external stylesheet, if any
+/// * `css` - contents of the embedded stylesheet, or none when there is nothing to embed
+/// * `show_counts` - whether to print the recipe count next to each ingredient
+/// * `name_heading` - the heading element of ingredient names, `h3` under letter
+///   headings and `h2` otherwise
+/// * `collapsible`, `open_by_default` - whether ingredients are `<details>` elements,
+///   and whether those start expanded
+/// * `filter_box`, `filter_input`, `filter_script` - whether the filter box is
+///   enabled, and the built-in markup and script implementing it
+/// * `letters` - the A–Z navigation as a list of `{label, anchor}`, where `anchor`
+///   is none for letters without ingredients; empty when `alphabet_nav` is off
+/// * `ingredients` - list of `{name, anchor, letter_anchor, letter_heading, count, count_label, recipes}`,
+///   where `letter_anchor` is the navigation target to place before the entry,
+///   `letter_heading` the letter group it starts, if any, `count_label` the
+///   count as worded by the labels, e.g. `3 recipes`, and each recipe is
+///   `{name, url, path, quantity, context, folder}` with `folder` the recipe's top-level folder
+///   and `context` the already escaped step quoting the ingredient, if any
+/// * `total_ingredients`, `total_recipes` - overall totals
+/// * `generated_at` - generation time in RFC 3339 format, e.g. `2024-05-01T12:00:00Z`,
+///   taken from [`HtmlOptions::generated_at`] when set
+/// * `footer` - the footer markup chosen with [`HtmlOptions::footer`], if any
+///
+/// Values are HTML-escaped on output; use the `safe` filter for trusted markup.
+pub const DEFAULT_TEMPLATE: &str = r##"<!DOCTYPE html>
+<html lang="{{ lang }}">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>{{ title }}</title>
+{% if stylesheet_href is not none %}    <link rel="stylesheet" href="{{ stylesheet_href }}">
+{% endif %}{% if css is not none %}    <style>
+{{ css|safe }}    </style>
+{% endif %}</head>
+<body>
+<main>
+    <h1>{{ heading }}</h1>
+{% if intro_html is not none %}{{ intro_html|safe }}{% if intro_html is not endingwith "\n" %}
+{% endif %}{% endif %}{% if container_class is not none %}<div class="{{ container_class }}">
+{% endif %}{% if summary is not none %}{{ summary|safe }}{% endif %}{% if tag_cloud is not none %}{{ tag_cloud|safe }}{% endif %}{% if letters %}    <nav class="alphabet-nav" aria-label="{{ labels.alphabet_nav }}">
+{% for letter in letters %}{% if letter.anchor is not none %}        <a href="#{{ letter.anchor }}">{{ letter.label }}</a>
+{% else %}        <span>{{ letter.label }}</span>
+{% endif %}{% endfor %}    </nav>
+{% endif %}{% if filter_box %}{{ filter_input|safe }}{% endif %}{% for ingredient in ingredients %}{% if ingredient.letter_anchor is not none %}<a class="letter-anchor" id="{{ ingredient.letter_anchor }}"></a>
+{% endif %}{% if ingredient.letter_heading is not none %}<h2 class="letter-heading">{{ ingredient.letter_heading }}</h2>
+{% endif %}{% if collapsible %}<details class="ingredient" id="{{ ingredient.anchor }}"{% if open_by_default %} open{% endif %}>
+    <summary class="ingredient-name"><a href="#{{ ingredient.anchor }}">{{ ingredient.name }}</a> <span class="ingredient-count">({{ ingredient.count_label }})</span></summary>
+{% else %}<section class="ingredient" id="{{ ingredient.anchor }}">
+    <{{ name_heading }} class="ingredient-name"><a href="#{{ ingredient.anchor }}">{{ ingredient.name }}</a>{% if show_counts %} <span class="ingredient-count">({{ ingredient.count_label }})</span>{% endif %}</{{ name_heading }}>
+{% endif %}    <ul class="recipe-list">
+{% for recipe in ingredient.recipes %}        <li><a href="{{ recipe.url }}">{{ recipe.name }}</a>{% if recipe.quantity is not none %} <span class="recipe-quantity">— {{ recipe.quantity }}</span>{% endif %}{% if recipe.context is not none %}<div class="recipe-context">{{ recipe.context|safe }}</div>{% endif %}</li>
+{% endfor %}    </ul>
+{% if collapsible %}</details>{% else %}</section>{% endif %}
+{% endfor %}{% if container_class is not none %}</div>
+{% endif %}</main>
+{% if footer is not none %}{{ footer|safe }}{% endif %}{% if filter_box %}{{ filter_script|safe }}{% endif %}</body>
+</html>
+"##;
+
+/// Everything a template can refer to
+#[derive(Serialize)]
+struct TemplateContext {
+    lang: String,
+    labels: HtmlLabels,
+    title: String,
+    heading: String,
+    intro_html: Option<String>,
+    container_class: Option<String>,
+    summary: Option<String>,
+    tag_cloud: Option<String>,
+    stylesheet_href: Option<String>,
+    css: Option<String>,
+    show_counts: bool,
+    name_heading: &'static str,
+    collapsible: bool,
+    open_by_default: bool,
+    filter_box: bool,
+    filter_input: String,
+    filter_script: &'static str,
+    letters: Vec<TemplateLetter>,
+    ingredients: Vec<TemplateIngredient>,
+    total_ingredients: usize,
+    total_recipes: usize,
+    generated_at: String,
+    footer: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TemplateLetter {
+    label: char,
+    anchor: Option<String>,
+}
+
+#[derive(Serialize)]
+struct TemplateIngredient {
+    name: String,
+    anchor: String,
+    letter_anchor: Option<String>,
+    letter_heading: Option<char>,
+    count: usize,
+    count_label: String,
+    recipes: Vec<TemplateRecipe>,
+}
+
+#[derive(Serialize)]
+struct TemplateRecipe {
+    name: String,
+    url: String,
+    path: String,
+    quantity: Option<String>,
+    context: Option<String>,
+    folder: Option<String>,
+}
+
+impl TemplateIngredient {
+    fn new(entry: IngredientEntry, labels: &HtmlLabels) -> Self {
+        Self {
+            name: entry.name,
+            anchor: entry.anchor,
+            letter_anchor: entry.letter_anchor,
+            letter_heading: entry.letter_heading,
+            count: entry.recipes.len(),
+            count_label: labels.recipe_count(entry.recipes.len()),
+            recipes: entry
+                .recipes
+                .into_iter()
+                .map(|recipe| TemplateRecipe {
+                    name: recipe.name,
+                    url: recipe.url,
+                    path: recipe.path.to_string_lossy().into_owned(),
+                    quantity: recipe.quantity,
+                    context: recipe.context,
+                    folder: recipe.folder,
+                })
+                .collect(),
+        }
+    }
+}
+
+/// Renders the index with `template`, falling back to [`DEFAULT_TEMPLATE`]
+pub(crate) fn render_template(index: &IngredientIndex, template: Option<&str>, options: &HtmlOptions) -> Result<String> {
+    let mut html = Vec::new();
+    write_template(index, template, options, &mut html)?;
+    Ok(String::from_utf8(html)?)
+}
+
+/// Renders the index with `template` straight into `out`, as the template
+/// engine produces it; see [`render_template`]
+pub(crate) fn write_template(
+    index: &IngredientIndex,
+    template: Option<&str>,
+    options: &HtmlOptions,
+    out: &mut dyn Write,
+) -> Result<()> {
+    let mut env = Environment::new();
+    // The built-in writer ends the document without a newline
+    env.set_keep_trailing_newline(false);
+    env.set_formatter(escape_formatter);
+    env.add_template("index.html", template.unwrap_or(DEFAULT_TEMPLATE))
+        .map_err(template_error)?;
+
+    let options = resolve_options(index, options)?;
+    // Link warnings are already logged while collecting the entries
+    let (entries, warnings) = collect_entries(index, &options);
+
+    let generated_at = options.generated_at.unwrap_or_else(SystemTime::now);
+    let context = TemplateContext {
+        lang: options.lang.clone(),
+        labels: options.labels.clone(),
+        title: options.title.clone(),
+        heading: options.heading.clone().unwrap_or_else(|| options.title.clone()),
+        intro_html: options.intro_html.clone(),
+        container_class: options.container_class.clone(),
+        summary: summary(index, &entries, &warnings, &options),
+        tag_cloud: tag_cloud(&entries, &options),
+        stylesheet_href: options.stylesheet_href.clone(),
+        css: stylesheet(&options),
+        show_counts: options.show_counts,
+        name_heading: if options.group_by_letter { "h3" } else { "h2" },
+        collapsible: options.collapsible,
+        open_by_default: options.open_by_default,
+        filter_box: options.filter_box,
+        filter_input: filter_input(&options.labels),
+        filter_script: FILTER_SCRIPT,
+        letters: if options.alphabet_nav {
+            alphabet_links(&entries)
+                .into_iter()
+                .map(|(label, anchor)| TemplateLetter { label, anchor })
+                .collect()
+        } else {
+            Vec::new()
+        },
+        total_ingredients: entries.len(),
+        total_recipes: index.recipes.len(),
+        ingredients: entries.into_iter().map(|entry| TemplateIngredient::new(entry, &options.labels)).collect(),
+        generated_at: humantime::format_rfc3339_seconds(generated_at).to_string(),
+        footer: footer(index, &options),
+    };
+
+    let rendered = env.get_template("index.html")?.render_captured_to(Value::from_serialize(&context), out).map(drop);
+    match rendered {
+        Ok(()) => Ok(()),
+        Err(err) if err.kind() == ErrorKind::WriteFailure => Err(anyhow::Error::new(err).context("failed to write HTML")),
+        Err(err) => Err(template_error(err)),
+    }
+}
+
+/// Escapes every value with the same rules as the built-in writer, unless marked safe
+fn escape_formatter(out: &mut Output, _state: &State, value: &Value) -> Result<(), Error> {
+    let text = if value.is_safe() {
+        value.to_string()
+    } else {
+        escape_html(&value.to_string())
+    };
+    out.write_str(&text)
+        .map_err(|err| Error::new(ErrorKind::WriteFailure, "failed to write output").with_source(err))
+}
+
+/// Describes a template error, pointing at the offending line when known
+fn template_error(err: Error) -> anyhow::Error {
+    let detail = err.detail().map(str::to_string).unwrap_or_else(|| err.kind().to_string());
+    match err.line() {
+        Some(line) => anyhow!("template error on line {}: {}", line, detail),
+        None => anyhow!("template error: {}", detail),
+    }
+}
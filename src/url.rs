@@ -0,0 +1,689 @@
+// File: src/url.rs
+
+//! Conversion of recipe paths into the URLs the generated pages link to
+
+use std::borrow::Cow;
+use std::ffi::OsString;
+use std::fmt;
+use std::fs;
+use std::path::{Component, Path, PathBuf, Prefix};
+use std::sync::Arc;
+
+/// What to do with the recipe file extension when building URLs
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub enum UrlExtension {
+    /// Strip the extension for links below a base URL, where a server such as
+    /// `chef serve` does clean-URL rewriting, and keep it for relative links
+    /// that point straight at the files (the default)
+    #[default]
+    Auto,
+    /// Drop the extension, e.g. `pasta.cook` -> `pasta`
+    Strip,
+    /// Keep the original extension, e.g. `pasta.cook` -> `pasta.cook`
+    Keep,
+    /// Replace the extension, e.g. `Replace("html")` turns `pasta.cook` into `pasta.html`
+    Replace(String),
+}
+
+/// A user-supplied function mapping `(recipe path, base dir)` to a final href
+///
+/// Created with [`UrlMapper::new`] or [`HtmlOptions::url_mapper`](crate::HtmlOptions::url_mapper).
+#[derive(Clone)]
+pub struct UrlMapper(Arc<MapperFn>);
+
+type MapperFn = dyn Fn(&Path, &Path) -> String + Send + Sync;
+
+impl UrlMapper {
+    /// Wraps a closure receiving the recipe path and the base directory
+    pub fn new(mapper: impl Fn(&Path, &Path) -> String + Send + Sync + 'static) -> Self {
+        Self(Arc::new(mapper))
+    }
+
+    /// Applies the mapping to a recipe path
+    pub fn map(&self, path: &Path, base_dir: &Path) -> String {
+        (self.0)(path, base_dir)
+    }
+}
+
+impl fmt::Debug for UrlMapper {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("UrlMapper(..)")
+    }
+}
+
+/// Which kind of URL recipe links are built as
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum UrlMode {
+    /// Links below the base URL, or relative links when there is none (the default)
+    #[default]
+    Standard,
+    /// Absolute `file://` URLs built from the canonicalized recipe paths, so the
+    /// index works when opened from anywhere on the machine. Any base URL is
+    /// ignored, and with the default [`UrlExtension::Auto`] the extension is kept.
+    FileScheme,
+}
+
+/// A base URL used for recipes below one subdirectory of the recipes directory
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::{path_to_url_with, DirBaseUrl, UrlOptions};
+/// use std::path::Path;
+///
+/// let options = UrlOptions {
+///     dir_base_urls: vec![DirBaseUrl::new("desserts", "https://sweets.example.com/r")],
+///     ..UrlOptions::default()
+/// };
+/// let url = path_to_url_with(
+///     Path::new("recipes/desserts/flan.cook"),
+///     "https://example.com/recipes",
+///     Path::new("recipes"),
+///     &options,
+/// );
+/// assert_eq!(url, "https://sweets.example.com/r/flan");
+/// ```
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct DirBaseUrl {
+    /// Directory relative to the recipes directory; the longest matching one wins
+    pub dir: PathBuf,
+    /// Base URL for recipes below `dir`
+    pub base_url: String,
+    /// Whether `dir` is stripped from the recipe path before it is appended to
+    /// `base_url` (`desserts/flan` -> `flan`) or kept
+    pub strip_dir: bool,
+}
+
+impl DirBaseUrl {
+    /// Maps recipes below `dir` to `base_url`, stripping `dir` from their URLs
+    pub fn new(dir: impl Into<PathBuf>, base_url: impl Into<String>) -> Self {
+        Self {
+            dir: dir.into(),
+            base_url: base_url.into(),
+            strip_dir: true,
+        }
+    }
+}
+
+/// Options controlling how recipe paths are turned into URLs
+#[derive(Debug, Clone, Default)]
+pub struct UrlOptions {
+    /// Which kind of URL to build
+    pub mode: UrlMode,
+    /// How the recipe file extension appears in the URL
+    pub extension: UrlExtension,
+    /// Custom mapping used instead of the built-in URL scheme when set
+    ///
+    /// The mapper's output is used verbatim as the href (it is still escaped for
+    /// HTML), so it must do its own percent-encoding.
+    pub mapper: Option<UrlMapper>,
+    /// Per-directory base URLs; recipes outside all of them use the default
+    /// base URL (or a relative link when there is none)
+    pub dir_base_urls: Vec<DirBaseUrl>,
+}
+
+impl UrlOptions {
+    /// Finds the entry of `dir_base_urls` with the longest directory containing
+    /// `relative_path`
+    pub(crate) fn dir_base_url(&self, relative_path: &Path) -> Option<&DirBaseUrl> {
+        self.dir_base_urls
+            .iter()
+            .filter(|entry| relative_path.starts_with(&entry.dir))
+            .max_by_key(|entry| entry.dir.components().count())
+    }
+}
+
+/// Error returned when a base URL is unusable for building recipe links
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InvalidBaseUrl {
+    /// The rejected value
+    pub value: String,
+    /// Everything found wrong with it
+    pub problems: Vec<String>,
+}
+
+impl fmt::Display for InvalidBaseUrl {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "invalid base URL {:?}: {} (expected something like \"https://example.com/recipes\")",
+            self.value,
+            self.problems.join(", ")
+        )
+    }
+}
+
+impl std::error::Error for InvalidBaseUrl {}
+
+/// Checks that a base URL can be used to build recipe links
+///
+/// The URL must use the `http`, `https` or `file` scheme, name a host (except
+/// for `file`) and contain no whitespace. With `lenient` set, a bare host such
+/// as `example.com/recipes` is accepted and `https://` is prepended.
+///
+/// # Arguments
+/// * `base_url` - The base URL to check
+/// * `lenient` - Whether to fix up bare hosts instead of rejecting them
+///
+/// # Returns
+/// * `Result<String, InvalidBaseUrl>` - The (possibly fixed up) base URL, or
+///   everything found wrong with it
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::validate_base_url;
+///
+/// assert!(validate_base_url("https://example.com/recipes", false).is_ok());
+/// assert!(validate_base_url("example.com/recipes", false).is_err());
+/// assert_eq!(
+///     validate_base_url("example.com/recipes", true).unwrap(),
+///     "https://example.com/recipes"
+/// );
+/// ```
+pub fn validate_base_url(base_url: &str, lenient: bool) -> Result<String, InvalidBaseUrl> {
+    let mut problems = Vec::new();
+    let mut value = base_url.to_string();
+
+    if base_url.trim().is_empty() {
+        problems.push("it is empty".to_string());
+    } else if base_url.chars().any(char::is_whitespace) {
+        problems.push("it contains whitespace".to_string());
+    }
+
+    if problems.is_empty() {
+        match base_url.split_once("://") {
+            Some((scheme, rest)) => {
+                let scheme = scheme.to_ascii_lowercase();
+                if !matches!(scheme.as_str(), "http" | "https" | "file") {
+                    problems.push(format!(
+                        "unsupported scheme `{}` (use http, https or file)",
+                        scheme
+                    ));
+                } else if scheme != "file" && rest.split('/').next().unwrap_or("").is_empty() {
+                    problems.push("it has no host".to_string());
+                }
+            }
+            None if lenient && looks_like_host(base_url) => {
+                value = format!("https://{}", base_url);
+            }
+            None => problems.push("it has no scheme (add http:// or https://)".to_string()),
+        }
+    }
+
+    if problems.is_empty() {
+        Ok(value)
+    } else {
+        Err(InvalidBaseUrl { value: base_url.to_string(), problems })
+    }
+}
+
+/// Returns true if the string starts with something resembling a host name
+fn looks_like_host(value: &str) -> bool {
+    let host = value.split('/').next().unwrap_or("");
+    let host = host.split(':').next().unwrap_or("");
+    (host.contains('.') || host == "localhost")
+        && host.chars().all(|c| c.is_alphanumeric() || c == '.' || c == '-')
+}
+
+/// Errors raised when a recipe path cannot be converted to a URL
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum UrlError {
+    /// A directory name or the file stem is not valid UTF-8
+    NonUtf8 {
+        /// The component that failed to decode
+        component: OsString,
+    },
+    /// The path has no file name to build the URL from (e.g. it ends in `..`)
+    MissingFileStem,
+    /// The path (relative to the base directory) contains a root, drive prefix
+    /// or `..` component that has no meaningful URL representation
+    UnsupportedComponent {
+        /// The offending component
+        component: OsString,
+    },
+    /// No relative path leads from the output directory to the recipe, e.g.
+    /// because they are on different drives
+    NoRelativePath,
+    /// The recipe path could not be canonicalized for a `file://` URL
+    Canonicalize {
+        /// The underlying IO error message
+        reason: String,
+    },
+}
+
+impl fmt::Display for UrlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            UrlError::NonUtf8 { component } => {
+                write!(f, "path component {:?} is not valid UTF-8", component)
+            }
+            UrlError::MissingFileStem => write!(f, "path has no file name"),
+            UrlError::UnsupportedComponent { component } => write!(
+                f,
+                "path component {:?} cannot be represented in a URL",
+                component
+            ),
+            UrlError::NoRelativePath => {
+                write!(f, "no relative path leads from the output directory to the recipe")
+            }
+            UrlError::Canonicalize { reason } => {
+                write!(f, "cannot resolve absolute path: {}", reason)
+            }
+        }
+    }
+}
+
+impl std::error::Error for UrlError {}
+
+/// Converts a file path to a URL using the provided base URL
+///
+/// This is the infallible convenience wrapper around [`try_path_to_url`]: path
+/// components that are not valid UTF-8 are converted lossily, unsupported
+/// components are dropped and a missing file name becomes `unknown`.
+///
+/// The recipe extension is stripped; use [`path_to_url_with`] to keep or
+/// replace it.
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `base_url` - Base URL where recipes are hosted
+/// * `base_dir` - Base directory of recipes (used to create relative paths)
+///
+/// # Returns
+/// * `String` - Full URL to the recipe
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::path_to_url;
+/// use std::path::Path;
+/// 
+/// let url = path_to_url(
+///     Path::new("recipes/chicken_pasta.cook"),
+///     "http://example.com/recipes",
+///     Path::new("recipes")
+/// );
+/// assert_eq!(url, "http://example.com/recipes/chicken_pasta");
+/// ```
+pub fn path_to_url(path: &Path, base_url: &str, base_dir: &Path) -> String {
+    path_to_url_with(path, base_url, base_dir, &UrlOptions::default())
+}
+
+/// Converts a file path to a URL using the provided base URL and URL options
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `base_url` - Base URL where recipes are hosted
+/// * `base_dir` - Base directory of recipes (used to create relative paths)
+/// * `options` - Controls how the URL is built, e.g. the file extension
+///
+/// # Returns
+/// * `String` - Full URL to the recipe
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::{path_to_url_with, UrlExtension, UrlOptions};
+/// use std::path::Path;
+///
+/// let path = Path::new("recipes/chicken_pasta.cook");
+/// let base_dir = Path::new("recipes");
+///
+/// // Extensionless (the default), for servers that rewrite clean URLs
+/// let url = path_to_url_with(path, "http://example.com/recipes", base_dir, &UrlOptions::default());
+/// assert_eq!(url, "http://example.com/recipes/chicken_pasta");
+///
+/// // Keep `.cook` for a plain static file server
+/// let options = UrlOptions { extension: UrlExtension::Keep, ..UrlOptions::default() };
+/// let url = path_to_url_with(path, "http://example.com/recipes", base_dir, &options);
+/// assert_eq!(url, "http://example.com/recipes/chicken_pasta.cook");
+///
+/// // Swap in `.html` when recipes are rendered to pages
+/// let options = UrlOptions {
+///     extension: UrlExtension::Replace("html".to_string()),
+///     ..UrlOptions::default()
+/// };
+/// let url = path_to_url_with(path, "http://example.com/recipes", base_dir, &options);
+/// assert_eq!(url, "http://example.com/recipes/chicken_pasta.html");
+/// ```
+pub fn path_to_url_with(path: &Path, base_url: &str, base_dir: &Path, options: &UrlOptions) -> String {
+    build_url(path, base_url, base_dir, options, true)
+        .unwrap_or_else(|_| format!("{}/unknown", base_url.trim_end_matches('/')))
+}
+
+/// Converts a file path to a URL, failing on paths that cannot be represented
+///
+/// Unlike [`path_to_url`], this reports which component of the path could not
+/// be converted instead of silently producing a URL that collides with another
+/// recipe or points nowhere. Each path segment is percent-encoded separately.
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `base_url` - Base URL where recipes are hosted
+/// * `base_dir` - Base directory of recipes (used to create relative paths)
+///
+/// # Returns
+/// * `Result<String, UrlError>` - Full URL to the recipe, or the reason it could
+///   not be built
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::try_path_to_url;
+/// use std::path::Path;
+///
+/// let url = try_path_to_url(
+///     Path::new("recipes/mains/chicken pasta.cook"),
+///     "http://example.com/recipes",
+///     Path::new("recipes")
+/// ).unwrap();
+/// assert_eq!(url, "http://example.com/recipes/mains/chicken%20pasta");
+///
+/// assert!(try_path_to_url(Path::new("recipes/.."), "http://example.com", Path::new("recipes")).is_err());
+/// ```
+pub fn try_path_to_url(path: &Path, base_url: &str, base_dir: &Path) -> Result<String, UrlError> {
+    try_path_to_url_with(path, base_url, base_dir, &UrlOptions::default())
+}
+
+/// Fallible counterpart of [`path_to_url_with`]
+///
+/// When `options.mapper` is set it is used as-is and this never fails.
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `base_url` - Base URL where recipes are hosted
+/// * `base_dir` - Base directory of recipes (used to create relative paths)
+/// * `options` - Controls how the URL is built, e.g. the file extension
+///
+/// # Returns
+/// * `Result<String, UrlError>` - Full URL to the recipe, or the reason it could
+///   not be built
+pub fn try_path_to_url_with(
+    path: &Path,
+    base_url: &str,
+    base_dir: &Path,
+    options: &UrlOptions,
+) -> Result<String, UrlError> {
+    build_url(path, base_url, base_dir, options, false)
+}
+
+/// Builds a link to a recipe relative to the directory the HTML is written to
+///
+/// Useful for opening the index straight from disk: no host is prepended and each
+/// segment is percent-encoded. With the default [`UrlExtension::Auto`] the link
+/// keeps the recipe's extension so it points at the actual file.
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `base_dir` - Base directory of recipes (passed on to a custom mapper)
+/// * `output_dir` - Directory the linking file will be written to
+/// * `options` - Controls how the URL is built, e.g. the file extension
+///
+/// # Returns
+/// * `Result<String, UrlError>` - Relative link to the recipe, or the reason it
+///   could not be built
+///
+/// # Example
+/// ```
+/// use cooklang_indexer::{relative_url, UrlOptions};
+/// use std::path::Path;
+///
+/// let path = Path::new("recipes/mains/chicken pasta.cook");
+/// let options = UrlOptions::default();
+///
+/// // HTML written into the recipes directory
+/// let url = relative_url(path, Path::new("recipes"), Path::new("recipes"), &options).unwrap();
+/// assert_eq!(url, "mains/chicken%20pasta.cook");
+///
+/// // HTML written one level above it
+/// let url = relative_url(path, Path::new("recipes"), Path::new("."), &options).unwrap();
+/// assert_eq!(url, "recipes/mains/chicken%20pasta.cook");
+/// ```
+pub fn relative_url(
+    path: &Path,
+    base_dir: &Path,
+    output_dir: &Path,
+    options: &UrlOptions,
+) -> Result<String, UrlError> {
+    build_relative_url(path, base_dir, output_dir, options, false)
+}
+
+/// Builds an absolute `file://` URL for a recipe from its canonicalized path
+///
+/// Each segment is percent-encoded, and Windows drive paths produce
+/// `file:///C:/...` style URLs. The file must exist. With the default
+/// [`UrlExtension::Auto`] the extension is kept.
+///
+/// # Arguments
+/// * `path` - Path to the recipe file
+/// * `options` - Controls how the URL is built, e.g. the file extension
+///
+/// # Returns
+/// * `Result<String, UrlError>` - The `file://` URL, or the reason it could not be built
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{file_url, UrlOptions};
+/// use std::path::Path;
+///
+/// let url = file_url(Path::new("recipes/chicken pasta.cook"), &UrlOptions::default()).unwrap();
+/// assert!(url.starts_with("file:///"));
+/// assert!(url.ends_with("/recipes/chicken%20pasta.cook"));
+/// ```
+pub fn file_url(path: &Path, options: &UrlOptions) -> Result<String, UrlError> {
+    build_file_url(path, options, false)
+}
+
+/// Shared implementation of the `path_to_url` family
+///
+/// When `lossy` is set, non-UTF-8 components are converted with replacement
+/// characters and unsupported components are skipped instead of failing.
+pub(crate) fn build_url(
+    path: &Path,
+    base_url: &str,
+    base_dir: &Path,
+    options: &UrlOptions,
+    lossy: bool,
+) -> Result<String, UrlError> {
+    if let Some(mapper) = &options.mapper {
+        return Ok(mapper.map(path, base_dir));
+    }
+    if options.mode == UrlMode::FileScheme {
+        return build_file_url(path, options, lossy);
+    }
+
+    // Strip the base directory from the path to get the relative path
+    let stripped = strip_base_dir(path, base_dir);
+    let mut relative_path = stripped.as_deref()
+        .unwrap_or(path);  // Fallback to full path if strip fails
+
+    // A matching per-directory entry overrides the base URL
+    let mut base_url = base_url;
+    if let Some(entry) = options.dir_base_url(relative_path) {
+        base_url = &entry.base_url;
+        if entry.strip_dir {
+            relative_path = relative_path.strip_prefix(&entry.dir).unwrap_or(relative_path);
+        }
+    }
+
+    let keep_extension = matches!(options.extension, UrlExtension::Keep);
+    let segments = url_segments(relative_path, &options.extension, keep_extension, false, lossy)?;
+
+    // Construct the final URL, ensuring no double slashes
+    let base = base_url.trim_end_matches('/');
+    Ok(format!("{}/{}", base, segments.join("/")))
+}
+
+/// Implementation of [`relative_url`], with the same `lossy` handling as [`build_url`]
+pub(crate) fn build_relative_url(
+    path: &Path,
+    base_dir: &Path,
+    output_dir: &Path,
+    options: &UrlOptions,
+    lossy: bool,
+) -> Result<String, UrlError> {
+    if let Some(mapper) = &options.mapper {
+        return Ok(mapper.map(path, base_dir));
+    }
+    if options.mode == UrlMode::FileScheme {
+        return build_file_url(path, options, lossy);
+    }
+    // Recipes in directories with their own base URL get absolute links
+    if let Some(below_base) = strip_base_dir(path, base_dir) {
+        if let Some(entry) = options.dir_base_url(&below_base) {
+            return build_url(path, &entry.base_url, base_dir, options, lossy);
+        }
+    }
+
+    let relative_path = relative_path(output_dir, path).ok_or(UrlError::NoRelativePath)?;
+    let keep_extension = !matches!(options.extension, UrlExtension::Strip);
+    let segments = url_segments(&relative_path, &options.extension, keep_extension, true, lossy)?;
+    Ok(segments.join("/"))
+}
+
+/// Implementation of [`file_url`], with the same `lossy` handling as [`build_url`]
+fn build_file_url(path: &Path, options: &UrlOptions, lossy: bool) -> Result<String, UrlError> {
+    let absolute = fs::canonicalize(path)
+        .map_err(|err| UrlError::Canonicalize { reason: err.to_string() })?;
+
+    // The drive or UNC prefix decides the host and the leading segment; the rest
+    // of the path is encoded like any other URL
+    let mut host = String::new();
+    let mut drive = None;
+    let mut rest = PathBuf::new();
+    for component in absolute.components() {
+        match component {
+            Component::Prefix(prefix) => match prefix.kind() {
+                Prefix::Disk(letter) | Prefix::VerbatimDisk(letter) => {
+                    drive = Some(format!("{}:", letter as char));
+                }
+                Prefix::UNC(server, share) | Prefix::VerbatimUNC(server, share) => {
+                    host = server.to_string_lossy().into_owned();
+                    rest.push(share);
+                }
+                _ => {
+                    return Err(UrlError::UnsupportedComponent {
+                        component: prefix.as_os_str().to_os_string(),
+                    })
+                }
+            },
+            Component::RootDir => {}
+            other => rest.push(other),
+        }
+    }
+
+    let keep_extension = !matches!(options.extension, UrlExtension::Strip);
+    let segments = url_segments(&rest, &options.extension, keep_extension, false, lossy)?;
+    let drive = drive.map(|d| format!("/{}", d)).unwrap_or_default();
+    Ok(format!("file://{}{}/{}", host, drive, segments.join("/")))
+}
+
+/// Splits a relative recipe path into percent-encoded URL segments
+///
+/// `keep_extension` decides what [`UrlExtension::Auto`] and [`UrlExtension::Keep`]
+/// resolve to, and `allow_parent` permits leading `..` segments.
+fn url_segments(
+    relative_path: &Path,
+    extension: &UrlExtension,
+    keep_extension: bool,
+    allow_parent: bool,
+    lossy: bool,
+) -> Result<Vec<String>, UrlError> {
+    let to_segment = |component: &std::ffi::OsStr| -> Result<String, UrlError> {
+        match component.to_str() {
+            Some(segment) => Ok(segment.to_string()),
+            None if lossy => Ok(component.to_string_lossy().into_owned()),
+            None => Err(UrlError::NonUtf8 { component: component.to_os_string() }),
+        }
+    };
+
+    // Collect the parent directories, excluding the base directory
+    let mut segments = Vec::new();
+    for component in relative_path.parent().into_iter().flat_map(Path::components) {
+        match component {
+            Component::Normal(name) => segments.push(to_segment(name)?),
+            Component::CurDir => {}
+            Component::ParentDir if allow_parent => segments.push("..".to_string()),
+            _ if lossy => {}
+            other => {
+                return Err(UrlError::UnsupportedComponent {
+                    component: other.as_os_str().to_os_string(),
+                })
+            }
+        }
+    }
+
+    // Get the stem (filename without extension) and apply the extension policy
+    let file_stem = relative_path.file_stem().ok_or(UrlError::MissingFileStem)?;
+    let file_name = match extension {
+        UrlExtension::Replace(extension) => {
+            format!("{}.{}", to_segment(file_stem)?, extension.trim_start_matches('.'))
+        }
+        _ if keep_extension => to_segment(relative_path.file_name().unwrap_or(file_stem))?,
+        _ => to_segment(file_stem)?,
+    };
+    segments.push(file_name);
+
+    Ok(segments.iter().map(|s| urlencoding::encode(s).into_owned()).collect())
+}
+
+/// Computes the path leading from directory `from` to `to`, lexically
+///
+/// Both paths are made absolute against the current directory first. Returns
+/// `None` when they share no common root (e.g. different Windows drives).
+fn relative_path(from: &Path, to: &Path) -> Option<PathBuf> {
+    let from = normalize_path(&std::path::absolute(from).ok()?);
+    let to = normalize_path(&std::path::absolute(to).ok()?);
+
+    let from: Vec<_> = from.components().collect();
+    let to: Vec<_> = to.components().collect();
+    let common = from.iter().zip(&to).take_while(|(a, b)| a == b).count();
+    if common == 0 {
+        return None;
+    }
+
+    let mut relative = PathBuf::new();
+    for _ in common..from.len() {
+        relative.push("..");
+    }
+    relative.extend(&to[common..]);
+    Some(relative)
+}
+
+/// A recipe path relative to the recipes directory, joined with `/` on every
+/// platform, for exports; paths outside it are kept whole
+pub(crate) fn export_path(path: &Path, base_dir: &Path) -> String {
+    let relative = strip_base_dir(path, base_dir).unwrap_or(Cow::Borrowed(path));
+    let components: Vec<_> = relative.iter().map(|part| part.to_string_lossy()).collect();
+    components.join("/")
+}
+
+/// Strips the recipes directory from a recipe path
+///
+/// When the two are spelled differently, e.g. one absolute and the other
+/// relative or going through `..`, both are canonicalized first; paths that do
+/// not exist are made absolute and normalized lexically instead. Returns `None`
+/// when the recipe is not below `base_dir` at all.
+pub(crate) fn strip_base_dir<'a>(path: &'a Path, base_dir: &Path) -> Option<Cow<'a, Path>> {
+    if let Ok(relative) = path.strip_prefix(base_dir) {
+        return Some(Cow::Borrowed(relative));
+    }
+    let resolve = |path: &Path| {
+        fs::canonicalize(path)
+            .ok()
+            .or_else(|| std::path::absolute(path).ok().map(|path| normalize_path(&path)))
+    };
+    let (path, base_dir) = (resolve(path)?, resolve(base_dir)?);
+    path.strip_prefix(base_dir).ok().map(|relative| Cow::Owned(relative.to_path_buf()))
+}
+
+/// Resolves `.` and `..` components without touching the filesystem
+pub(crate) fn normalize_path(path: &Path) -> PathBuf {
+    let mut normalized = PathBuf::new();
+    for component in path.components() {
+        match component {
+            Component::CurDir => {}
+            Component::ParentDir => {
+                normalized.pop();
+            }
+            other => normalized.push(other),
+        }
+    }
+    normalized
+}
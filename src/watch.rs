@@ -0,0 +1,140 @@
+// File: src/watch.rs
+
+//! Watching a recipes directory for changes, to keep an index up to date
+
+use anyhow::{Context, Result};
+use notify::{EventKind, RecommendedWatcher, RecursiveMode, Watcher};
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender};
+use std::time::Duration;
+
+/// Reports the files changing below a recipes directory, a burst of changes
+/// at a time
+///
+/// Saving a file often takes several file system events, e.g. a write to a
+/// temporary file followed by a rename, so changes are only reported once
+/// none came in for the [debounce](RecipeWatcher::debounce) period. The
+/// paths are spelled as the index scan finds them, ready for
+/// [`IngredientIndex::refresh_files`](crate::IngredientIndex::refresh_files).
+///
+/// # Example
+/// ```no_run
+/// use cooklang_indexer::{IngredientIndex, RecipeWatcher};
+///
+/// let mut index = IngredientIndex::new("./recipes").unwrap();
+/// let watcher = RecipeWatcher::new("./recipes").unwrap();
+/// while let Some(paths) = watcher.wait() {
+///     let report = index.refresh_files(&paths);
+///     println!("{} recipes updated", report.updated.len());
+/// }
+/// ```
+pub struct RecipeWatcher {
+    /// Kept alive for as long as events are wanted
+    _watcher: RecommendedWatcher,
+    sender: Sender<Message>,
+    events: Receiver<Message>,
+    /// The recipes directory as given
+    base_dir: PathBuf,
+    /// The recipes directory, canonicalized, as event paths are spelled
+    root: PathBuf,
+    debounce: Duration,
+}
+
+/// Stops a [`RecipeWatcher`] from another thread, e.g. a Ctrl-C handler
+#[derive(Clone)]
+pub struct WatchStopper(Sender<Message>);
+
+impl WatchStopper {
+    /// Makes [`RecipeWatcher::wait`] return `None`
+    pub fn stop(&self) {
+        // Nothing to stop once the watcher is gone
+        let _ = self.0.send(Message::Stop);
+    }
+}
+
+enum Message {
+    Changed(Vec<PathBuf>),
+    Stop,
+}
+
+impl RecipeWatcher {
+    /// Starts watching a recipes directory and its subdirectories
+    ///
+    /// # Arguments
+    /// * `recipes_dir` - The directory the index was built from
+    ///
+    /// # Returns
+    /// * `Result<RecipeWatcher>` - The watcher, or an error if the directory
+    ///   cannot be watched
+    pub fn new(recipes_dir: impl AsRef<Path>) -> Result<Self> {
+        let base_dir = recipes_dir.as_ref().to_path_buf();
+        let root = fs::canonicalize(&base_dir).with_context(|| format!("failed to open {}", base_dir.display()))?;
+        let (sender, events) = mpsc::channel();
+        let events_sender = sender.clone();
+        let mut watcher = notify::recommended_watcher(move |event: notify::Result<notify::Event>| match event {
+            Ok(event) if !matches!(event.kind, EventKind::Access(_)) => {
+                let _ = events_sender.send(Message::Changed(event.paths));
+            }
+            Ok(_) => {}
+            Err(err) => log::warn!("file watcher error: {}", err),
+        })
+        .context("failed to start the file watcher")?;
+        watcher
+            .watch(&root, RecursiveMode::Recursive)
+            .with_context(|| format!("failed to watch {}", base_dir.display()))?;
+        Ok(Self { _watcher: watcher, sender, events, base_dir, root, debounce: Duration::from_millis(200) })
+    }
+
+    /// Sets how long changes must pause before they are reported, 200ms by default
+    pub fn debounce(mut self, debounce: Duration) -> Self {
+        self.debounce = debounce;
+        self
+    }
+
+    /// A handle to stop the watcher with
+    pub fn stopper(&self) -> WatchStopper {
+        WatchStopper(self.sender.clone())
+    }
+
+    /// Blocks until files change, then until the changes pause
+    ///
+    /// # Returns
+    /// * `Option<Vec<PathBuf>>` - The changed files and directories, sorted,
+    ///   or `None` once [stopped](WatchStopper::stop)
+    pub fn wait(&self) -> Option<Vec<PathBuf>> {
+        loop {
+            let paths = self.next_burst()?;
+            if !paths.is_empty() {
+                return Some(paths);
+            }
+        }
+    }
+
+    /// The paths of the next burst of events below the recipes directory
+    fn next_burst(&self) -> Option<Vec<PathBuf>> {
+        let mut paths = match self.events.recv() {
+            Ok(Message::Changed(paths)) => paths,
+            Ok(Message::Stop) | Err(_) => return None,
+        };
+        loop {
+            match self.events.recv_timeout(self.debounce) {
+                Ok(Message::Changed(more)) => paths.extend(more),
+                Ok(Message::Stop) | Err(RecvTimeoutError::Disconnected) => return None,
+                Err(RecvTimeoutError::Timeout) => break,
+            }
+        }
+
+        let mut paths: Vec<PathBuf> = paths
+            .into_iter()
+            .filter_map(|path| {
+                let relative = path.strip_prefix(&self.root).ok()?;
+                // Events on the directory itself say nothing about its recipes
+                (!relative.as_os_str().is_empty()).then(|| self.base_dir.join(relative))
+            })
+            .collect();
+        paths.sort();
+        paths.dedup();
+        Some(paths)
+    }
+}
@@ -0,0 +1,54 @@
+// tests/atomic_test.rs
+use cooklang_indexer::{write_atomically, write_atomically_with};
+use std::fs;
+use std::io::{self, Write};
+use std::path::Path;
+
+/// Names of the files in `dir`, sorted
+fn files(dir: &Path) -> Vec<String> {
+    let mut names: Vec<_> = fs::read_dir(dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().file_name().to_string_lossy().into_owned())
+        .collect();
+    names.sort();
+    names
+}
+
+#[test]
+fn test_write_atomically_creates_and_replaces() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.html");
+
+    write_atomically(&path, "first").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "first");
+    write_atomically(&path, b"second").unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "second");
+    assert_eq!(files(dir.path()), ["index.html"]);
+}
+
+#[test]
+fn test_interrupted_write_keeps_the_old_file() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.html");
+    fs::write(&path, "<html>complete</html>").unwrap();
+
+    // Fails halfway, as a full disk would
+    let err = write_atomically_with(&path, |file| {
+        file.write_all(b"<html>trunc")?;
+        Err(io::Error::other("disk full"))
+    })
+    .unwrap_err();
+
+    assert_eq!(fs::read_to_string(&path).unwrap(), "<html>complete</html>");
+    assert_eq!(files(dir.path()), ["index.html"]);
+    let message = format!("{:#}", err);
+    assert!(message.contains("index.html") && message.contains("disk full"), "{}", message);
+}
+
+#[test]
+fn test_write_into_missing_directory_fails() {
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("missing").join("index.html");
+    assert!(write_atomically(&path, "page").is_err());
+    assert!(!path.exists());
+}
@@ -0,0 +1,397 @@
+// tests/builder_test.rs
+mod common;
+
+use cooklang_indexer::{IngredientIndex, RecipeProblems};
+
+#[test]
+fn test_min_ingredient_len_drops_noise() {
+    let dir = common::recipe_dir(&[("salad.cook", "Drizzle @oil{1%tbsp} over @a{} the leaves.")]);
+
+    let index = IngredientIndex::builder(dir.path())
+        .min_ingredient_len(2)
+        .build()
+        .unwrap();
+    assert!(index.get_recipes_for_ingredient("a").is_none());
+    assert!(index.get_recipes_for_ingredient("oil").is_some());
+
+    // The default threshold keeps single-character names
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert!(index.get_recipes_for_ingredient("a").is_some());
+}
+
+#[test]
+fn test_custom_normalizer_merges_names() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Blend @fresh basil{1%bunch} with @garlic{1%clove}."),
+        ("caprese.cook", "Layer @tomato{2} and @Basil{4%leaves}."),
+    ]);
+
+    let index = IngredientIndex::builder(dir.path())
+        .normalizer(|name| {
+            let name = name.trim().to_lowercase();
+            name.strip_prefix("fresh ").unwrap_or(&name).to_string()
+        })
+        .build()
+        .unwrap();
+
+    assert_eq!(index.get_recipes_for_ingredient("basil").unwrap().len(), 2);
+    assert!(index.get_recipes_for_ingredient("fresh basil").is_none());
+}
+
+#[test]
+fn test_default_normalizer_unicode_case() {
+    let dir = common::recipe_dir(&[
+        ("a.cook", "Order a @CAFÉ{1} and some @ΣΟΥΒΛΑΚΙ{2}."),
+        // "café" spelled with a combining accent
+        ("b.cook", "Order a @cafe\u{301}{1} and some @σουβλακι{2}."),
+        ("c.cook", "Add @ЧЕСНОК{1%clove} and @İncir{3}."),
+        ("d.cook", "Add @чеснок{2%cloves} and @incir{1}."),
+    ]);
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.get_recipes_for_ingredient("café").unwrap().len(), 2);
+    assert_eq!(index.get_recipes_for_ingredient("σουβλακι").unwrap().len(), 2);
+    assert_eq!(index.get_recipes_for_ingredient("чеснок").unwrap().len(), 2);
+    // Without a locale, `İ` lowercases to `i` plus a combining dot
+    assert_eq!(index.get_recipes_for_ingredient("i\u{307}ncir").unwrap().len(), 1);
+    assert_eq!(index.get_recipes_for_ingredient("incir").unwrap().len(), 1);
+}
+
+#[test]
+fn test_case_fold_merges_across_locales() {
+    let dir = common::recipe_dir(&[
+        ("a.cook", "Add @İncir{3}, @KAFFEESAHNE{1} and @Weißwurst{2}."),
+        ("b.cook", "Add @ıncır{1}, @kaffeesahne{1} and @WEISSWURST{1}."),
+        ("c.cook", "Add @incir{1} and @CAFÉ{1}."),
+        ("d.cook", "Add @café{1} but not @cafe{1}."),
+    ]);
+
+    let index = IngredientIndex::builder(dir.path()).case_fold().build().unwrap();
+    assert_eq!(index.ingredients(), ["cafe", "café", "incir", "kaffeesahne", "weisswurst"]);
+    assert_eq!(index.get_recipes_for_ingredient("incir").unwrap().len(), 3);
+    assert_eq!(index.get_recipes_for_ingredient("weisswurst").unwrap().len(), 2);
+    assert_eq!(index.get_recipes_for_ingredient("café").unwrap().len(), 2);
+}
+
+#[test]
+fn test_crlf_line_endings() {
+    let dir = common::recipe_dir(&[(
+        "bread.cook",
+        "Sift the @flour\r\nAdd @water{300%ml} and @salt{1%tsp}\r\nKnead well.\r\n",
+    )]);
+
+    // A normalizer that doesn't trim exposes anything the parser leaves behind
+    let index = IngredientIndex::builder(dir.path())
+        .normalizer(|name| name.to_lowercase())
+        .build()
+        .unwrap();
+
+    assert!(index.get_recipes_for_ingredient("flour").is_some());
+    assert!(index.ingredients().iter().all(|name| !name.contains('\r')));
+}
+
+#[test]
+fn test_strict_mode_reports_all_problems() {
+    let dir = common::recipe_dir(&[
+        ("bread.cook", "Mix @flour{500%g} and @water{300%g}."),
+        ("broken.cook", "Add @salt{1%tsp and stir.\nTop with @ {} and a lone @"),
+    ]);
+
+    // Best effort by default
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert!(index.get_recipes_for_ingredient("flour").is_some());
+
+    let err = IngredientIndex::builder(dir.path()).strict(true).build().unwrap_err();
+    let problems = &err.downcast_ref::<RecipeProblems>().unwrap().problems;
+    assert_eq!(problems.len(), 3, "{:?}", problems);
+    assert!(problems.iter().all(|p| p.contains("broken.cook: line")));
+    assert!(problems[0].ends_with("line 1: unclosed `{` after @salt"));
+    assert!(problems[1].ends_with("line 2: empty ingredient name"));
+    assert!(problems[2].ends_with("line 2: `@` without an ingredient name"));
+}
+
+#[test]
+fn test_blank_names_are_never_indexed() {
+    let dir = common::recipe_dir(&[("soup.cook", "Pour @{2%cups} of water over @leeks{2} and @ {1}.")]);
+
+    for index in [
+        IngredientIndex::new(dir.path()).unwrap(),
+        IngredientIndex::builder(dir.path()).min_ingredient_len(0).build().unwrap(),
+        IngredientIndex::builder(dir.path()).normalizer(str::to_string).build().unwrap(),
+    ] {
+        assert!(index.get_recipes_for_ingredient("").is_none());
+        assert!(index.ingredients().iter().all(|name| !name.trim().is_empty()));
+        assert_eq!(index.ingredients().len(), 1);
+    }
+
+    // Upserting takes the same path
+    let mut index = IngredientIndex::builder(dir.path()).min_ingredient_len(0).build().unwrap();
+    index.upsert_file(dir.path().join("soup.cook")).unwrap();
+    assert!(index.get_recipes_for_ingredient("").is_none());
+}
+
+#[test]
+fn test_low_memory_gives_identical_output() {
+    use cooklang_indexer::HtmlOptions;
+
+    let full = IngredientIndex::new("./test_recipes").unwrap();
+    let lean = IngredientIndex::builder("./test_recipes").low_memory(true).build().unwrap();
+
+    assert_eq!(lean.ingredients(), full.ingredients());
+    assert_eq!(lean.recipes_by_complexity(), full.recipes_by_complexity());
+    assert_eq!(lean.intra_recipe_duplicates(), full.intra_recipe_duplicates());
+    assert_eq!(
+        lean.generate_html("http://example.com/r").unwrap(),
+        full.generate_html("http://example.com/r").unwrap()
+    );
+    let options = HtmlOptions { show_quantities: true, ..HtmlOptions::default() };
+    assert_eq!(lean.generate_html_with(&options).unwrap(), full.generate_html_with(&options).unwrap());
+    for ingredient in full.ingredients() {
+        assert_eq!(lean.get_recipes_for_ingredient(ingredient), full.get_recipes_for_ingredient(ingredient));
+    }
+}
+
+#[test]
+fn test_cooklang_extension() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Blend @basil{1%bunch}."),
+        ("caprese.cooklang", "Layer @tomato{2} and @basil{4%leaves}."),
+        ("notes.txt", "Remember @saffron{}."),
+    ]);
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(
+        index.get_recipes_for_ingredient("basil").unwrap(),
+        &[dir.path().join("caprese.cooklang"), dir.path().join("pesto.cook")]
+    );
+    assert!(index.get_recipes_for_ingredient("saffron").is_none());
+
+    let index = IngredientIndex::builder(dir.path()).extensions(&["cook", ".txt"]).build().unwrap();
+    assert!(index.get_recipes_for_ingredient("tomato").is_none());
+    assert_eq!(index.get_recipes_for_ingredient("saffron").unwrap().len(), 1);
+}
+
+#[test]
+fn test_ampersand_merging() {
+    use cooklang_indexer::Ampersand;
+
+    let dir = common::recipe_dir(&[
+        ("steak.cook", "Season with @salt & pepper{}."),
+        ("eggs.cook", "Season with @Salt and Pepper{} and top with @m&ms{}."),
+        ("lunch.cook", "Serve @sandwich{} with @salt&pepper chips{}."),
+    ]);
+
+    // Kept apart by default
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert!(index.get_recipes_for_ingredient("salt & pepper").is_some());
+    assert!(index.get_recipes_for_ingredient("salt and pepper").is_some());
+
+    let index = IngredientIndex::builder(dir.path()).ampersand(Ampersand::ToAnd).build().unwrap();
+    assert_eq!(index.get_recipes_for_ingredient("salt and pepper").unwrap().len(), 2);
+    assert!(index.get_recipes_for_ingredient("salt & pepper").is_none());
+    // Glued forms are left alone
+    assert!(index.get_recipes_for_ingredient("m&ms").is_some());
+    assert!(index.get_recipes_for_ingredient("salt&pepper chips").is_some());
+    assert!(index.get_recipes_for_ingredient("sandwich").is_some());
+    // Lookups go through the same rewrite
+    assert_eq!(index.recipes_without("Salt & Pepper").len(), 1);
+
+    let index = IngredientIndex::builder(dir.path()).ampersand(Ampersand::ToAmpersand).build().unwrap();
+    assert_eq!(index.get_recipes_for_ingredient("salt & pepper").unwrap().len(), 2);
+    assert!(index.get_recipes_for_ingredient("sandwich").is_some());
+}
+
+#[test]
+fn test_split_on_comma() {
+    let dir = common::recipe_dir(&[
+        ("steak.cook", "Season with @salt, pepper{1%tsp}."),
+        ("eggs.cook", "Finish with @salt{}."),
+    ]);
+
+    // One name by default
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.ingredients(), ["salt", "salt, pepper"]);
+
+    let index = IngredientIndex::builder(dir.path()).split_on_comma(true).build().unwrap();
+    assert_eq!(index.ingredients(), ["pepper", "salt"]);
+    assert_eq!(index.get_recipes_for_ingredient("salt").unwrap().len(), 2);
+    assert_eq!(index.get_recipes_for_ingredient("pepper").unwrap(), &[dir.path().join("steak.cook")]);
+}
+
+#[test]
+fn test_strip_prefixes() {
+    let dir = common::recipe_dir(&[
+        ("soup.cook", "Sweat the @Chopped Onion{1} with @fresh chopped parsley{}."),
+        ("salad.cook", "Slice an @onion{1} and add @chopped{} nuts and @groundnut oil{}."),
+    ]);
+
+    // Nothing is stripped by default
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.ingredients(), ["chopped", "chopped onion", "fresh chopped parsley", "groundnut oil", "onion"]);
+
+    let index = IngredientIndex::builder(dir.path())
+        .strip_prefixes(&["chopped ", "Fresh", "ground"])
+        .build()
+        .unwrap();
+    assert_eq!(index.ingredients(), ["chopped", "groundnut oil", "onion", "parsley"]);
+    assert_eq!(index.get_recipes_for_ingredient("onion").unwrap().len(), 2);
+    assert_eq!(index.options().stripped_prefixes, ["chopped", "fresh", "ground"]);
+}
+
+#[test]
+fn test_strip_prefixes_applies_to_spelling_dictionary() {
+    let dir = common::recipe_dir(&[("salad.cook", "Toss @Fresh parsly{} with @chopped Tomatos{2}.")]);
+    let index = IngredientIndex::builder(dir.path())
+        .strip_prefixes(&["Fresh ", "CHOPPED"])
+        .spelling_dictionary(["fresh parsley".to_string(), "Chopped tomatoes".to_string()].into())
+        .build()
+        .unwrap();
+    // The dictionary words lose their prefixes just like the names in recipes
+    assert_eq!(index.ingredients(), ["parsley", "tomatoes"]);
+}
+
+#[test]
+fn test_exclude_categories() {
+    use cooklang_indexer::Aisles;
+
+    let dir = common::recipe_dir(&[
+        ("bread.cook", "Mix @flour{500%g} with @Water{300%ml} and @salt{}."),
+        ("broth.cook", "Simmer @bones{1%kg} in @water{2%l}."),
+    ]);
+    let aisles = Aisles::parse("[Pantry Staple]\nwater\n[baking]\nflour\n");
+
+    let index = IngredientIndex::builder(dir.path()).aisles(aisles.clone()).build().unwrap();
+    assert_eq!(index.get_recipes_for_ingredient("water").unwrap().len(), 2);
+
+    let index = IngredientIndex::builder(dir.path())
+        .exclude_categories(&["pantry staple"])
+        .aisles(aisles)
+        .build()
+        .unwrap();
+    assert_eq!(index.ingredients(), ["bones", "flour", "salt"]);
+    let list = index.shopping_list(&[dir.path().join("broth.cook")]);
+    assert_eq!(list.to_string(), "- bones: 1 kg\n");
+    assert_eq!(index.options().excluded_categories, ["pantry staple"]);
+
+    // Nothing is excluded without aisles to look the categories up in
+    let index = IngredientIndex::builder(dir.path()).exclude_categories(&["pantry staple"]).build().unwrap();
+    assert!(index.get_recipes_for_ingredient("water").is_some());
+}
+
+#[test]
+fn test_sniff_content() {
+    let dir = common::recipe_dir(&[
+        ("toast.cook", "Toast @bread{}."),
+        ("pancakes", ">> servings: 4\nWhisk @flour{250%g} with @eggs{2}."),
+        ("stew", "Brown the meat in a #dutch oven{}, then simmer for ~{2%hours} with @carrots{3}."),
+        ("README", "# My recipes\nQuestions? Mail me@example.com or tag #cooking."),
+        (".draft", "Add @saffron{}."),
+        ("notes.txt", "Try @sumac{} next time."),
+    ]);
+    std::fs::write(dir.path().join("blob"), b"\0\x01@yeast{}").unwrap();
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.ingredients(), ["bread"]);
+
+    let index = IngredientIndex::builder(dir.path()).sniff_content(true).build().unwrap();
+    assert_eq!(index.ingredients(), ["bread", "carrots", "eggs", "flour"]);
+    assert_eq!(index.get_recipes_for_ingredient("flour").unwrap(), &[dir.path().join("pancakes")]);
+}
+
+#[test]
+fn test_ingredient_order() {
+    use cooklang_indexer::{HtmlOptions, IngredientOrder, IngredientSort};
+
+    let dir = common::recipe_dir(&[
+        ("b_pasta.cook", "Boil @pasta{} in salted @water{}, then add @basil{} and @salt{}."),
+        ("a_toast.cook", "Toast @bread{}, rub with @garlic{} and drizzle with @oil{} and @salt{}."),
+        ("c_soup.cook", "Simmer @water{}, @garlic{} and @salt{}."),
+    ]);
+    let order = |order| {
+        let index = IngredientIndex::builder(dir.path()).ingredient_order(order).build().unwrap();
+        index.ingredients().into_iter().cloned().collect::<Vec<_>>()
+    };
+
+    assert_eq!(order(IngredientOrder::Alphabetical), ["basil", "bread", "garlic", "oil", "pasta", "salt", "water"]);
+    // Recipes are read by path, each from top to bottom
+    assert_eq!(order(IngredientOrder::FirstSeen), ["bread", "garlic", "oil", "salt", "pasta", "water", "basil"]);
+    assert_eq!(order(IngredientOrder::ByFrequency), ["salt", "garlic", "water", "basil", "bread", "oil", "pasta"]);
+
+    // The HTML follows the index unless told otherwise
+    let index = IngredientIndex::builder(dir.path()).ingredient_order(IngredientOrder::FirstSeen).build().unwrap();
+    let position = |html: &str, name: &str| html.find(&format!("id=\"{}\"", name)).unwrap();
+    let html = index.generate_html_with(&HtmlOptions::default()).unwrap();
+    assert!(position(&html, "pasta") < position(&html, "basil"));
+    let options = HtmlOptions { sort: IngredientSort::Alphabetical, ..HtmlOptions::default() };
+    let html = index.generate_html_with(&options).unwrap();
+    assert!(position(&html, "basil") < position(&html, "pasta"));
+}
+
+#[test]
+fn test_spelling_dictionary() {
+    let dir = common::recipe_dir(&[
+        ("bread.cook", "Knead @flur{500%g} with @watr{300%ml} and @Salt{}."),
+        ("cake.cook", "Mix @flour{200%g}, @pear{} and @pean{}."),
+        ("stew.cook", "Simmer @beens{} with @saffron{}."),
+    ]);
+    let dictionary = ["Flour", "water", "salt", "pear", "bean", "beans"].iter().map(|w| w.to_string()).collect();
+    let index = IngredientIndex::builder(dir.path()).spelling_dictionary(dictionary).build().unwrap();
+
+    assert_eq!(index.get_recipes_for_ingredient("flour").unwrap().len(), 2);
+    assert!(index.get_recipes_for_ingredient("water").is_some());
+    // Close to several words, or to none: kept as written
+    assert!(index.get_recipes_for_ingredient("pean").is_some());
+    let names: Vec<&String> = index.ingredients();
+    assert!(index.get_recipes_for_ingredient("beens").is_none());
+    assert!(index.get_recipes_for_ingredient("beans").is_some());
+    assert!(names.iter().any(|name| *name == "saffron"));
+    assert!(!names.iter().any(|name| *name == "flur"));
+}
+
+#[test]
+fn test_options_reflect_builder() {
+    use cooklang_indexer::{Ampersand, IngredientOrder};
+
+    let dir = common::recipe_dir(&[("salad.cook", "Toss @Lettuce{} with @oil{}.")]);
+    let index = IngredientIndex::builder(dir.path())
+        .min_ingredient_len(3)
+        .ampersand(Ampersand::ToAnd)
+        .ingredient_order(IngredientOrder::ByFrequency)
+        .extensions(&[".cook", "txt"])
+        .follow_links(false)
+        .low_memory(true)
+        .split_on_comma(true)
+        .sniff_content(true)
+        .spelling_dictionary(["Lettuce".to_string()].into())
+        .build()
+        .unwrap();
+
+    let options = index.options();
+    assert_eq!(options.min_ingredient_len, 3);
+    assert_eq!(options.ampersand, Ampersand::ToAnd);
+    assert_eq!(options.ingredient_order, IngredientOrder::ByFrequency);
+    assert_eq!(options.extensions, ["cook", "txt"]);
+    assert!(!options.follow_links);
+    assert!(options.low_memory);
+    assert!(options.split_on_comma);
+    assert!(options.sniff_content);
+    assert!(!options.strict);
+    // Stored as normalized for matching
+    assert!(options.spelling_dictionary.contains("lettuce"));
+
+    assert!(IngredientIndex::new(dir.path()).unwrap().options().follow_links);
+}
+
+#[cfg(unix)]
+#[test]
+fn test_follow_links() {
+    let dir = common::recipe_dir(&[("salad.cook", "Toss @lettuce{}.")]);
+    let shared = common::recipe_dir(&[("soup.cook", "Simmer @leeks{}.")]);
+    std::os::unix::fs::symlink(shared.path(), dir.path().join("shared")).unwrap();
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert!(index.get_recipes_for_ingredient("leeks").is_some());
+    let index = IngredientIndex::builder(dir.path()).follow_links(false).build().unwrap();
+    assert!(index.get_recipes_for_ingredient("leeks").is_none());
+    assert!(index.get_recipes_for_ingredient("lettuce").is_some());
+}
@@ -0,0 +1,358 @@
+// tests/cli_test.rs
+mod common;
+
+use std::process::Command;
+
+fn indexer() -> Command {
+    let mut command = Command::new(env!("CARGO_BIN_EXE_cooklang-indexer"));
+    command.env("RUST_LOG", "off");
+    command
+}
+
+#[test]
+fn test_query_prints_recipes() {
+    let output = indexer().args(["query", "flour", "test_recipes"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "test_recipes/pancakes.cook\n");
+
+    let output = indexer().args(["query", "garlic", "test_recipes"]).output().unwrap();
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "test_recipes/mains/chicken_curry.cook\ntest_recipes/soups/tomato-soup.cook\n"
+    );
+
+    // Looked up like the names in recipes are indexed
+    let output = indexer().args(["query", " Flour ", "test_recipes"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "test_recipes/pancakes.cook\n");
+}
+
+#[test]
+fn test_query_unknown_ingredient_fails() {
+    let output = indexer().args(["query", "saffron", "test_recipes"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("no recipe uses \"saffron\""));
+}
+
+#[test]
+fn test_search_prints_relative_paths() {
+    let output = indexer().args(["search", "test_recipes", "garlic", "butter", "--all"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "soups/tomato-soup.cook\n");
+
+    let output = indexer().args(["search", "test_recipes", "garlc", "--fuzzy", "--json"]).output().unwrap();
+    let paths: Vec<String> = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(paths, ["mains/chicken_curry.cook", "soups/tomato-soup.cook"]);
+}
+
+#[test]
+fn test_search_without_match_exits_with_one() {
+    let output = indexer().args(["search", "test_recipes", "saffron"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(output.stdout.is_empty() && output.stderr.is_empty());
+
+    let output = indexer().args(["search", "test_recipes"]).output().unwrap();
+    assert_eq!(output.status.code(), Some(1));
+    assert!(String::from_utf8(output.stderr).unwrap().contains("missing the ingredients to search for"));
+}
+
+#[test]
+fn test_search_reads_a_saved_index() {
+    let out = tempfile::tempdir().unwrap();
+    let snapshot = out.path().join("index.json");
+    let status = indexer().arg("test_recipes").arg("--format").arg(format!("json={}", snapshot.display())).status().unwrap();
+    assert!(status.success());
+
+    let output = indexer().arg("search").arg("--index").arg(&snapshot).args(["milk", "--substring"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "mains/chicken_curry.cook\npancakes.cook\n");
+}
+
+#[test]
+fn test_stats_table() {
+    let output = indexer().args(["stats", "test_recipes", "--top", "3"]).output().unwrap();
+    assert!(output.status.success());
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "Recipes:                3
+Ingredients:            14
+Ingredients per recipe: 6.0
+Used by one recipe:     10
+Largest recipe:         mains/chicken_curry.cook (8 ingredients)
+Smallest recipe:        pancakes.cook (4 ingredients)
+
+Top 3 ingredients:
+1. butter  2
+2. garlic  2
+3. onion   2
+"
+    );
+}
+
+#[test]
+fn test_stats_json() {
+    let output = indexer().args(["stats", "test_recipes", "--json"]).output().unwrap();
+    assert!(output.status.success());
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["recipes"], 3);
+    assert_eq!(stats["ingredients"], 14);
+    assert_eq!(stats["average_ingredients_per_recipe"], 6.0);
+    assert_eq!(stats["single_use_ingredients"], 10);
+    assert_eq!(stats["top_ingredients"].as_array().unwrap().len(), 10);
+    assert_eq!(stats["top_ingredients"][0], serde_json::json!({"name": "butter", "recipes": 2}));
+    assert_eq!(stats["largest_recipe"], serde_json::json!({"path": "mains/chicken_curry.cook", "ingredients": 8}));
+    assert_eq!(stats["smallest_recipe"], serde_json::json!({"path": "pancakes.cook", "ingredients": 4}));
+
+    let empty = tempfile::tempdir().unwrap();
+    let output = indexer().arg("stats").arg(empty.path()).arg("--json").output().unwrap();
+    let stats: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(stats["recipes"], 0);
+    assert!(stats["largest_recipe"].is_null() && stats["top_ingredients"].as_array().unwrap().is_empty());
+}
+
+#[test]
+fn test_shopping_list_formats() {
+    let output = indexer()
+        .args(["shopping-list", "test_recipes", "pancakes.cook", "./soups/tomato-soup.cook", "--scale", "0.5"])
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("- butter: 1.5 tbsp\n- eggs: 1\n- flour: 125 g\n"), "{}", text);
+
+    let output = indexer().args(["shopping-list", "test_recipes", "pancakes.cook", "--format", "json"]).output().unwrap();
+    let list: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert_eq!(list["groups"][0]["items"][2], serde_json::json!({"name": "flour", "amounts": [{"quantity": 250.0, "unit": "g"}], "other": []}));
+
+    let output = indexer().args(["shopping-list", "test_recipes", "pancakes.cook", "--format", "cooklang"]).output().unwrap();
+    assert_eq!(String::from_utf8(output.stdout).unwrap(), "@butter{1%tbsp}\n@eggs{2}\n@flour{250%g}\n@milk{500%ml}\n");
+}
+
+#[test]
+fn test_shopping_list_with_aisles_and_staples() {
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("aisle.conf"), "[produce]\nonion\ngarlic\n").unwrap();
+    std::fs::write(dir.path().join("staples.txt"), "# always at hand\nsalt\noil # olive\n").unwrap();
+    let output = indexer()
+        .args(["shopping-list", "test_recipes", "mains/chicken_curry.cook", "--aisle"])
+        .arg(dir.path().join("aisle.conf"))
+        .arg("--staples")
+        .arg(dir.path().join("staples.txt"))
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let text = String::from_utf8(output.stdout).unwrap();
+    assert!(text.starts_with("[produce]\n- garlic: 3 cloves\n- onion: 2\n[other]\n"), "{}", text);
+    assert!(!text.contains("salt") && !text.contains("oil"));
+}
+
+#[test]
+fn test_shopping_list_unknown_recipe_suggests_close_matches() {
+    let output = indexer().args(["shopping-list", "test_recipes", "pancakes.cook", "mains/curry.cook"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(output.stdout.is_empty());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("no recipe mains/curry.cook in test_recipes, did you mean mains/chicken_curry.cook?"), "{}", stderr);
+
+    let output = indexer().args(["shopping-list", "test_recipes", "pancakes.cook", "--scale", "0"]).output().unwrap();
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--scale must be a positive number"));
+}
+
+#[test]
+fn test_build_replaces_the_index() {
+    let recipes = std::fs::canonicalize("test_recipes").unwrap();
+    let out = tempfile::tempdir().unwrap();
+    std::fs::write(out.path().join("ingredient-index.html"), "stale").unwrap();
+
+    let status = indexer().arg("build").arg(&recipes).current_dir(out.path()).status().unwrap();
+    assert!(status.success());
+    let html = std::fs::read_to_string(out.path().join("ingredient-index.html")).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>") && html.contains("chicken thighs"));
+    // The temporary file was renamed over the index
+    assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 1);
+}
+
+#[test]
+fn test_build_writes_several_formats() {
+    let recipes = std::fs::canonicalize("test_recipes").unwrap();
+    let out = tempfile::tempdir().unwrap();
+
+    let status = indexer()
+        .arg("build")
+        .arg(&recipes)
+        .args(["--format", "html=public/index.html", "--format", "json"])
+        .current_dir(out.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let html = std::fs::read_to_string(out.path().join("public/index.html")).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>"));
+    // Links are relative to the page, wherever it is written
+    assert!(html.contains("href=\"../../"), "{}", html);
+    let json: serde_json::Value =
+        serde_json::from_str(&std::fs::read_to_string(out.path().join("ingredient-index.json")).unwrap()).unwrap();
+    assert_eq!(json["ingredients"][0]["name"], "butter");
+    assert_eq!(json["recipes"].as_array().unwrap().len(), 3);
+    assert!(!out.path().join("ingredient-index.html").exists());
+}
+
+#[test]
+fn test_build_rejects_unknown_format() {
+    let output = indexer().args(["build", "test_recipes", "--format", "pdf"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("expected one of: html, json, csv, markdown, ndjson"));
+}
+
+#[test]
+fn test_index_is_the_default_command() {
+    let recipes = std::fs::canonicalize("test_recipes").unwrap();
+    let out = tempfile::tempdir().unwrap();
+
+    // Bare directory, `--dir`, and the `index` subcommand all write the index
+    let runs: [&[&str]; 3] = [&[], &["--dir"], &["index"]];
+    for (i, args) in runs.into_iter().enumerate() {
+        let page = format!("page-{}.html", i);
+        let status = indexer()
+            .args(args)
+            .arg(&recipes)
+            .args(["--base-url", "https://example.com/r/", "--output", &page])
+            .current_dir(out.path())
+            .status()
+            .unwrap();
+        assert!(status.success(), "{:?}", args);
+        let html = std::fs::read_to_string(out.path().join(&page)).unwrap();
+        assert!(html.contains("href=\"https://example.com/r/pancakes\""));
+    }
+}
+
+#[test]
+fn test_output_needs_a_single_unnamed_format() {
+    let output = indexer()
+        .args(["test_recipes", "--output", "index", "--format", "html", "--format", "json"])
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--format FORMAT=PATH"));
+}
+
+#[test]
+fn test_help_lists_commands_and_options() {
+    let output = indexer().arg("--help").output().unwrap();
+    let help = String::from_utf8(output.stdout).unwrap();
+    for expected in ["index", "query", "site", "--dir", "--base-url", "--output", "--format"] {
+        assert!(help.contains(expected), "{} missing from\n{}", expected, help);
+    }
+    // Without arguments the help is shown instead of an error about the directory
+    let output = indexer().output().unwrap();
+    assert!(String::from_utf8(output.stderr).unwrap().contains("Usage:"));
+}
+
+#[test]
+fn test_output_to_stdout() {
+    let out = tempfile::tempdir().unwrap();
+    let recipes = std::fs::canonicalize("test_recipes").unwrap();
+    let output = indexer()
+        .arg(&recipes)
+        .args(["-o", "-"])
+        .env("RUST_LOG", "info")
+        .current_dir(out.path())
+        .output()
+        .unwrap();
+    assert!(output.status.success());
+    let html = String::from_utf8(output.stdout).unwrap();
+    assert!(html.starts_with("<!DOCTYPE html>") && html.ends_with("</html>"));
+    // Messages stay out of the pipe, and no file is written
+    assert!(String::from_utf8(output.stderr).unwrap().contains("standard output"));
+    assert_eq!(std::fs::read_dir(out.path()).unwrap().count(), 0);
+
+    let output = indexer().args(["test_recipes", "--format", "json=-"]).output().unwrap();
+    let json: serde_json::Value = serde_json::from_slice(&output.stdout).unwrap();
+    assert!(json["ingredients"].is_array());
+
+    let output = indexer().args(["test_recipes", "--format", "html=-", "--format", "json=-"]).output().unwrap();
+    assert!(!output.status.success());
+}
+
+#[test]
+fn test_output_to_directory_fails() {
+    let out = tempfile::tempdir().unwrap();
+    let output = indexer().arg("test_recipes").arg("--output").arg(out.path()).output().unwrap();
+    assert!(!output.status.success());
+    let stderr = String::from_utf8(output.stderr).unwrap();
+    assert!(stderr.contains("is a directory; give the file to write, e.g."), "{}", stderr);
+    assert!(stderr.contains("ingredient-index.html"));
+}
+
+#[test]
+fn test_default_file_names_follow_the_format() {
+    let recipes = std::fs::canonicalize("test_recipes").unwrap();
+    let out = tempfile::tempdir().unwrap();
+    let status = indexer()
+        .arg(&recipes)
+        .args(["--format", "csv", "--format", "markdown", "--format", "ndjson"])
+        .current_dir(out.path())
+        .status()
+        .unwrap();
+    assert!(status.success());
+
+    let csv = std::fs::read_to_string(out.path().join("ingredient-index.csv")).unwrap();
+    assert!(csv.starts_with("ingredient,recipe\r\nbutter,pancakes.cook\r\n"));
+    let markdown = std::fs::read_to_string(out.path().join("ingredient-index.md")).unwrap();
+    assert!(markdown.starts_with("# Recipe Ingredient Index\n\n## butter\n"));
+    let ndjson = std::fs::read_to_string(out.path().join("ingredient-index.ndjson")).unwrap();
+    assert_eq!(ndjson.lines().count(), 14);
+}
+
+#[test]
+fn test_base_url_needs_a_linking_format() {
+    let output = indexer().args(["test_recipes", "--base-url", "https://example.com/r/", "--format", "csv=-"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("--base-url only applies to the html and markdown formats"));
+
+    let output = indexer()
+        .args(["test_recipes", "--base-url", "https://example.com/r/", "--format", "markdown=-"])
+        .output()
+        .unwrap();
+    assert!(String::from_utf8(output.stdout).unwrap().contains("- [Pancakes](https://example.com/r/pancakes)\n"));
+}
+
+#[cfg(feature = "watch")]
+#[test]
+fn test_watch_refuses_standard_output() {
+    let output = indexer().args(["watch", "test_recipes", "--output", "-"]).output().unwrap();
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("instead of -"));
+}
+
+#[test]
+fn test_index_output_is_reproducible() {
+    let run = || indexer().args(["test_recipes", "-o", "-"]).env_remove("SOURCE_DATE_EPOCH").output().unwrap().stdout;
+    let first = run();
+    assert!(!String::from_utf8_lossy(&first).contains("page-footer"));
+    assert_eq!(first, run());
+
+    let output = indexer().args(["test_recipes", "-o", "-", "--footer"]).env("SOURCE_DATE_EPOCH", "0").output().unwrap();
+    assert!(output.status.success());
+    assert!(String::from_utf8(output.stdout).unwrap().contains("<footer class=\"page-footer\">Generated 1970-01-01"));
+}
+
+#[test]
+fn test_site_strict_fails_on_malformed_recipes() {
+    let recipes = common::recipe_dir(&[("pancakes.cook", "Mix @flour{200%g with @milk{}.")]);
+    let out = tempfile::tempdir().unwrap();
+    let site = |strict: bool| {
+        let mut command = indexer();
+        command.arg("site").arg(recipes.path()).arg("--out").arg(out.path());
+        if strict {
+            command.arg("--strict");
+        }
+        command.output().unwrap()
+    };
+
+    assert!(site(false).status.success());
+    let output = site(true);
+    assert!(!output.status.success());
+    assert!(String::from_utf8(output.stderr).unwrap().contains("problem(s) in recipes"));
+}
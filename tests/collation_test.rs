@@ -0,0 +1,50 @@
+// tests/collation_test.rs
+#![cfg(feature = "collation")]
+mod common;
+
+use cooklang_indexer::{HtmlOptions, IngredientIndex};
+
+fn ingredient_order(index: &IngredientIndex, options: &HtmlOptions) -> Vec<String> {
+    let html = index.generate_html_with(options).unwrap();
+    html.split("<section class=\"ingredient\" id=\"")
+        .skip(1)
+        .map(|entry| entry[..entry.find('"').unwrap()].to_string())
+        .collect()
+}
+
+#[test]
+fn test_french_collation() {
+    let dir = common::recipe_dir(&[(
+        "gratin.cook",
+        "Sweat @échalote{2} in @beurre{}, add @zucchini{1}, @Emmental{} and @eau{}.",
+    )]);
+    let index = IngredientIndex::builder(dir.path()).normalizer(|name| name.trim().to_string()).build().unwrap();
+
+    // Byte order stays the default
+    let default = ingredient_order(&index, &HtmlOptions::default());
+    assert_eq!(default, ["emmental", "beurre", "eau", "zucchini", "echalote"]);
+
+    let options = HtmlOptions { collation: Some("fr".to_string()), ..HtmlOptions::default() };
+    assert_eq!(ingredient_order(&index, &options), ["beurre", "eau", "echalote", "emmental", "zucchini"]);
+
+    // Letter groups keep the collated order
+    let options = HtmlOptions { group_by_letter: true, ..options };
+    assert_eq!(ingredient_order(&index, &options), ["beurre", "eau", "echalote", "emmental", "zucchini"]);
+}
+
+#[test]
+fn test_invalid_collation_locale() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions { collation: Some("not a locale!".to_string()), ..HtmlOptions::default() };
+    let err = index.generate_html_with(&options).unwrap_err();
+    assert!(err.to_string().contains("invalid collation locale"));
+}
+
+#[test]
+fn test_invalid_collation_locale_with_url_mapper() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions { collation: Some("not a locale!".to_string()), ..HtmlOptions::default() }
+        .url_mapper(|path, _base_dir| format!("?recipe={}", path.display()));
+    let err = index.generate_html_with(&options).unwrap_err();
+    assert!(err.to_string().contains("invalid collation locale"));
+}
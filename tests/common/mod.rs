@@ -0,0 +1,18 @@
+// tests/common/mod.rs
+#![allow(dead_code)]
+
+use std::fs;
+use tempfile::TempDir;
+
+/// Writes the given `(relative path, content)` pairs into a fresh temporary directory
+pub fn recipe_dir(recipes: &[(&str, &str)]) -> TempDir {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, content) in recipes {
+        let path = dir.path().join(name);
+        if let Some(parent) = path.parent() {
+            fs::create_dir_all(parent).unwrap();
+        }
+        fs::write(path, content).unwrap();
+    }
+    dir
+}
@@ -0,0 +1,40 @@
+// tests/export_test.rs
+mod common;
+
+use cooklang_indexer::{HtmlOptions, IngredientIndex};
+
+#[test]
+fn test_generate_csv() {
+    let dir = common::recipe_dir(&[
+        ("mains/fish, chips.cook", "Fry @fish{} and @\"mushy\" peas{}."),
+        ("toast.cook", "Spread @butter{} on @bread{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(
+        index.generate_csv(),
+        "ingredient,recipe\r\n\
+         \"\"\"mushy\"\" peas\",\"mains/fish, chips.cook\"\r\n\
+         bread,toast.cook\r\n\
+         butter,toast.cook\r\n\
+         fish,\"mains/fish, chips.cook\"\r\n"
+    );
+}
+
+#[test]
+fn test_generate_markdown() {
+    let dir = common::recipe_dir(&[
+        ("mains/fish (battered).cook", "Fry @fish{} in @oil{}."),
+        ("fish_tacos.cook", ">> title: *Best* Tacos\nWrap @fish{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = HtmlOptions { title: "Pantry".to_string(), ..HtmlOptions::default() };
+    assert_eq!(
+        index.generate_markdown(&options).unwrap(),
+        "# Pantry\n\n\
+         ## fish\n\n\
+         - [\\*Best\\* Tacos](fish_tacos.cook)\n\
+         - [Fish (battered)](mains/fish%20%28battered%29.cook)\n\n\
+         ## oil\n\n\
+         - [Fish (battered)](mains/fish%20%28battered%29.cook)\n"
+    );
+}
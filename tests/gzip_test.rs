@@ -0,0 +1,30 @@
+// tests/gzip_test.rs
+#![cfg(feature = "gzip")]
+
+use cooklang_indexer::IngredientIndex;
+use flate2::read::GzDecoder;
+use std::io::Read;
+
+#[test]
+fn test_write_html_gz_roundtrip() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.html.gz");
+    index.write_html_gz(&path, "https://example.com/recipes").unwrap();
+
+    let mut html = String::new();
+    GzDecoder::new(std::fs::File::open(&path).unwrap()).read_to_string(&mut html).unwrap();
+    assert_eq!(html, index.generate_html("https://example.com/recipes").unwrap());
+}
+
+#[test]
+fn test_write_json_gz_roundtrip() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("index.json.gz");
+    index.write_json_gz(&path).unwrap();
+
+    let mut json = String::new();
+    GzDecoder::new(std::fs::File::open(&path).unwrap()).read_to_string(&mut json).unwrap();
+    assert_eq!(json, index.generate_json().unwrap());
+}
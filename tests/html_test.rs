@@ -0,0 +1,835 @@
+// tests/html_test.rs
+mod common;
+
+use cooklang_indexer::{Footer, HtmlOptions, IngredientIndex, Theme};
+use std::time::{Duration, UNIX_EPOCH};
+
+fn render(options: &HtmlOptions) -> String {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    index.generate_html_report(options).unwrap().html
+}
+
+#[test]
+fn test_dark_theme() {
+    let html = render(&HtmlOptions { theme: Theme::Dark, ..HtmlOptions::default() });
+    assert!(html.contains("--background: #1e2227;"));
+    assert!(!render(&HtmlOptions::default()).contains("#1e2227"));
+}
+
+#[test]
+fn test_auto_theme() {
+    let html = render(&HtmlOptions { theme: Theme::Auto, ..HtmlOptions::default() });
+    let (light, dark) = html.split_once("@media (prefers-color-scheme: dark)").unwrap();
+    assert!(light.contains("--link: #3498db;"));
+    assert!(dark.contains("--link: #6cb6ff;"));
+}
+
+#[test]
+fn test_theme_variables() {
+    let theme = Theme::Variables("--link: #c0392b;\n--muted: gray;".to_string());
+    let html = render(&HtmlOptions { theme, ..HtmlOptions::default() });
+    // Applied after the light colors, so they win while the rest keep their default
+    let custom = html.find("--link: #c0392b;").unwrap();
+    assert!(html.find("--link: #3498db;").unwrap() < custom);
+    assert!(html.contains("--muted: gray;"));
+    assert!(html.contains("--heading: #2c3e50;"));
+    assert!(html.contains("color: var(--link);"));
+}
+
+#[test]
+fn test_custom_theme() {
+    let css = "body { font-family: serif; }";
+    let html = render(&HtmlOptions { theme: Theme::Custom(css.to_string()), ..HtmlOptions::default() });
+    assert!(html.contains(css));
+    // The custom stylesheet replaces the built-in one
+    assert!(!html.contains("BlinkMacSystemFont"));
+}
+
+#[test]
+fn test_title_without_css() {
+    let html = render(&HtmlOptions {
+        title: "Pantry & Fridge".to_string(),
+        include_default_css: false,
+        ..HtmlOptions::default()
+    });
+    assert!(html.contains("<title>Pantry &amp; Fridge</title>"));
+    assert!(html.contains("<h1>Pantry &amp; Fridge</h1>"));
+    assert!(!html.contains("<style>"));
+}
+
+#[test]
+fn test_sort_by_count_with_counts() {
+    use cooklang_indexer::IngredientSort;
+
+    let html = render(&HtmlOptions {
+        sort: IngredientSort::ByRecipeCountDesc,
+        show_counts: true,
+        ..HtmlOptions::default()
+    });
+    // Shared ingredients come first, in alphabetical order among themselves
+    let butter = html.find("id=\"butter\"").unwrap();
+    let garlic = html.find("id=\"garlic\"").unwrap();
+    let chicken = html.find("id=\"chicken-thighs\"").unwrap();
+    assert!(butter < garlic && garlic < chicken);
+    assert!(html.contains("garlic</a> <span class=\"ingredient-count\">(2 recipes)</span>"));
+    assert!(html.contains("chicken thighs</a> <span class=\"ingredient-count\">(1 recipe)</span>"));
+}
+
+#[test]
+fn test_sort_by_count_ascending() {
+    use cooklang_indexer::IngredientSort;
+
+    let html = render(&HtmlOptions { sort: IngredientSort::ByRecipeCountAsc, ..HtmlOptions::default() });
+    // Rare ingredients come first, ties still in alphabetical order
+    let chicken = html.find("id=\"chicken-thighs\"").unwrap();
+    let butter = html.find("id=\"butter\"").unwrap();
+    let garlic = html.find("id=\"garlic\"").unwrap();
+    assert!(chicken < butter && butter < garlic);
+}
+
+#[test]
+fn test_recipe_sort() {
+    use cooklang_indexer::RecipeSort;
+    use std::fs::File;
+
+    let dir = common::recipe_dir(&[
+        ("b/zucchini-bread.cook", "Grate @zucchini{1}, fold into @flour{}."),
+        ("apple-cake.cook", "Fold @apples{3} into @flour{}."),
+        ("a/Banana bread.cook", "Mash @bananas{2}, stir in @flour{}."),
+    ]);
+    for (name, secs) in [("apple-cake.cook", 1_000), ("a/Banana bread.cook", 3_000), ("b/zucchini-bread.cook", 2_000)] {
+        let file = File::options().write(true).open(dir.path().join(name)).unwrap();
+        file.set_modified(UNIX_EPOCH + Duration::from_secs(secs)).unwrap();
+    }
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    // Recipe names in the order they are listed under flour
+    let flour_recipes = |options: &HtmlOptions| -> Vec<String> {
+        let html = index.generate_html_with(options).unwrap();
+        let flour = &html[html.find("id=\"flour\"").unwrap()..];
+        let flour = &flour[..flour.find("</ul>").unwrap()];
+        flour
+            .lines()
+            .filter_map(|line| line.trim().strip_prefix("<li><a href=\""))
+            .map(|item| item.split(['>', '<']).nth(1).unwrap().to_string())
+            .collect()
+    };
+
+    // By name, ignoring case and folders
+    assert_eq!(flour_recipes(&HtmlOptions::default()), ["Apple Cake", "Banana Bread", "Zucchini Bread"]);
+    assert_eq!(
+        flour_recipes(&HtmlOptions { recipe_sort: RecipeSort::NewestFirst, ..HtmlOptions::default() }),
+        ["Banana Bread", "Zucchini Bread", "Apple Cake"]
+    );
+}
+
+#[test]
+fn test_recipe_sort_key_by_rating() {
+    let dir = common::recipe_dir(&[
+        ("risotto.cook", ">> rating: 3\nStir @rice{} into @stock{}."),
+        ("soup.cook", ">> Rating: 5\nSimmer @stock{} with @leeks{}."),
+        ("gravy.cook", "Whisk @stock{} into @flour{}."),
+        ("braise.cook", ">> rating: 3\nCover @beef{} with @stock{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    // Highest rated first, unrated last, equal ratings by path
+    let options = HtmlOptions::default().recipe_sort_key(|recipe| {
+        let rating: u32 = recipe.metadata.get("rating").and_then(|rating| rating.parse().ok()).unwrap_or(0);
+        (9 - rating).to_string()
+    });
+    let html = index.generate_html_with(&options).unwrap();
+    let stock = &html[html.find("id=\"stock\"").unwrap()..];
+    let at: Vec<usize> = [">Soup<", ">Braise<", ">Risotto<", ">Gravy<"].iter().map(|name| stock.find(name).unwrap()).collect();
+    assert!(at.windows(2).all(|pair| pair[0] < pair[1]));
+}
+
+#[test]
+fn test_recipe_display_names() {
+    use cooklang_indexer::recipe_display_name;
+    use std::path::Path;
+
+    for (file, expected) in [
+        ("beef_stew_with_dumplings.cook", "Beef Stew with Dumplings"),
+        ("a-tale-of-two-soups.cook", "A Tale of Two Soups"),
+        ("the_best_of_the_rest.cook", "The Best of the Rest"),
+        ("BBQ_ribs.cook", "BBQ Ribs"),
+        ("mac-and-cheese.cook", "Mac and Cheese"),
+        ("iPhone_friendly_snacks.cook", "iPhone Friendly Snacks"),
+        ("2-minute-mug-cake.cook", "2 Minute Mug Cake"),
+        ("3rd_attempt_at_1.5x_bread.cook", "3rd Attempt at 1.5x Bread"),
+        ("crème-brûlée.cook", "Crème Brûlée"),
+        ("soups/--double--_dashes_.cook", "Double Dashes"),
+        ("Already Titled.cook", "Already Titled"),
+        ("", "Unknown Recipe"),
+    ] {
+        assert_eq!(recipe_display_name(Path::new(file)), expected, "{}", file);
+    }
+}
+
+#[test]
+fn test_disambiguate_duplicate_names() {
+    let dir = tempfile::tempdir().unwrap();
+    for (name, content) in [
+        ("dinners/soup.cook", "Simmer @onion{1} in @stock{1%l}."),
+        ("lunches/soup.cook", "Blend @onion{1} with @leek{2}."),
+        ("soup.cook", "Boil @onion{2}."),
+        ("stew.cook", "Braise @onion{3}."),
+    ] {
+        let path = dir.path().join(name);
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        std::fs::write(path, content).unwrap();
+    }
+    let index = IngredientIndex::new(dir.path()).unwrap();
+
+    let duplicates = index.duplicate_recipe_names();
+    assert_eq!(duplicates.len(), 1);
+    assert_eq!(duplicates[0].0, "Soup");
+    assert_eq!(duplicates[0].1.len(), 3);
+
+    let html = index
+        .generate_html_with(&HtmlOptions { disambiguate_names: true, ..HtmlOptions::default() })
+        .unwrap();
+    assert!(html.contains(">Soup (dinners)</a>"));
+    assert!(html.contains(">Soup (lunches)</a>"));
+    assert!(html.contains(">Soup</a>"));
+    assert!(html.contains(">Stew</a>"));
+    // Under "stock" the name is unique, so it stays bare
+    let stock = &html[html.find("id=\"stock\"").unwrap()..];
+    let stock = &stock[..stock.find("</ul>").unwrap()];
+    assert!(stock.contains(">Soup</a>"));
+    assert!(!stock.contains("(dinners)"));
+
+    let plain = index
+        .generate_html_with(&HtmlOptions { disambiguate_names: false, ..HtmlOptions::default() })
+        .unwrap();
+    assert!(!plain.contains("(dinners)"));
+}
+
+#[test]
+fn test_disambiguate_by_title_and_path() {
+    let dir = common::recipe_dir(&[
+        ("mains/curry.cook", "Fry @onion{1}."),
+        ("soups/curry.cook", "Sweat @onion{2}."),
+        ("old/mains/curry.cook", "Brown @onion{1}."),
+        ("mains/dal.cook", ">> title: Weeknight Dal\nSimmer @lentils{} with @onion{}."),
+        ("soups/dal.cook", ">> title: Dal Shorba\nBlend @lentils{} and @onion{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index.generate_html_with(&HtmlOptions::default()).unwrap();
+
+    assert!(html.contains(">Curry (soups)</a>"));
+    // Both "mains" folders clash, so the relative path is spelled out
+    assert!(html.contains(">Curry (mains/curry.cook)</a>"));
+    assert!(html.contains(">Curry (old/mains/curry.cook)</a>"));
+    assert!(!html.contains(">Curry (mains)</a>"));
+    // Titles that differ need no suffix
+    assert!(html.contains(">Weeknight Dal</a>"));
+    assert!(html.contains(">Dal Shorba</a>"));
+}
+
+#[test]
+fn test_default_output_snapshot() {
+    assert_eq!(render(&HtmlOptions::default()), include_str!("snapshots/default_index.html"));
+}
+
+#[test]
+fn test_external_stylesheet_and_extra_css() {
+    let html = render(&HtmlOptions {
+        stylesheet_href: Some("/css/site.css?v=1&dark=\"yes\"".to_string()),
+        extra_css: Some(".ingredient { margin: 0; }".to_string()),
+        ..HtmlOptions::default()
+    });
+    assert!(html.contains(r#"<link rel="stylesheet" href="/css/site.css?v=1&amp;dark=&quot;yes&quot;">"#));
+    assert!(html.contains("        }\n.ingredient { margin: 0; }\n    </style>"));
+
+    // Extra rules survive without the built-in stylesheet
+    let html = render(&HtmlOptions {
+        include_default_css: false,
+        extra_css: Some("body { color: red; }".to_string()),
+        ..HtmlOptions::default()
+    });
+    assert!(html.contains("    <style>\nbody { color: red; }\n    </style>\n"));
+    assert!(!html.contains("BlinkMacSystemFont"));
+}
+
+#[test]
+fn test_heading_and_intro() {
+    let html = render(&HtmlOptions {
+        title: "Pots & Pans".to_string(),
+        heading: Some("What's <cooking>".to_string()),
+        intro_html: Some(r#"<p>Back to <a href="/">my homepage</a>.</p>"#.to_string()),
+        ..HtmlOptions::default()
+    });
+    assert!(html.contains("<title>Pots &amp; Pans</title>"));
+    assert!(html.contains("    <h1>What&#39;s &lt;cooking&gt;</h1>\n<p>Back to <a href=\"/\">my homepage</a>.</p>\n    <nav"));
+}
+
+#[test]
+fn test_alphabet_nav() {
+    let dir = common::recipe_dir(&[(
+        "stew.cook",
+        "Fry @échalote{2} and @apple{1}, add @7-spice{1%tsp}, @anise{} and @letter a{}.",
+    )]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index.generate_html_with(&HtmlOptions::default()).unwrap();
+
+    assert!(html.contains("        <a href=\"#letter_a\">A</a>\n        <span>B</span>\n"));
+    // Diacritics are folded into the base letter, digits go under #
+    assert!(html.contains("<a href=\"#letter_e\">E</a>"));
+    assert!(html.contains("<a href=\"#letter_other\">#</a>"));
+    assert!(html.contains("<a class=\"letter-anchor\" id=\"letter_a\"></a>\n<section class=\"ingredient\" id=\"anise\">"));
+    assert!(html.contains("<a class=\"letter-anchor\" id=\"letter_e\"></a>\n<section class=\"ingredient\" id=\"echalote\">"));
+    // Navigation targets never take an ingredient's slug
+    assert!(html.contains("<section class=\"ingredient\" id=\"letter-a\">"));
+
+    let html = index
+        .generate_html_with(&HtmlOptions { alphabet_nav: false, ..HtmlOptions::default() })
+        .unwrap();
+    assert!(!html.contains("class=\"alphabet-nav\""));
+    assert!(!html.contains("letter-anchor"));
+}
+
+#[test]
+fn test_output_is_deterministic() {
+    use cooklang_indexer::IngredientSort;
+
+    let options = HtmlOptions {
+        sort: IngredientSort::ByRecipeCountDesc,
+        show_counts: true,
+        disambiguate_names: true,
+        ..HtmlOptions::default()
+    };
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let first = index.generate_html_with(&options).unwrap();
+    assert_eq!(index.generate_html_with(&options).unwrap(), first);
+
+    // A fresh scan of the same recipes renders the same bytes too
+    let rebuilt = IngredientIndex::new("./test_recipes").unwrap();
+    assert_eq!(rebuilt.generate_html_with(&options).unwrap(), first);
+}
+
+#[test]
+fn test_ingredient_permalinks_are_unique() {
+    use cooklang_indexer::slugify;
+
+    let dir = common::recipe_dir(&[("dip.cook", "Stir @crème fraîche{} into @creme fraiche{} and @buttermilk{}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index.generate_html_with(&HtmlOptions::default()).unwrap();
+
+    assert_eq!(slugify("crème fraîche"), "creme-fraiche");
+    assert!(html.contains(
+        "<section class=\"ingredient\" id=\"buttermilk\">\n    <h2 class=\"ingredient-name\"><a href=\"#buttermilk\">buttermilk</a>"
+    ));
+    assert!(html.contains("<a href=\"#creme-fraiche\">creme fraiche</a>"));
+    assert!(html.contains("<a href=\"#creme-fraiche-2\">crème fraîche</a>"));
+    assert_eq!(html.matches("id=\"creme-fraiche\"").count(), 1);
+}
+
+#[test]
+fn test_group_by_letter() {
+    use cooklang_indexer::IngredientSort;
+
+    let dir = common::recipe_dir(&[
+        ("a.cook", "Use @basil{}, @échalote{}, @7-spice{} and @bay leaf{}."),
+        ("b.cook", "Use @bay leaf{} again."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index
+        .generate_html_with(&HtmlOptions {
+            group_by_letter: true,
+            sort: IngredientSort::ByRecipeCountDesc,
+            ..HtmlOptions::default()
+        })
+        .unwrap();
+
+    let headings: Vec<_> = html.match_indices("<h2 class=\"letter-heading\">").map(|(i, _)| &html[i + 27..i + 28]).collect();
+    assert_eq!(headings, ["B", "E", "#"]);
+    // Within a letter the requested sort still applies
+    assert!(html.find("id=\"bay-leaf\"").unwrap() < html.find("id=\"basil\"").unwrap());
+    assert!(html.contains("<a class=\"letter-anchor\" id=\"letter_b\"></a>\n<h2 class=\"letter-heading\">B</h2>\n"));
+}
+
+#[test]
+fn test_semantic_structure() {
+    let html = render(&HtmlOptions::default());
+    assert!(html.contains("<body>\n<main>\n    <h1>Recipe Ingredient Index</h1>\n"));
+    assert!(html.ends_with("</section>\n</main>\n</body>\n</html>"));
+    assert!(html.contains("    <nav class=\"alphabet-nav\" aria-label=\"Ingredients by letter\">\n"));
+    // Every ingredient is a section named by a heading, and nothing else is a div
+    assert_eq!(html.matches("<section class=\"ingredient\"").count(), html.matches("<h2 class=\"ingredient-name\">").count());
+    assert!(!html.contains("<div"));
+
+    // Under letter headings, ingredient names go one level down
+    let html = render(&HtmlOptions { group_by_letter: true, ..HtmlOptions::default() });
+    assert!(html.contains("<h2 class=\"letter-heading\">B</h2>\n<section class=\"ingredient\" id=\"butter\">\n    <h3 class=\"ingredient-name\">"));
+    assert!(!html.contains("<h2 class=\"ingredient-name\">"));
+}
+
+#[test]
+fn test_filter_box() {
+    let html = render(&HtmlOptions { filter_box: true, ..HtmlOptions::default() });
+    // Hidden until the script runs, so nothing changes without JavaScript
+    assert!(html.contains("<input type=\"search\" id=\"ingredient-filter\""));
+    assert!(html.contains(" hidden>\n    <p id=\"no-matches\" hidden>No matches</p>\n"));
+    assert!(html.contains("input.hidden = false;"));
+    assert!(html.ends_with("</script>\n</body>\n</html>"));
+    assert!(!html.contains("<section class=\"ingredient\" hidden"));
+
+    assert!(!render(&HtmlOptions::default()).contains("<script>"));
+}
+
+#[test]
+fn test_collapsible_sections_with_deduplicated_counts() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Pound @basil{} with @garlic{}, then add more @basil{} and @Basil{}."),
+        ("salad.cook", "Tear @basil{} over the @tomatoes{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index
+        .generate_html_with(&HtmlOptions { collapsible: true, ..HtmlOptions::default() })
+        .unwrap();
+
+    assert!(html.contains(
+        "<details class=\"ingredient\" id=\"basil\">\n    \
+         <summary class=\"ingredient-name\"><a href=\"#basil\">basil</a> <span class=\"ingredient-count\">(2 recipes)</span></summary>\n"
+    ));
+    assert_eq!(html.matches(">Pesto</a></li>").count(), 2, "listed once per ingredient");
+    assert!(html.contains("    </ul>\n</details>\n"));
+    assert!(html.contains("summary.ingredient-name {"));
+
+    let html = index
+        .generate_html_with(&HtmlOptions { collapsible: true, open_by_default: true, ..HtmlOptions::default() })
+        .unwrap();
+    assert!(html.contains("<details class=\"ingredient\" id=\"basil\" open>"));
+}
+
+#[test]
+fn test_custom_ingredient_order() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let order = ["Tomatoes", "salt", "not an ingredient", "butter"].map(String::from);
+    let html = index.generate_html_with_order("https://example.com/r", &order).unwrap();
+
+    let position = |id: &str| html.find(&format!("<section class=\"ingredient\" id=\"{}\">", id)).unwrap();
+    let first = html.find("<section class=\"ingredient\"").unwrap();
+    assert_eq!(first, position("tomatoes"));
+    assert!(position("tomatoes") < position("salt") && position("salt") < position("butter"));
+    // Unlisted ingredients follow alphabetically
+    assert!(position("butter") < position("chicken-thighs") && position("chicken-thighs") < position("coconut-milk"));
+}
+
+#[test]
+fn test_show_quantities() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Blend @basil{2%cups} with @garlic{1%clove}, then fold in @basil{1/2%cup} and @basil{1%cups}."),
+        ("salad.cook", "Tear @basil{} over the salad."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index
+        .generate_html_with(&HtmlOptions { show_quantities: true, ..HtmlOptions::default() })
+        .unwrap();
+
+    assert!(html.contains(">Pesto</a> <span class=\"recipe-quantity\">— 3 cups, 0.5 cup</span></li>"));
+    assert!(html.contains(">garlic</a>"));
+    assert!(html.contains(">Salad</a></li>"));
+
+    assert!(!render(&HtmlOptions::default()).contains("recipe-quantity"));
+}
+
+#[test]
+fn test_print_mode() {
+    let interactive = HtmlOptions {
+        filter_box: true,
+        tag_cloud: true,
+        collapsible: true,
+        ..HtmlOptions::default()
+    };
+    let html = render(&interactive);
+    assert!(!html.contains("@media print"));
+    assert!(html.contains("<details") && html.contains("<nav class=\"alphabet-nav\""));
+
+    let html = render(&HtmlOptions { print: true, ..interactive });
+    assert!(html.contains("        @media print {\n"), "{}", html);
+    assert!(html.contains("break-inside: avoid;"));
+    // Everything interactive is left out and every ingredient shown in full
+    assert!(!html.contains("<details") && !html.contains("<summary"));
+    assert!(!html.contains("<script") && !html.contains("ingredient-filter\""));
+    assert!(!html.contains("<nav"));
+    assert_eq!(html.matches("<section class=\"ingredient\"").count(), 14);
+    assert!(html.contains("chicken_curry"));
+}
+
+#[test]
+fn test_show_context() {
+    let long_step = format!("Let it {} rest, then dust with @flour{{}} and serve.", "rest and ".repeat(20));
+    let dir = common::recipe_dir(&[
+        (
+            "soup.cook",
+            "Chop the onion. Sweat it in @butter{2%tbsp} & a pinch of @salt{} for ~{5%minutes}, stirring with a #wooden spoon{}.\nTaste, then add more @salt{}.",
+        ),
+        ("bread.cook", &long_step),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = HtmlOptions { show_context: true, ..HtmlOptions::default() };
+    let html = index.generate_html_with(&options).unwrap();
+
+    // Only the sentence using the ingredient is quoted, without its markup
+    assert!(html.contains(
+        ">Soup</a><div class=\"recipe-context\">Sweat it in <mark>butter</mark> &amp; a pinch of salt for 5 minutes, stirring with a wooden spoon.</div></li>"
+    ));
+    // The first use of the ingredient is quoted
+    assert!(html.contains("Sweat it in butter &amp; a pinch of <mark>salt</mark> for"));
+    assert!(!html.contains("Taste, then"));
+
+    let context = html.split("<div class=\"recipe-context\">").find(|part| part.contains("<mark>flour</mark>")).unwrap();
+    let context = &context[..context.find("</div>").unwrap()];
+    assert!(context.starts_with('…') && context.ends_with("and serve."));
+    assert!(context.replace("<mark>", "").replace("</mark>", "").chars().count() <= 120);
+    assert!(html.contains(".recipe-context {"));
+
+    assert!(!render(&HtmlOptions::default()).contains("recipe-context"));
+    // Low-memory indexes keep no recipe text to quote
+    let lean = IngredientIndex::builder(dir.path()).low_memory(true).build().unwrap();
+    assert!(!lean.generate_html_with(&options).unwrap().contains("<mark>"));
+}
+
+#[test]
+fn test_recipe_index_page() {
+    let dir = common::recipe_dir(&[
+        ("zucchini-bread.cook", ">> title: Aunt May's Bread\nMix @flour{} with @zucchini{} and @flour{}, then @sugar{}."),
+        ("soups/borscht.cook", "Simmer @beets{4}."),
+        ("apple-pie.cook", "Fill with @apples{6}."),
+        ("2-minute-mug-cake.cook", "Microwave @flour{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = HtmlOptions { recipe_index_ingredients: 2, ..HtmlOptions::default() };
+    let html = index.generate_recipe_index_html(&options).unwrap();
+
+    let positions: Vec<_> = [">Apple Pie<", ">Aunt May&#39;s Bread<", ">Borscht<", ">2 Minute Mug Cake<"]
+        .iter()
+        .map(|title| html.find(title).unwrap())
+        .collect();
+    assert!(positions.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(html.matches("<h2 class=\"letter-heading\">").count(), 3);
+    assert!(html.contains("<h2 class=\"letter-heading\">#</h2>"));
+    assert!(html.contains("<a href=\"soups/borscht.cook\">Borscht</a>"));
+    assert!(html.contains("Bread</a> <span class=\"recipe-ingredients\">— flour, zucchini</span></li>"));
+
+    let html = index.generate_recipe_index_html(&HtmlOptions::default()).unwrap();
+    assert!(!html.contains("recipe-ingredients"));
+}
+
+#[test]
+fn test_tag_index_page() {
+    let dir = common::recipe_dir(&[
+        ("chili.cook", ">> tags: Weeknight, freezer-friendly\nBrown @beef{500%g}."),
+        ("lasagna.cook", ">> title: Big Lasagna\n>> tags: freezer-friendly, party\nLayer @pasta{}."),
+        ("omelette.cook", ">> tags: weeknight,,  breakfast \nWhisk @eggs{3}."),
+        ("toast.cook", "Toast @bread{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index.generate_tag_index_html(&HtmlOptions::default()).unwrap();
+
+    let order: Vec<_> = ["freezer-friendly</a>", "Weeknight</a>", "breakfast</a>", "party</a>", "untagged</a>"]
+        .iter()
+        .map(|tag| html.find(tag).unwrap())
+        .collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+    assert!(html.contains(
+        "<h2 class=\"tag-name\"><a href=\"#tag-weeknight\">Weeknight</a> <span class=\"tag-count\">(2 recipes)</span></h2>"
+    ));
+    assert!(html.contains("<a href=\"lasagna.cook\">Big Lasagna</a>"));
+    assert!(html.contains("<a href=\"toast.cook\">Toast</a>"));
+
+    let html = index
+        .generate_tag_index_html(&HtmlOptions { show_untagged: false, ..HtmlOptions::default() })
+        .unwrap();
+    assert!(!html.contains("untagged"));
+    assert!(!html.contains("toast"));
+}
+
+#[test]
+fn test_group_recipes_by_dir() {
+    let dir = common::recipe_dir(&[
+        ("soups/minestrone.cook", "Add @onion{1}."),
+        ("mains/stew/beef-stew.cook", "Add @onion{2}."),
+        ("mains/chicken-curry.cook", "Add @onion{1}."),
+        ("onion-rings.cook", "Slice @onion{3}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index
+        .generate_html_with(&HtmlOptions { group_recipes_by_dir: true, ..HtmlOptions::default() })
+        .unwrap();
+
+    let order: Vec<_> = [">Onion Rings<", "mains/</div>", ">Beef Stew<", ">Chicken Curry<", "soups/</div>", ">Minestrone<"]
+        .iter()
+        .map(|needle| html.find(needle).unwrap())
+        .collect();
+    assert!(order.windows(2).all(|pair| pair[0] < pair[1]));
+    assert_eq!(html.matches("class=\"recipe-folder\"").count(), 2);
+    assert!(!html.contains(&dir.path().to_string_lossy().into_owned()));
+
+    assert!(!render(&HtmlOptions::default()).contains("recipe-folder"));
+}
+
+#[test]
+fn test_summary_line() {
+    let html = render(&HtmlOptions { show_summary: true, ..HtmlOptions::default() });
+    let total = IngredientIndex::new("./test_recipes").unwrap().ingredients().len();
+    assert!(html.contains(&format!(
+        "<h1>Recipe Ingredient Index</h1>\n    <p class=\"index-summary\">{} ingredients across 3 recipes, generated from ./test_recipes</p>\n",
+        total
+    )));
+
+    let html = render(&HtmlOptions { show_summary: true, summary_dir: false, ..HtmlOptions::default() });
+    assert!(html.contains("across 3 recipes</p>"));
+    assert!(!render(&HtmlOptions::default()).contains("index-summary"));
+
+    // Absolute paths outside the working directory are shown by name only
+    let dir = common::recipe_dir(&[("pesto.cook", "Blend @basil{}."), ("salad.cook", "Add @basil{}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let html = index.generate_html_with(&HtmlOptions { show_summary: true, ..HtmlOptions::default() }).unwrap();
+    let name = dir.path().file_name().unwrap().to_string_lossy().into_owned();
+    assert!(html.contains(&format!("1 ingredient across 2 recipes, generated from {}</p>", name)));
+    assert!(!html.contains(&dir.path().to_string_lossy().into_owned()));
+}
+
+#[test]
+fn test_footer() {
+    let generated_at = Some(UNIX_EPOCH + Duration::from_secs(1_717_250_520));
+    let html = render(&HtmlOptions { footer: Footer::Auto, generated_at, ..HtmlOptions::default() });
+    let expected = format!(
+        "    <footer class=\"page-footer\">Generated 2024-06-01 14:02 UTC by cooklang-indexer {}</footer>\n</body>",
+        env!("CARGO_PKG_VERSION")
+    );
+    assert!(html.contains(&expected));
+    // A fixed time gives identical output
+    assert_eq!(html, render(&HtmlOptions { footer: Footer::Auto, generated_at, ..HtmlOptions::default() }));
+
+    let html = render(&HtmlOptions { footer: Footer::Custom("Built by <CI>".to_string()), ..HtmlOptions::default() });
+    assert!(html.contains("<footer class=\"page-footer\">Built by &lt;CI&gt;</footer>"));
+    assert!(!render(&HtmlOptions::default()).contains("<footer"));
+
+    let leap_day = Some(UNIX_EPOCH + Duration::from_secs(951_782_400));
+    let html = render(&HtmlOptions { footer: Footer::Auto, generated_at: leap_day, ..HtmlOptions::default() });
+    assert!(html.contains("Generated 2000-02-29 00:00 UTC"));
+}
+
+#[test]
+fn test_stats_footer() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let generated_at = Some(UNIX_EPOCH + Duration::from_secs(1_717_250_520));
+    let html = index.generate_html_with(&HtmlOptions { footer: Footer::Stats, generated_at, ..HtmlOptions::default() }).unwrap();
+    let expected = format!(
+        "<footer class=\"page-footer\">Generated on 2024-06-01 14:02 UTC — 3 recipes, {} ingredients</footer>",
+        index.ingredients().len()
+    );
+    assert!(html.contains(&expected), "{}", html);
+}
+
+#[test]
+fn test_tag_cloud() {
+    let html = render(&HtmlOptions { tag_cloud: true, ..HtmlOptions::default() });
+    assert!(html.contains("<nav class=\"ingredient-cloud\" aria-label=\"Ingredients by use\">"));
+
+    let font_size = |name: &str| -> f64 {
+        let link = html
+            .lines()
+            .find(|line| line.contains("font-size") && line.contains(&format!("\">{}</a>", name)))
+            .unwrap();
+        let size = link.split("font-size: ").nth(1).unwrap().split("em").next().unwrap();
+        size.parse().unwrap()
+    };
+    // garlic is in two recipes, ginger in one
+    assert!(font_size("garlic") > font_size("ginger"));
+    assert!(html.contains("<a href=\"#garlic\" style=\"font-size: 2.00em\">garlic</a>"));
+    assert!(html.contains("<a href=\"#ginger\" style=\"font-size: 0.80em\">ginger</a>"));
+
+    assert!(!render(&HtmlOptions::default()).contains("ingredient-cloud"));
+}
+
+#[test]
+fn test_localized_labels() {
+    use cooklang_indexer::{BrokenLinks, HtmlLabels};
+    use regex::Regex;
+
+    let dir = common::recipe_dir(&[
+        ("crepes.cook", ">> tags: dessert\nMix @farine{250%g} with @lait{}."),
+        ("soupe.cook", "Simmer @poireaux{2} in @lait{}."),
+    ]);
+    #[cfg(unix)]
+    {
+        use std::os::unix::ffi::OsStrExt;
+        let bad = dir.path().join(std::ffi::OsStr::from_bytes(b"cr\xeape.cook"));
+        std::fs::write(bad, "Add @farine{}.").unwrap();
+    }
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = HtmlOptions {
+        base_url: Some("https://example.com/r".to_string()),
+        broken_links: BrokenLinks::Skip,
+        lang: "fr".to_string(),
+        title: "Index des ingrédients".to_string(),
+        labels: HtmlLabels {
+            recipe: "{} recette".to_string(),
+            recipes: "{} recettes".to_string(),
+            ingredient: "{} ingrédient".to_string(),
+            ingredients: "{} ingrédients".to_string(),
+            summary: "{} dans {}".to_string(),
+            summary_dir: ", tirés de {}".to_string(),
+            summary_skipped: "{} ignorées".to_string(),
+            filter_placeholder: "Filtrer".to_string(),
+            no_matches: "Aucun résultat".to_string(),
+            untagged: "sans étiquette".to_string(),
+            generated: "Généré le {} par {}".to_string(),
+            generated_stats: "Généré le {} — {}, {}".to_string(),
+            all_ingredients: "Tous les ingrédients".to_string(),
+            all_recipes: "Toutes les recettes".to_string(),
+            source: "Source du fichier".to_string(),
+            previous_page: "Précédente".to_string(),
+            next_page: "Suivante".to_string(),
+            page_position: "Page {} sur {}".to_string(),
+            alphabet_nav: "Ingrédients par lettre".to_string(),
+            ingredient_cloud: "Ingrédients par usage".to_string(),
+            pagination: "Pages".to_string(),
+        },
+        show_summary: true,
+        filter_box: true,
+        footer: Footer::Auto,
+        ..HtmlOptions::default()
+    };
+
+    let out = tempfile::tempdir().unwrap();
+    let manifest = index.generate_site(out.path(), &options).unwrap();
+    let mut pages = vec![
+        index.generate_html_with(&options).unwrap(),
+        index.generate_tag_index_html(&options).unwrap(),
+        index.generate_recipe_index_html(&options).unwrap(),
+    ];
+    pages.extend(
+        manifest
+            .files()
+            .filter(|path| path.extension().is_some_and(|ext| ext == "html"))
+            .map(|path| std::fs::read_to_string(path).unwrap()),
+    );
+
+    // Only the visible text, without markup, styles and scripts
+    let hidden = Regex::new(r"(?s)<style>.*?</style>|<script>.*?</script>|<[^>]*>").unwrap();
+    let english = Regex::new(r"\b(recipes?|ingredients?|across|generated|skipped|linked|Filter|matches|untagged|by|All)\b").unwrap();
+    for html in &pages {
+        assert!(html.starts_with("<!DOCTYPE html>\n<html lang=\"fr\">"));
+        let text = hidden.replace_all(html, " ");
+        assert!(!english.is_match(&text), "English text left in {}", text);
+    }
+
+    let html = &pages[0];
+    assert!(html.contains("<span class=\"ingredient-count\">(2 recettes)</span>"));
+    assert!(html.contains("<p class=\"index-summary\">3 ingrédients dans 2 recettes, tirés de "));
+    #[cfg(unix)]
+    assert!(html.contains("<span class=\"skipped-count\">(1 recette ignorées)</span>"));
+    assert!(html.contains("placeholder=\"Filtrer\" aria-label=\"Filtrer\""));
+    assert!(html.contains(">Aucun résultat</p>"));
+    assert!(html.contains("<footer class=\"page-footer\">Généré le "));
+    assert!(pages[1].contains(">sans étiquette</a>"));
+    assert!(pages.iter().any(|page| page.contains(">Tous les ingrédients</a>")));
+    assert!(pages.iter().any(|page| page.contains(">Toutes les recettes</a>")));
+    assert!(pages.iter().any(|page| page.contains(">Source du fichier</a>")));
+}
+
+#[test]
+fn test_html_fragment() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions {
+        container_class: Some("pantry".to_string()),
+        footer: Footer::Custom("Bye".to_string()),
+        ..HtmlOptions::default()
+    };
+    let fragment = index.generate_html_fragment(&options).unwrap();
+    assert!(fragment.starts_with("<div class=\"pantry\">\n"));
+    assert!(fragment.ends_with("</div>\n"));
+    assert!(fragment.contains("<section class=\"ingredient\" id=\"flour\">"));
+    for page_only in ["<!DOCTYPE", "<head>", "<body>", "<main>", "<h1>", "Bye"] {
+        assert!(!fragment.contains(page_only), "{}", page_only);
+    }
+    // The full page is the fragment between its heading and its footer
+    let page = index.generate_html_with(&options).unwrap();
+    let (_, body) = page.split_once("</h1>\n").unwrap();
+    assert!(body.starts_with(&fragment));
+
+    // Without a container class there is no wrapper; the filter script comes along
+    let options = HtmlOptions { filter_box: true, ..HtmlOptions::default() };
+    let fragment = index.generate_html_fragment(&options).unwrap();
+    assert!(fragment.starts_with("    <nav class=\"alphabet-nav\" aria-label=\"Ingredients by letter\">"));
+    assert!(fragment.trim_end().ends_with("</script>"));
+}
+
+#[test]
+fn test_write_html_streams() {
+    use std::io::{self, Write};
+
+    /// Accepts a fixed number of bytes, then fails
+    struct Full(usize);
+    impl Write for Full {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            if self.0 == 0 {
+                return Err(io::Error::new(io::ErrorKind::StorageFull, "disk full"));
+            }
+            let n = buf.len().min(self.0);
+            self.0 -= n;
+            Ok(n)
+        }
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions { filter_box: true, footer: Footer::Auto, generated_at: Some(UNIX_EPOCH), ..HtmlOptions::default() };
+    let mut out = Vec::new();
+    index.write_html(&mut out, &options).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), index.generate_html_with(&options).unwrap());
+
+    let err = index.write_html(Full(1000), &options).unwrap_err();
+    assert!(format!("{:#}", err).contains("disk full"), "{:#}", err);
+}
+
+#[test]
+fn test_minify() {
+    use regex::Regex;
+
+    let options = HtmlOptions {
+        filter_box: true,
+        show_summary: true,
+        tag_cloud: true,
+        group_by_letter: true,
+        footer: Footer::Custom("Made with care".to_string()),
+        extra_css: Some("/* brand */\nh1 { font-family: \"Iowan Old Style\", serif; }".to_string()),
+        ..HtmlOptions::default()
+    };
+    let html = render(&options);
+    let minified = render(&HtmlOptions { minify: true, ..options });
+    assert!(minified.len() < html.len());
+    assert!(!minified.contains('\n'));
+    assert!(minified.contains("<style>:root{--background:Canvas;"));
+    assert!(minified.contains("h1{font-family:\"Iowan Old Style\",serif}"));
+    assert!(!minified.contains("brand"));
+    // Joined without whitespace, the cloud links are kept apart by their margin
+    assert!(minified.contains("</a><a href=\"#"));
+    assert!(minified.contains(".ingredient-cloud a{margin-right:6px}"));
+
+    // Same links and text, leaving out the whitespace of the style and script
+    let hrefs = Regex::new(r#"href="([^"]*)""#).unwrap();
+    let text = Regex::new(r">([^<]*)<").unwrap();
+    let code = Regex::new(r"(?s)<(style|script)>.*?</(style|script)>").unwrap();
+    let contents = |html: &str| {
+        let html = code.replace_all(html, "");
+        let links: Vec<String> = hrefs.captures_iter(&html).map(|c| c[1].to_string()).collect();
+        let text: Vec<String> = text
+            .captures_iter(&html)
+            .map(|c| c[1].trim().to_string())
+            .filter(|text| !text.is_empty())
+            .collect();
+        (links, text)
+    };
+    let (links, text) = contents(&html);
+    assert!(links.contains(&"pancakes.cook".to_string()));
+    assert!(text.contains(&"Made with care".to_string()));
+    assert_eq!(contents(&minified), (links, text));
+}
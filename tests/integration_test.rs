@@ -1,9 +1,168 @@
 // tests/integration_test.rs
+mod common;
+
 use cooklang_indexer::IngredientIndex;
-use std::path::Path;
+use std::fs;
 
 #[test]
 fn test_index_creation() {
     let index = IngredientIndex::new("./test_recipes").unwrap();
     assert!(!index.ingredients().is_empty());
 }
+
+#[test]
+fn test_recipes_by_complexity() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let ranked: Vec<_> = index
+        .recipes_by_complexity()
+        .into_iter()
+        .map(|(path, count)| (path.file_name().unwrap().to_str().unwrap(), count))
+        .collect();
+    assert_eq!(ranked, [("chicken_curry.cook", 8), ("tomato-soup.cook", 6), ("pancakes.cook", 4)]);
+}
+
+#[test]
+fn test_ranked_ingredients_page() {
+    let dir = common::recipe_dir(&[
+        ("a.cook", "Mix @salt{} with @flour{} and @eggs{}."),
+        ("b.cook", "Beat @eggs{} with @salt{} and @butter{}."),
+        ("c.cook", "Fry @eggs{} in @butter{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let page: Vec<_> = index.ranked_ingredients(1, 2).into_iter().map(|(name, count)| (name.as_str(), count)).collect();
+    // eggs (3) leads; butter and salt tie on 2 and go alphabetically
+    assert_eq!(page, [("butter", 2), ("salt", 2)]);
+
+    assert_eq!(index.ranked_ingredients(0, 1)[0].0, "eggs");
+    assert!(index.ranked_ingredients(4, 2).is_empty());
+}
+
+#[test]
+fn test_ingredients_for_language() {
+    let dir = common::recipe_dir(&[
+        ("pancakes.cook", ">> language: en\nWhisk @flour{} with @milk{} and @eggs{}."),
+        ("scones.cook", ">> Language: en-GB\nRub @butter{} into @flour{}."),
+        ("tortilla.cook", ">> language: es\nFríe las @patatas{} y añade los @huevos{} y la @cebolla{}."),
+        ("untagged.cook", "Toast @bread{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+
+    assert_eq!(index.ingredients_for_language("en"), ["butter", "eggs", "flour", "milk"]);
+    assert_eq!(index.ingredients_for_language("ES"), ["cebolla", "huevos", "patatas"]);
+    // Regional variants only match themselves
+    assert_eq!(index.ingredients_for_language("en-gb"), ["butter", "flour"]);
+    assert!(index.ingredients_for_language("e").is_empty());
+    assert!(index.ingredients_for_language("fr").is_empty());
+}
+
+#[test]
+fn test_missing_from_expected_vocabulary() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let missing = index.missing_from(&["Flour", "saffron", "eggs", "sumac"]);
+    assert_eq!(missing, ["saffron", "sumac"]);
+}
+
+#[test]
+fn test_recipes_without_staple() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let unsalted = index.recipes_without("Salt");
+    assert_eq!(unsalted.len(), 1);
+    assert!(unsalted[0].ends_with("pancakes.cook"));
+
+    assert_eq!(index.recipes_without("saffron").len(), 3);
+    assert!(index.recipes_without("flour").iter().all(|path| !path.ends_with("pancakes.cook")));
+}
+
+#[test]
+fn test_recipes_without_lists_recipes_without_ingredients() {
+    let dir = common::recipe_dir(&[
+        ("toast.cook", "Toast @bread{} with @salt{}."),
+        ("water.cook", "Boil some water."),
+    ]);
+    let mut index = IngredientIndex::new(dir.path()).unwrap();
+    let water = dir.path().join("water.cook");
+    // The recipe without ingredients uses none of them
+    assert_eq!(index.recipes_without("salt"), [&water]);
+    assert_eq!(index.recipes_without("pepper"), [&dir.path().join("toast.cook"), &water]);
+
+    fs::write(&water, "Boil some water with @salt{}.").unwrap();
+    index.upsert_file(&water).unwrap();
+    assert!(index.recipes_without("salt").is_empty());
+    fs::write(&water, "Boil some water.").unwrap();
+    index.upsert_file(&water).unwrap();
+    assert_eq!(index.recipes_without("salt"), [&water]);
+    index.remove_file(&water);
+    assert!(index.recipes_without("salt").is_empty());
+
+    // Merged recipes without ingredients are listed as well
+    let other = common::recipe_dir(&[("ice.cook", "Freeze some water.")]);
+    index.merge(IngredientIndex::new(other.path()).unwrap());
+    assert_eq!(index.recipes_without("salt"), [&other.path().join("ice.cook")]);
+}
+
+#[test]
+fn test_intra_recipe_duplicates() {
+    let dir = common::recipe_dir(&[
+        ("pie.cook", "= Crust\nRub @butter{50%g} into @flour{}.\n= Filling\nMelt @butter{20%g}.\nBrush with @Butter{}."),
+        ("toast.cook", "Spread @butter{} on @bread{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.intra_recipe_duplicates(), [(dir.path().join("pie.cook"), "butter".to_string(), 3)]);
+
+    assert!(IngredientIndex::new("./test_recipes").unwrap().intra_recipe_duplicates().is_empty());
+}
+
+#[test]
+fn test_unexpected_ingredients() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let pantry = [
+        "butter", "chicken thighs", "coconut milk", "curry paste", "Eggs", "flour", "garlic",
+        "ginger", "milk", "oil", "onion", "salt",
+    ];
+    assert_eq!(index.unexpected(&pantry), ["stock", "tomatoes"]);
+}
+
+#[test]
+fn test_merge_indexes_from_different_roots() {
+    let home = common::recipe_dir(&[
+        ("pancakes.cook", "Mix @flour{} and @milk{}."),
+        ("toast.cook", "Spread @butter{} on @bread{}."),
+    ]);
+    let family = common::recipe_dir(&[("soups/leek.cook", "Sweat @leeks{} in @butter{}.")]);
+    let mut index = IngredientIndex::new(home.path()).unwrap();
+    index.merge(IngredientIndex::new(family.path()).unwrap());
+
+    assert_eq!(index.ingredients(), ["bread", "butter", "flour", "leeks", "milk"]);
+    let mut butter = vec![home.path().join("toast.cook"), family.path().join("soups/leek.cook")];
+    butter.sort();
+    assert_eq!(index.get_recipes_for_ingredient("butter"), Some(&butter));
+
+    // Merged recipes stay relative to the directory they were found in
+    let csv = index.generate_csv();
+    assert!(csv.contains("leeks,soups/leek.cook\r\n"), "{}", csv);
+    assert!(csv.contains("milk,pancakes.cook\r\n"), "{}", csv);
+    let html = index.generate_html("https://example.com/r").unwrap();
+    assert!(html.contains("https://example.com/r/soups/leek"), "{}", html);
+
+    // Merging a rescan of the same directory replaces rather than duplicates
+    index.merge(IngredientIndex::new(home.path()).unwrap());
+    assert_eq!(index.get_recipes_for_ingredient("milk").map(Vec::len), Some(1));
+    assert_eq!(index.get_recipes_for_ingredient("butter"), Some(&butter));
+}
+
+#[test]
+fn test_best_match() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let pantry = ["Onion", "garlic", "butter", "salt", "flour", "eggs"];
+    let (path, matched, total) = index.best_match(&pantry).unwrap();
+    assert!(path.ends_with("tomato-soup.cook"));
+    assert_eq!((matched, total), (4, 6));
+
+    // Equally good matches go to the recipe missing the fewest ingredients
+    let (path, matched, total) = index.best_match(&["onion", "garlic", "milk", "eggs"]).unwrap();
+    assert!(path.ends_with("pancakes.cook"));
+    assert_eq!((matched, total), (2, 4));
+
+    assert!(index.best_match(&["saffron"]).is_none());
+    assert!(index.best_match(&[]).is_none());
+}
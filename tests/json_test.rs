@@ -0,0 +1,44 @@
+// tests/json_test.rs
+mod common;
+
+use cooklang_indexer::IngredientIndex;
+use serde_json::{json, Value};
+
+#[test]
+fn test_generate_json() {
+    let dir = common::recipe_dir(&[
+        ("soups/leek-soup.cook", ">> tags: soup, winter\nSweat @leeks{2} in @butter{}, then add @leeks{1} and @stock{1%l}."),
+        ("toast.cook", ">> title: Butter Toast\nSpread @butter{} on @bread{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let json: Value = serde_json::from_str(&index.generate_json().unwrap()).unwrap();
+
+    assert_eq!(json["base_dir"], dir.path().display().to_string());
+    assert_eq!(
+        json["ingredients"],
+        json!([
+            {"name": "bread", "recipes": ["toast.cook"]},
+            {"name": "butter", "recipes": ["soups/leek-soup.cook", "toast.cook"]},
+            {"name": "leeks", "recipes": ["soups/leek-soup.cook"]},
+            {"name": "stock", "recipes": ["soups/leek-soup.cook"]},
+        ])
+    );
+    assert_eq!(
+        json["recipes"],
+        json!([
+            {"path": "soups/leek-soup.cook", "title": "Leek Soup", "tags": ["soup", "winter"], "ingredients": ["leeks", "butter", "stock"]},
+            {"path": "toast.cook", "title": "Butter Toast", "tags": [], "ingredients": ["butter", "bread"]},
+        ])
+    );
+}
+
+#[test]
+fn test_generate_ndjson() {
+    let dir = common::recipe_dir(&[("toast.cook", "Spread @butter{} on @bread{}."), ("soup.cook", "Melt @butter{}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let ndjson = index.generate_ndjson().unwrap();
+
+    let lines: Vec<Value> = ndjson.lines().map(|line| serde_json::from_str(line).unwrap()).collect();
+    assert_eq!(lines, [json!({"name": "bread", "recipes": ["toast.cook"]}), json!({"name": "butter", "recipes": ["soup.cook", "toast.cook"]})]);
+    assert!(ndjson.ends_with("]}\n"));
+}
@@ -0,0 +1,52 @@
+// tests/logging_test.rs
+mod common;
+
+use cooklang_indexer::IngredientIndex;
+use log::{Level, Log, Metadata, Record};
+use std::sync::Mutex;
+
+/// Logger that records every message so tests can assert on them
+struct CaptureLogger {
+    records: Mutex<Vec<(Level, String)>>,
+}
+
+impl Log for CaptureLogger {
+    fn enabled(&self, _metadata: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        self.records
+            .lock()
+            .unwrap()
+            .push((record.level(), record.args().to_string()));
+    }
+
+    fn flush(&self) {}
+}
+
+static LOGGER: CaptureLogger = CaptureLogger {
+    records: Mutex::new(Vec::new()),
+};
+
+#[test]
+fn test_warns_on_unreadable_file() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Trace);
+
+    let dir = common::recipe_dir(&[("pesto.cook", "Blend @basil{} and @pine nuts{}.")]);
+    // Not valid UTF-8, so it cannot be read as a recipe
+    let broken = dir.path().join("broken.cook");
+    std::fs::write(&broken, b"@flour{}\xff\xfe").unwrap();
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert!(index.get_recipes_for_ingredient("basil").is_some());
+
+    let records = LOGGER.records.lock().unwrap();
+    assert!(records
+        .iter()
+        .any(|(level, msg)| *level == Level::Warn && msg.contains(&broken.display().to_string())));
+    assert!(records
+        .iter()
+        .any(|(level, msg)| *level == Level::Info && msg.contains("1 skipped")));
+}
@@ -0,0 +1,104 @@
+// tests/parse_test.rs
+mod common;
+
+use cooklang_indexer::{Ingredient, IngredientAmount, IngredientIndex, Modifier, ShoppingItem};
+
+/// Indexes a single-recipe directory and returns its shopping list items
+fn items(content: &str) -> Vec<ShoppingItem> {
+    let dir = common::recipe_dir(&[("recipe.cook", content)]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let list = index.shopping_list(&[dir.path().join("recipe.cook")]);
+    list.groups.into_iter().flat_map(|group| group.items).collect()
+}
+
+fn summary(content: &str) -> Vec<String> {
+    items(content).iter().map(ToString::to_string).collect()
+}
+
+#[test]
+fn test_preparation_note_after_braces() {
+    assert_eq!(summary("Top with @cheese{1%cup}(grated) and @chives{}."), ["cheese: 1 cup", "chives"]);
+    // An `@` inside the note is not an ingredient
+    assert_eq!(summary("Add @stock{1%l}(or @water) slowly."), ["stock: 1 l"]);
+}
+
+#[test]
+fn test_nested_braces_stay_in_the_amount() {
+    assert_eq!(summary("Mix @x{a{b}c} with @y{2}."), ["x: a{b}c", "y: 2"]);
+}
+
+#[test]
+fn test_double_braces_keep_the_first_group() {
+    assert_eq!(summary("Use @salt{1%tsp}{} and @pepper{}{2}."), ["pepper", "salt: 1 tsp"]);
+}
+
+#[test]
+fn test_unclosed_brace_keeps_the_name() {
+    assert_eq!(summary("Add @sugar{2%tbsp and @flour{100%g}."), ["flour: 100 g", "sugar"]);
+}
+
+#[test]
+fn test_recipe_references() {
+    let dir = common::recipe_dir(&[
+        ("sauces/base-sauce.cook", "Simmer @tomatoes{4} with @garlic{2%cloves}."),
+        ("sauces/pesto.cook", "Blend @basil{1%bunch}."),
+        ("mains/lasagna.cook", "Layer @../sauces/base-sauce{1%cup} with @pasta{12%sheets}.\nTop with @recipe:Pesto{2%tbsp}."),
+        ("mains/gnocchi.cook", "Toss @potato gnocchi{500%g} with @./missing{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+
+    let lasagna = dir.path().join("mains/lasagna.cook");
+    assert_eq!(
+        index.recipe_dependencies(&lasagna),
+        [dir.path().join("sauces/base-sauce.cook"), dir.path().join("sauces/pesto.cook")]
+    );
+    // References are not ingredients
+    assert!(index.get_recipes_for_ingredient("../sauces/base-sauce").is_none());
+    assert!(index.get_recipes_for_ingredient("recipe:pesto").is_none());
+    assert_eq!(index.get_recipes_for_ingredient("pasta").unwrap(), &[lasagna]);
+
+    assert!(index.recipe_dependencies(dir.path().join("mains/gnocchi.cook")).is_empty());
+}
+
+#[test]
+fn test_sibling_recipe_reference() {
+    let dir = common::recipe_dir(&[
+        ("base-sauce.cook", "Simmer @tomatoes{4}."),
+        ("pasta.cook", "Toss @spaghetti{200%g} with @./base-sauce{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.recipe_dependencies(dir.path().join("pasta.cook")), [dir.path().join("base-sauce.cook")]);
+}
+
+#[test]
+fn test_reference_to_cooklang_file() {
+    let dir = common::recipe_dir(&[
+        ("base-sauce.cooklang", "Simmer @tomatoes{4}."),
+        ("pasta.cook", "Toss @spaghetti{200%g} with @./base-sauce{}."),
+    ]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.recipe_dependencies(dir.path().join("pasta.cook")), [dir.path().join("base-sauce.cooklang")]);
+}
+
+#[test]
+fn test_ingredient_details() {
+    let dir = common::recipe_dir(&[("recipe.cook", "Top with @?Parmesan{50%g}(grated), @-salt{} and @&onion{}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let details = &index.recipes()[0].ingredients;
+    assert_eq!(
+        details[0],
+        Ingredient {
+            name: "parmesan".to_string(),
+            quantity: Some(IngredientAmount { quantity: Some("50".to_string()), unit: Some("g".to_string()) }),
+            note: Some("grated".to_string()),
+            modifier: Modifier::Optional,
+        }
+    );
+    assert_eq!((details[1].quantity.as_ref(), details[1].note.as_ref(), details[1].modifier), (None, None, Modifier::Hidden));
+    assert_eq!(details[2].modifier, Modifier::Reference);
+
+    // Displays as the indexed name, without the modifier
+    let names: Vec<String> = details.iter().map(ToString::to_string).collect();
+    assert_eq!(names, ["parmesan", "salt", "onion"]);
+    assert_eq!(index.ingredients(), ["onion", "parmesan", "salt"]);
+}
@@ -0,0 +1,51 @@
+// tests/search_test.rs
+use cooklang_indexer::{IngredientIndex, Matching, SearchOptions};
+use std::path::{Path, PathBuf};
+
+fn names(recipes: Vec<&PathBuf>) -> Vec<&str> {
+    recipes.iter().map(|path| path.file_stem().unwrap().to_str().unwrap()).collect()
+}
+
+#[test]
+fn test_matching_ingredients() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    assert_eq!(index.matching_ingredients(" Garlic", Matching::Exact), ["garlic"]);
+    assert!(index.matching_ingredients("garl", Matching::Exact).is_empty());
+    assert_eq!(index.matching_ingredients("milk", Matching::Substring), ["coconut milk", "milk"]);
+    assert_eq!(index.matching_ingredients("C", Matching::Prefix), ["chicken thighs", "coconut milk", "curry paste"]);
+    assert_eq!(index.matching_ingredients("tomatos", Matching::Fuzzy), ["tomatoes"]);
+    // A single word of a longer name is enough
+    assert_eq!(index.matching_ingredients("chiken", Matching::Fuzzy), ["chicken thighs"]);
+}
+
+#[test]
+fn test_search_combines_terms() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let any = SearchOptions::default();
+    assert_eq!(names(index.search(&["garlic"], &any)), ["chicken_curry", "tomato-soup"]);
+    assert_eq!(names(index.search(&["garlic", "butter"], &any)), ["chicken_curry", "pancakes", "tomato-soup"]);
+
+    let all = SearchOptions { all: true, ..SearchOptions::default() };
+    assert_eq!(names(index.search(&["garlic", "butter"], &all)), ["tomato-soup"]);
+    assert!(index.search(&["garlic", "flour"], &all).is_empty());
+
+    let substring = SearchOptions { matching: Matching::Substring, all: true };
+    assert_eq!(names(index.search(&["milk", "oil"], &substring)), ["chicken_curry"]);
+    assert!(index.search(&[], &any).is_empty());
+}
+
+#[test]
+fn test_from_json_reads_an_export_back() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let loaded = IngredientIndex::from_json(&index.generate_json().unwrap()).unwrap();
+
+    assert_eq!(loaded.base_dir(), Path::new("./test_recipes"));
+    assert_eq!(loaded.ingredients(), index.ingredients());
+    for name in index.ingredients() {
+        assert_eq!(loaded.get_recipes_for_ingredient(name), index.get_recipes_for_ingredient(name), "{}", name);
+    }
+    assert_eq!(loaded.generate_json().unwrap(), index.generate_json().unwrap());
+    assert_eq!(names(loaded.search(&["garlc"], &SearchOptions { matching: Matching::Fuzzy, all: false })).len(), 2);
+
+    assert!(IngredientIndex::from_json("{\"ingredients\": 3}").is_err());
+}
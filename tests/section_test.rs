@@ -0,0 +1,32 @@
+// tests/section_test.rs
+mod common;
+
+use cooklang_indexer::IngredientIndex;
+
+#[test]
+fn test_ingredients_attributed_to_sections() {
+    let dir = common::recipe_dir(&[(
+        "pie.cook",
+        "Preheat the oven.\n\
+         \n\
+         = Dough\n\
+         Rub @butter{100%g} into @flour{200%g}, then add @water{3%tbsp}.\n\
+         \n\
+         == Filling ==\n\
+         Toss @apples{4} with @sugar{50%g} and a little more @butter{20%g}.\n",
+    )]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let pie = dir.path().join("pie.cook");
+
+    assert_eq!(index.ingredients_in_section(&pie, "Dough"), vec!["butter", "flour", "water"]);
+    assert_eq!(index.ingredients_in_section(&pie, "Filling"), vec!["apples", "sugar", "butter"]);
+    assert!(index.ingredients_in_section(&pie, "Topping").is_empty());
+    assert!(index.ingredients_in_section(dir.path().join("missing.cook"), "Dough").is_empty());
+}
+
+#[test]
+fn test_unsectioned_recipe() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let soup = std::path::Path::new("./test_recipes/soups/tomato-soup.cook");
+    assert!(index.ingredients_in_section(soup, "").contains(&"tomatoes".to_string()));
+}
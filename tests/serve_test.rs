@@ -0,0 +1,202 @@
+// tests/serve_test.rs
+#![cfg(feature = "serve")]
+
+mod common;
+
+use cooklang_indexer::{HtmlOptions, IngredientIndex, RecipeServer};
+use std::io::{Read, Write};
+use std::net::{SocketAddr, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/// Sends a bare HTTP/1.0 request, returning the status code and the body
+fn request(addr: SocketAddr, method: &str, path: &str) -> (u16, String) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "{} {} HTTP/1.0\r\nHost: localhost\r\n\r\n", method, path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let status = response[9..12].parse().unwrap();
+    let body = response.split_once("\r\n\r\n").map_or("", |(_, body)| body).to_string();
+    (status, body)
+}
+
+/// Sends a GET request to a JSON endpoint, returning the status code, the
+/// content type and the parsed body
+fn get_json(addr: SocketAddr, path: &str) -> (u16, String, serde_json::Value) {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    write!(stream, "GET {} HTTP/1.0\r\nHost: localhost\r\n\r\n", path).unwrap();
+    let mut response = String::new();
+    stream.read_to_string(&mut response).unwrap();
+    let (head, body) = response.split_once("\r\n\r\n").unwrap();
+    let content_type = head
+        .lines()
+        .find_map(|line| line.strip_prefix("Content-Type: "))
+        .unwrap_or_default()
+        .to_string();
+    (head[9..12].parse().unwrap(), content_type, serde_json::from_str(body).unwrap())
+}
+
+fn start(index: IngredientIndex) -> (Arc<RecipeServer>, SocketAddr) {
+    let server = Arc::new(RecipeServer::bind(index, "127.0.0.1:0", &HtmlOptions::default()).unwrap());
+    let addr = server.local_addr().unwrap();
+    let running = Arc::clone(&server);
+    thread::spawn(move || running.run());
+    (server, addr)
+}
+
+#[test]
+fn test_serves_index_and_recipes() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let (server, addr) = start(index);
+
+    let (status, html) = request(addr, "GET", "/");
+    assert_eq!(status, 200);
+    assert!(html.contains("href=\"/r/mains/chicken_curry.cook\""), "{}", html);
+
+    // The links resolve to the recipe files
+    let (status, recipe) = request(addr, "GET", "/r/mains/chicken_curry.cook");
+    assert_eq!(status, 200);
+    assert_eq!(recipe, std::fs::read_to_string("test_recipes/mains/chicken_curry.cook").unwrap());
+
+    let (status, json) = request(addr, "GET", "/index.json?v=1");
+    assert_eq!(status, 200);
+    let json: serde_json::Value = serde_json::from_str(&json).unwrap();
+    assert_eq!(json["recipes"].as_array().unwrap().len(), 3);
+
+    assert_eq!(request(addr, "HEAD", "/r/pancakes.cook"), (200, String::new()));
+    assert_eq!(request(addr, "GET", "/r/missing.cook").0, 404);
+    assert_eq!(request(addr, "GET", "/r/mains").0, 404);
+    assert_eq!(request(addr, "POST", "/").0, 405);
+    server.unblock();
+}
+
+#[test]
+fn test_rejects_paths_outside_the_recipes_dir() {
+    let outside = common::recipe_dir(&[("secret.txt", "hunter2"), ("recipes/toast.cook", "Toast @bread{}.")]);
+    let index = IngredientIndex::new(outside.path().join("recipes")).unwrap();
+    #[cfg(unix)]
+    std::os::unix::fs::symlink(outside.path().join("secret.txt"), outside.path().join("recipes/link.txt")).unwrap();
+    let (server, addr) = start(index);
+
+    assert_eq!(request(addr, "GET", "/r/toast.cook").0, 200);
+    for path in [
+        "/r/../secret.txt",
+        "/r/%2e%2e/secret.txt",
+        "/r/%2E%2E%2Fsecret.txt",
+        "/r/./../secret.txt",
+        "/r/link.txt",
+        "/r//etc/passwd",
+        "/secret.txt",
+    ] {
+        let (status, body) = request(addr, "GET", path);
+        assert_eq!(status, 404, "{}", path);
+        assert!(!body.contains("hunter2"), "{}", path);
+    }
+    server.unblock();
+}
+
+#[test]
+fn test_api_ingredients() {
+    let (server, addr) = start(IngredientIndex::new("./test_recipes").unwrap());
+
+    let (status, content_type, json) = get_json(addr, "/api/ingredients");
+    assert_eq!((status, content_type.as_str()), (200, "application/json"));
+    let ingredients = json.as_array().unwrap();
+    assert_eq!(ingredients.len(), 14);
+    assert_eq!(ingredients[0], serde_json::json!({"name": "butter", "recipes": 2}));
+    assert!(ingredients.contains(&serde_json::json!({"name": "garlic", "recipes": 2})));
+
+    // Names with spaces arrive percent-encoded
+    let (status, _, json) = get_json(addr, "/api/ingredients/coconut%20milk/recipes");
+    assert_eq!(status, 200);
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "ingredient": "coconut milk",
+            "recipes": [{"path": "mains/chicken_curry.cook", "title": "Chicken Curry", "url": "/r/mains/chicken_curry.cook"}],
+        })
+    );
+    assert_eq!(get_json(addr, "/api/ingredients/Butter/recipes").2["recipes"].as_array().unwrap().len(), 2);
+
+    let (status, content_type, json) = get_json(addr, "/api/ingredients/saffron/recipes");
+    assert_eq!((status, content_type.as_str()), (404, "application/json"));
+    assert!(json["error"].as_str().unwrap().contains("saffron"));
+
+    let (status, _, json) = get_json(addr, "/api/recipes/soups/tomato-soup.cook/ingredients");
+    assert_eq!(status, 200);
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "path": "soups/tomato-soup.cook",
+            "title": "Tomato Soup",
+            "ingredients": ["onion", "garlic", "butter", "tomatoes", "stock", "salt"],
+        })
+    );
+    assert_eq!(get_json(addr, "/api/recipes/missing.cook/ingredients").0, 404);
+    assert_eq!(get_json(addr, "/api/recipes/..%2Ftest_recipes%2Fpancakes.cook/ingredients").0, 404);
+    server.unblock();
+}
+
+#[test]
+fn test_api_reads_plus_literally_in_paths_and_finds_merged_recipes() {
+    let dir = common::recipe_dir(&[
+        ("main/salt+pepper.cook", "Season with @salt{} and @pepper{}."),
+        ("extra/soups/leek.cook", "Simmer @leek{2}."),
+    ]);
+    let mut index = IngredientIndex::new(dir.path().join("main")).unwrap();
+    index.merge(IngredientIndex::new(dir.path().join("extra")).unwrap());
+    let (server, addr) = start(index);
+
+    let (status, _, json) = get_json(addr, "/api/recipes/salt+pepper.cook/ingredients");
+    assert_eq!(status, 200);
+    assert_eq!(json["ingredients"], serde_json::json!(["salt", "pepper"]));
+    assert_eq!(request(addr, "GET", "/r/salt+pepper.cook").0, 200);
+
+    let (status, _, json) = get_json(addr, "/api/recipes/soups/leek.cook/ingredients");
+    assert_eq!(status, 200);
+    assert_eq!(json["path"], "soups/leek.cook");
+    server.unblock();
+}
+
+#[test]
+fn test_api_search() {
+    let (server, addr) = start(IngredientIndex::new("./test_recipes").unwrap());
+
+    let (status, content_type, json) = get_json(addr, "/api/search?q=coconut+mlk&mode=fuzzy");
+    assert_eq!((status, content_type.as_str()), (200, "application/json"));
+    assert_eq!(json["query"], serde_json::json!(["coconut mlk"]));
+    assert_eq!(json["mode"], "fuzzy");
+    assert_eq!(json["ingredients"], serde_json::json!(["coconut milk"]));
+    assert_eq!(json["recipes"][0]["path"], "mains/chicken_curry.cook");
+
+    let json = get_json(addr, "/api/search?q=to&mode=prefix").2;
+    assert_eq!(json["ingredients"], serde_json::json!(["tomatoes"]));
+    let json = get_json(addr, "/api/search?q=milk&mode=substring").2;
+    assert_eq!(json["ingredients"], serde_json::json!(["coconut milk", "milk"]));
+    assert_eq!(json["recipes"].as_array().unwrap().len(), 2);
+
+    // Every term must match with all=true
+    let json = get_json(addr, "/api/search?q=garlic&q=butter&all=true").2;
+    assert_eq!(json["recipes"], serde_json::json!([{"path": "soups/tomato-soup.cook", "title": "Tomato Soup", "url": "/r/soups/tomato-soup.cook"}]));
+
+    assert_eq!(get_json(addr, "/api/search?q=garlic&mode=regex").0, 400);
+    assert_eq!(get_json(addr, "/api/search").0, 400);
+    assert_eq!(get_json(addr, "/api/nothing").0, 404);
+    server.unblock();
+}
+
+#[test]
+fn test_refresh_serves_changed_recipes() {
+    let dir = common::recipe_dir(&[("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}.")]);
+    let (server, addr) = start(IngredientIndex::new(dir.path()).unwrap());
+
+    let aioli = dir.path().join("aioli.cook");
+    std::fs::write(&aioli, "Pound @garlic{2%cloves} with @oil{}.").unwrap();
+    let report = server.refresh_files(std::slice::from_ref(&aioli)).unwrap();
+    assert_eq!(report.updated, [aioli]);
+
+    assert_eq!(get_json(addr, "/api/ingredients/oil/recipes").2["recipes"][0]["path"], "aioli.cook");
+    let (_, html) = request(addr, "GET", "/");
+    assert!(html.contains("href=\"/r/aioli.cook\""), "{}", html);
+    server.unblock();
+}
@@ -0,0 +1,91 @@
+// tests/shopping_test.rs
+mod common;
+
+use cooklang_indexer::{Aisles, IngredientIndex, ShoppingAmount, ShoppingOptions};
+
+#[test]
+fn test_shopping_list_merges_quantities() {
+    let dir = common::recipe_dir(&[
+        ("bread.cook", "Mix @flour{500%g} with @water{300%ml} and @salt{a pinch}."),
+        ("scones.cook", "Rub @butter{50%g} into @flour{1/2%kg} and @Flour{200%g}, add @salt{1%tsp}."),
+    ]);
+    let index = IngredientIndex::builder(dir.path())
+        .aisles(Aisles::parse("[baking]\nflour\n[dairy]\nbutter\n"))
+        .build()
+        .unwrap();
+    let list = index.shopping_list(&[dir.path().join("bread.cook"), dir.path().join("scones.cook")]);
+
+    let flour = &list.groups[0].items[0];
+    assert_eq!(flour.name, "flour");
+    assert_eq!(
+        flour.amounts,
+        vec![
+            ShoppingAmount { quantity: 700.0, unit: Some("g".to_string()) },
+            ShoppingAmount { quantity: 0.5, unit: Some("kg".to_string()) },
+        ]
+    );
+    assert_eq!(
+        list.to_string(),
+        "[baking]\n- flour: 700 g + 0.5 kg\n[dairy]\n- butter: 50 g\n[other]\n- salt: 1 tsp + a pinch\n- water: 300 ml\n"
+    );
+
+    // Low-memory indexes keep the amounts
+    let lean = IngredientIndex::builder(dir.path())
+        .aisles(Aisles::parse("[baking]\nflour\n[dairy]\nbutter\n"))
+        .low_memory(true)
+        .build()
+        .unwrap();
+    let lean_list = lean.shopping_list(&[dir.path().join("bread.cook"), dir.path().join("scones.cook")]);
+    assert_eq!(lean_list.to_string(), list.to_string());
+}
+
+#[test]
+fn test_unit_glued_to_quantity() {
+    let dir = common::recipe_dir(&[("latte.cook", "Steam @milk{200ml}, add @sugar{1/2tsp} and @ice{3 cubes}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let list = index.shopping_list(&[dir.path().join("latte.cook")]);
+    let items = &list.groups[0].items;
+
+    assert_eq!(items[1].name, "milk");
+    assert_eq!(items[1].amounts, vec![ShoppingAmount { quantity: 200.0, unit: Some("ml".to_string()) }]);
+    assert_eq!(items[2].amounts, vec![ShoppingAmount { quantity: 0.5, unit: Some("tsp".to_string()) }]);
+    // Only units glued to the number are split off
+    assert_eq!(items[0].other, vec!["3 cubes".to_string()]);
+}
+
+#[test]
+fn test_scaled_list_without_staples() {
+    let dir = common::recipe_dir(&[("bread.cook", "Mix @flour{1/2%kg} with @water{300%ml}, @Salt{1%tsp} and @yeast{a sachet}.")]);
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = ShoppingOptions { scale: 3.0, staples: vec![" SALT".to_string(), "water".to_string()] };
+    let list = index.shopping_list_with(&[dir.path().join("bread.cook")], &options);
+    // Quantities that are not numbers cannot be scaled
+    assert_eq!(list.to_string(), "- flour: 1.5 kg\n- yeast: a sachet\n");
+}
+
+#[test]
+fn test_shopping_list_as_cooklang_and_json() {
+    let dir = common::recipe_dir(&[
+        ("bread.cook", "Mix @flour{500%g} with @water{300%ml} and @salt{a pinch}."),
+        ("scones.cook", "Rub @butter{50%g} into @flour{1/2%kg}, add @salt{1%tsp} and @eggs{2}."),
+    ]);
+    let index = IngredientIndex::builder(dir.path()).aisles(Aisles::parse("[baking]\nflour\n")).build().unwrap();
+    let recipes = [dir.path().join("bread.cook"), dir.path().join("scones.cook")];
+    let list = index.shopping_list(&recipes);
+
+    let cooklang = list.to_cooklang();
+    assert_eq!(
+        cooklang,
+        "= baking\n@flour{500%g} @flour{0.5%kg}\n= other\n@butter{50%g}\n@eggs{2}\n@salt{1%tsp} @salt{a pinch}\n@water{300%ml}\n"
+    );
+    // Reading it back gives the same list
+    let copy = common::recipe_dir(&[("list.cook", &cooklang)]);
+    let reread = IngredientIndex::builder(copy.path()).aisles(Aisles::parse("[baking]\nflour\n")).build().unwrap();
+    assert_eq!(reread.shopping_list(&[copy.path().join("list.cook")]), list);
+
+    let json = serde_json::to_value(&list).unwrap();
+    assert_eq!(json["groups"][0]["aisle"], "baking");
+    assert_eq!(json["groups"][0]["items"][0]["amounts"][1], serde_json::json!({"quantity": 0.5, "unit": "kg"}));
+    assert!(json["groups"][1]["aisle"].is_null());
+    assert_eq!(json["groups"][1]["items"][3]["other"], serde_json::json!([]));
+}
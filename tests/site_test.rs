@@ -0,0 +1,209 @@
+// tests/site_test.rs
+mod common;
+
+use cooklang_indexer::{HtmlOptions, IngredientIndex};
+use regex::Regex;
+use std::fs;
+use std::path::Path;
+
+fn hrefs(html: &str) -> Vec<String> {
+    let href_regex = Regex::new(r##"href="([^"#]+)""##).unwrap();
+    href_regex.captures_iter(html).map(|cap| cap[1].to_string()).collect()
+}
+
+#[test]
+fn test_generate_site_link_graph() {
+    let recipes = common::recipe_dir(&[
+        ("pancakes.cook", "Mix @flour{200%g} with @creme fraiche{1%tbsp}."),
+        ("soups/tomato-soup.cook", "Stir @crème fraîche{2%tbsp} into @tomatoes{4}."),
+    ]);
+    let out = tempfile::tempdir().unwrap();
+    let index = IngredientIndex::new(recipes.path()).unwrap();
+
+    let manifest = index.generate_site(out.path(), &HtmlOptions::default()).unwrap();
+    assert!(manifest.warnings.is_empty());
+    let pages: Vec<_> = manifest.ingredient_pages.iter().map(|(name, path)| (name.as_str(), path.clone())).collect();
+    let pages_dir = out.path().join("ingredients");
+    assert_eq!(
+        pages,
+        [
+            ("creme fraiche", pages_dir.join("creme-fraiche.html")),
+            ("crème fraîche", pages_dir.join("creme-fraiche-2.html")),
+            ("flour", pages_dir.join("flour.html")),
+            ("tomatoes", pages_dir.join("tomatoes.html")),
+        ]
+    );
+
+    // Every link on the index leads to a written page, and every page links back
+    // to the index and to recipes that exist
+    let index_html = fs::read_to_string(&manifest.index).unwrap();
+    let linked: Vec<_> = hrefs(&index_html).iter().map(|href| out.path().join(href)).collect();
+    assert_eq!(linked[0], manifest.recipe_index);
+    assert_eq!(linked[1..], manifest.ingredient_pages.iter().map(|(_, path)| path.clone()).collect::<Vec<_>>());
+    let recipe_index_html = fs::read_to_string(&manifest.recipe_index).unwrap();
+    let linked: Vec<_> = hrefs(&recipe_index_html).iter().map(|href| out.path().join(href)).collect();
+    assert_eq!(linked[0], manifest.index);
+    assert_eq!(linked[1..], manifest.recipe_pages.iter().map(|(_, path)| path.clone()).collect::<Vec<_>>());
+    for (_, page) in &manifest.ingredient_pages {
+        let html = fs::read_to_string(page).unwrap();
+        let links = hrefs(&html);
+        assert_eq!(links[0], "../index.html");
+        assert!(links.len() > 1);
+        for href in &links {
+            let target = pages_dir.join(href);
+            assert!(target.exists(), "{} links to missing {}", page.display(), target.display());
+        }
+    }
+    let tomatoes = fs::read_to_string(pages_dir.join("tomatoes.html")).unwrap();
+    assert!(tomatoes.contains("<h1>tomatoes</h1>"));
+    assert!(tomatoes.contains("<a href=\"../recipes/soups/tomato-soup.html\">Tomato Soup</a>"));
+}
+
+#[test]
+fn test_generate_site_recipe_pages() {
+    let recipes = common::recipe_dir(&[
+        ("pancakes.cook", ">> title: Fluffy Pancakes\nMix @flour{200%g} with @milk{} and more @flour{}."),
+        ("soups/tomato-soup.cook", "Simmer @tomatoes{4} in @milk{}."),
+    ]);
+    let out = tempfile::tempdir().unwrap();
+    let index = IngredientIndex::new(recipes.path()).unwrap();
+    let manifest = index.generate_site(out.path(), &HtmlOptions::default()).unwrap();
+
+    let recipes_dir = out.path().join("recipes");
+    let soup_page = recipes_dir.join("soups/tomato-soup.html");
+    assert_eq!(manifest.recipe_pages.len(), 2);
+    assert!(manifest.recipe_pages.contains(&(recipes.path().join("soups/tomato-soup.cook"), soup_page.clone())));
+
+    let pancakes = fs::read_to_string(recipes_dir.join("pancakes.html")).unwrap();
+    assert!(pancakes.contains("<h1>Fluffy Pancakes</h1>"));
+    // Each ingredient is listed once
+    assert_eq!(pancakes.matches("flour.html").count(), 1);
+
+    // Every recipe page links to ingredient pages listing it back, and to its source
+    for (source, page) in &manifest.recipe_pages {
+        let html = fs::read_to_string(page).unwrap();
+        let dir = page.parent().unwrap();
+        let links = hrefs(&html);
+        assert_eq!(fs::canonicalize(dir.join(&links[0])).unwrap(), fs::canonicalize(&manifest.index).unwrap());
+        let source_link = links.last().unwrap();
+        assert_eq!(fs::canonicalize(dir.join(source_link)).unwrap(), fs::canonicalize(source).unwrap());
+        for href in &links[1..links.len() - 1] {
+            let ingredient_page = dir.join(href);
+            let back_links = hrefs(&fs::read_to_string(&ingredient_page).unwrap());
+            let back_dir = ingredient_page.parent().unwrap();
+            assert!(
+                back_links.iter().any(|back| fs::canonicalize(back_dir.join(back)).unwrap() == fs::canonicalize(page).unwrap()),
+                "{} does not link back to {}",
+                ingredient_page.display(),
+                page.display()
+            );
+        }
+    }
+
+    let soup = fs::read_to_string(&soup_page).unwrap();
+    assert!(soup.contains("<a href=\"../../index.html\">"));
+    assert!(soup.contains("<li><a href=\"../../ingredients/milk.html\">milk</a></li>"));
+}
+
+#[test]
+fn test_generate_site_removes_stale_pages() {
+    let recipes = common::recipe_dir(&[("pancakes.cook", "Mix @flour{200%g} and @milk.")]);
+    let out = tempfile::tempdir().unwrap();
+    let pages_dir = out.path().join("ingredients");
+    fs::create_dir_all(&pages_dir).unwrap();
+    fs::write(pages_dir.join("sugar.html"), "old").unwrap();
+    fs::write(pages_dir.join("notes.txt"), "keep").unwrap();
+
+    let index = IngredientIndex::new(recipes.path()).unwrap();
+    let manifest = index.generate_site(out.path(), &HtmlOptions::default()).unwrap();
+    assert_eq!(manifest.removed, [pages_dir.join("sugar.html")]);
+    assert!(!pages_dir.join("sugar.html").exists());
+    assert!(pages_dir.join("notes.txt").exists());
+    assert_eq!(manifest.files().count(), 6);
+    assert!(manifest.files().all(Path::exists));
+}
+
+#[test]
+fn test_generate_site_paginated_index() {
+    let recipes = common::recipe_dir(&[
+        ("salad.cook", "Toss @apple{}, @avocado{}, @carrot{} and @dill{}."),
+        ("toast.cook", "Top @bread{} with @cheese{}."),
+    ]);
+    let out = tempfile::tempdir().unwrap();
+    let index = IngredientIndex::new(recipes.path()).unwrap();
+
+    let options = HtmlOptions { ingredients_per_page: 2, ..HtmlOptions::default() };
+    let manifest = index.generate_site(out.path(), &options).unwrap();
+    let names = ["index.html", "index-2.html", "index-3.html"];
+    assert_eq!(manifest.index_pages, names.map(|name| out.path().join(name)));
+    assert_eq!(manifest.index, manifest.index_pages[0]);
+    assert_eq!(manifest.files().count(), 3 + 2 + 6 + 2);
+
+    let pages: Vec<String> = manifest.index_pages.iter().map(|page| fs::read_to_string(page).unwrap()).collect();
+    assert!(pages[0].contains("ingredients/apple.html") && pages[0].contains("ingredients/avocado.html"));
+    assert!(!pages[0].contains("ingredients/bread.html"));
+    // Letters link to the page they start on
+    assert!(pages[0].contains("<a href=\"#letter_a\">A</a>"));
+    assert!(pages[0].contains("<a href=\"index-2.html#letter_c\">C</a>"));
+    assert!(pages[0].contains("<a href=\"index-3.html#letter_d\">D</a>"));
+    assert!(pages[1].contains("<a href=\"#letter_c\">C</a>"));
+    assert!(pages[2].contains("<a href=\"index-2.html#letter_c\">C</a>"));
+    // Previous and next links
+    assert!(!pages[0].contains("rel=\"prev\""));
+    assert!(pages[0].contains("<a href=\"index-2.html\" rel=\"next\">Next</a>"));
+    assert!(pages[1].contains("<a href=\"index.html\" rel=\"prev\">Previous</a>"));
+    assert!(pages[1].contains("<nav class=\"pagination\" aria-label=\"Pages\">"));
+    assert!(pages[1].contains("<span>Page 2 of 3</span>"));
+    assert!(!pages[2].contains("rel=\"next\""));
+
+    // Going back to a single page removes the others
+    let manifest = index.generate_site(out.path(), &HtmlOptions::default()).unwrap();
+    assert_eq!(manifest.index_pages, [out.path().join("index.html")]);
+    assert_eq!(manifest.removed, [out.path().join(names[1]), out.path().join(names[2])]);
+    let html = fs::read_to_string(&manifest.index).unwrap();
+    assert!(html.contains("<a href=\"#letter_c\">C</a>"));
+    assert!(!html.contains("class=\"pagination\""));
+}
+
+#[test]
+fn test_generate_site_search_index_and_sitemap() {
+    let recipes = common::recipe_dir(&[
+        ("pancakes.cook", "Mix @flour{200%g} with @milk{}."),
+        ("soups/tomato-soup.cook", "Simmer @tomatoes{4} in @milk{}."),
+    ]);
+    let out = tempfile::tempdir().unwrap();
+    let index = IngredientIndex::new(recipes.path()).unwrap();
+    let options = HtmlOptions { base_url: Some("https://example.com".to_string()), ..HtmlOptions::default() };
+    let manifest = index.generate_site(out.path(), &options).unwrap();
+
+    assert_eq!(manifest.search_index, out.path().join("search-index.json"));
+    assert_eq!(fs::read_to_string(&manifest.search_index).unwrap(), index.generate_json().unwrap());
+
+    let sitemap_path = out.path().join("sitemap.xml");
+    assert_eq!(manifest.sitemap.as_ref(), Some(&sitemap_path));
+    let sitemap = fs::read_to_string(&sitemap_path).unwrap();
+    assert!(sitemap.starts_with("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n"));
+    let loc_regex = Regex::new(r"<loc>([^<]+)</loc>").unwrap();
+    let locations: Vec<&str> = loc_regex.captures_iter(&sitemap).map(|cap| cap.get(1).unwrap().as_str()).collect();
+    assert_eq!(
+        locations,
+        [
+            "https://example.com/index.html",
+            "https://example.com/recipes.html",
+            "https://example.com/ingredients/flour.html",
+            "https://example.com/ingredients/milk.html",
+            "https://example.com/ingredients/tomatoes.html",
+            "https://example.com/recipes/pancakes.html",
+            "https://example.com/recipes/soups/tomato-soup.html",
+        ]
+    );
+    assert!(manifest.files().any(|file| file == manifest.search_index));
+    assert!(manifest.files().any(|file| file == sitemap_path));
+
+    // Sitemaps need absolute URLs, so without a base URL there is none
+    let manifest = index.generate_site(out.path(), &HtmlOptions::default()).unwrap();
+    assert!(manifest.sitemap.is_none());
+    assert!(!sitemap_path.exists());
+    assert!(manifest.files().all(|file| file != sitemap_path));
+    assert_eq!(manifest.removed, [sitemap_path]);
+}
@@ -0,0 +1,46 @@
+// tests/slug_test.rs
+use cooklang_indexer::{slugify, IngredientIndex, SlugSet};
+use proptest::prelude::*;
+
+#[test]
+fn test_slugify_examples() {
+    assert_eq!(slugify("Chicken Thighs"), "chicken-thighs");
+    assert_eq!(slugify("  crème   fraîche "), "creme-fraiche");
+    assert_eq!(slugify("half_and--half"), "half-and-half");
+    assert_eq!(slugify("Weißwurst"), "weisswurst");
+    assert_eq!(slugify("salt & pepper!"), "salt-pepper");
+    assert_eq!(slugify("-- 2% milk --"), "2-milk");
+    assert_eq!(slugify("&&&"), "");
+}
+
+#[test]
+fn test_slug_set_collisions() {
+    let mut slugs = SlugSet::new();
+    assert_eq!(slugs.insert("Basil"), "basil");
+    assert_eq!(slugs.insert("basil"), "basil-2");
+    assert_eq!(slugs.insert("BASIL?"), "basil-3");
+    assert_eq!(slugs.insert("basil 2"), "basil-2-2");
+    assert!(slugs.contains("basil-3"));
+}
+
+#[test]
+fn test_html_uses_slug_anchors() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let html = index.generate_html("http://example.com/r").unwrap();
+    assert!(html.contains("<section class=\"ingredient\" id=\"coconut-milk\">"));
+}
+
+proptest! {
+    #[test]
+    fn slugify_is_idempotent(name in "\\PC*") {
+        let slug = slugify(&name);
+        prop_assert_eq!(slugify(&slug), slug);
+    }
+
+    #[test]
+    fn slugs_have_no_stray_hyphens(name in "\\PC*") {
+        let slug = slugify(&name);
+        prop_assert!(!slug.starts_with('-') && !slug.ends_with('-'));
+        prop_assert!(!slug.contains("--"));
+    }
+}
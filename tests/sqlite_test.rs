@@ -0,0 +1,44 @@
+// tests/sqlite_test.rs
+#![cfg(feature = "sqlite")]
+
+use cooklang_indexer::IngredientIndex;
+use rusqlite::Connection;
+
+#[test]
+fn test_export_sqlite_in_memory() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let mut conn = Connection::open_in_memory().unwrap();
+    index.write_sqlite(&mut conn).unwrap();
+
+    let count: i64 = conn
+        .query_row(
+            "SELECT COUNT(*) FROM recipe_ingredients ri
+             JOIN ingredients i ON i.id = ri.ingredient_id
+             WHERE i.name = ?1",
+            ["onion"],
+            |row| row.get(0),
+        )
+        .unwrap();
+    assert_eq!(count, 2);
+
+    let ingredients: i64 = conn
+        .query_row("SELECT COUNT(*) FROM ingredients", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(ingredients as usize, index.ingredients().len());
+}
+
+#[test]
+fn test_export_sqlite_file() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let dir = tempfile::tempdir().unwrap();
+    let db = dir.path().join("index.db");
+    index.export_sqlite(&db).unwrap();
+    // Exporting again replaces the previous contents
+    index.export_sqlite(&db).unwrap();
+
+    let conn = Connection::open(&db).unwrap();
+    let recipes: i64 = conn
+        .query_row("SELECT COUNT(*) FROM recipes", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(recipes, 3);
+}
@@ -0,0 +1,94 @@
+// tests/template_test.rs
+#![cfg(feature = "templates")]
+
+use cooklang_indexer::{Footer, HtmlLabels, HtmlOptions, IngredientIndex};
+use std::time::UNIX_EPOCH;
+
+#[test]
+fn test_default_template_matches_builtin_html() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    for options in [
+        HtmlOptions::default(),
+        HtmlOptions {
+            base_url: Some("https://example.com/r".to_string()),
+            show_counts: true,
+            include_default_css: false,
+            stylesheet_href: Some("/site.css".to_string()),
+            extra_css: Some("h1 { color: red; }".to_string()),
+            heading: Some("Pots & Pans".to_string()),
+            intro_html: Some("<p>Hello</p>".to_string()),
+            container_class: Some("pantry".to_string()),
+            alphabet_nav: false,
+            group_by_letter: true,
+            filter_box: true,
+            show_summary: true,
+            tag_cloud: true,
+            ..HtmlOptions::default()
+        },
+        HtmlOptions { print: true, filter_box: true, collapsible: true, ..HtmlOptions::default() },
+        HtmlOptions {
+            collapsible: true,
+            open_by_default: true,
+            show_quantities: true,
+            show_context: true,
+            footer: Footer::Auto,
+            generated_at: Some(UNIX_EPOCH),
+            lang: "de".to_string(),
+            labels: HtmlLabels {
+                recipe: "{} Rezept".to_string(),
+                recipes: "{} Rezepte".to_string(),
+                generated: "Erstellt {} mit {}".to_string(),
+                ..HtmlLabels::default()
+            },
+            ..HtmlOptions::default()
+        },
+    ] {
+        let expected = index.generate_html_with(&options).unwrap();
+        assert_eq!(index.generate_html_from_template(None, &options).unwrap(), expected);
+    }
+}
+
+#[test]
+fn test_write_html_from_template_streams() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions { filter_box: true, footer: Footer::Auto, generated_at: Some(UNIX_EPOCH), ..HtmlOptions::default() };
+    let mut out = Vec::new();
+    index.write_html_from_template(&mut out, None, &options).unwrap();
+    assert_eq!(String::from_utf8(out).unwrap(), index.generate_html_from_template(None, &options).unwrap());
+
+    // Errors in the template are reported as when rendering to a string
+    let err = index.write_html_from_template(Vec::new(), Some("{{ oops"), &options).unwrap_err();
+    assert!(err.to_string().contains("line 1"), "{}", err);
+    // Failed writes keep their cause
+    let mut full = [0u8; 100];
+    let err = index.write_html_from_template(&mut full[..], None, &options).unwrap_err();
+    assert!(format!("{:#}", err).starts_with("failed to write HTML"), "{:#}", err);
+}
+
+#[test]
+fn test_custom_template_context() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let template = "{{ total_ingredients }}/{{ total_recipes }}\
+        {% for i in ingredients if i.name == 'coconut milk' %}\
+        |{{ i.anchor }}:{% for r in i.recipes %}{{ r.name }}={{ r.url }}{% endfor %}{% endfor %}";
+    let html = index.generate_html_from_template(Some(template), &HtmlOptions::default()).unwrap();
+    let total = index.ingredients().len();
+    assert_eq!(html, format!("{}/3|coconut-milk:Chicken Curry=mains/chicken_curry.cook", total));
+}
+
+#[test]
+fn test_template_escapes_values() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions { title: "<b>Pantry</b>".to_string(), ..HtmlOptions::default() };
+    let html = index.generate_html_from_template(Some("{{ title }}|{{ title|safe }}"), &options).unwrap();
+    assert_eq!(html, "&lt;b&gt;Pantry&lt;/b&gt;|<b>Pantry</b>");
+}
+
+#[test]
+fn test_template_syntax_error_reports_line() {
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let err = index
+        .generate_html_from_template(Some("<ul>\n{% for i in ingredients %}\n<li>{{ i.name }</li>\n"), &HtmlOptions::default())
+        .unwrap_err();
+    assert!(err.to_string().contains("line 3"), "{}", err);
+}
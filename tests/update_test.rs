@@ -0,0 +1,120 @@
+// tests/update_test.rs
+mod common;
+
+use cooklang_indexer::IngredientIndex;
+
+#[test]
+fn test_upsert_replaces_changed_file() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Blend @basil{} with @garlic{1%clove}."),
+        ("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}."),
+    ]);
+    let mut index = IngredientIndex::new(dir.path()).unwrap();
+    let pesto = dir.path().join("pesto.cook");
+
+    std::fs::write(&pesto, "Blend @parsley{} with @walnuts{}.").unwrap();
+    index.upsert_file(&pesto).unwrap();
+
+    assert!(index.get_recipes_for_ingredient("basil").is_none());
+    assert_eq!(index.get_recipes_for_ingredient("parsley").unwrap(), &vec![pesto.clone()]);
+    // The other recipe's contributions are untouched
+    assert_eq!(
+        index.get_recipes_for_ingredient("garlic").unwrap(),
+        &vec![dir.path().join("toast.cook")]
+    );
+    assert!(index.get_recipes_for_ingredient("bread").is_some());
+}
+
+#[test]
+fn test_upsert_new_file_and_remove() {
+    let dir = common::recipe_dir(&[("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}.")]);
+    let mut index = IngredientIndex::new(dir.path()).unwrap();
+
+    let aioli = dir.path().join("aioli.cook");
+    std::fs::write(&aioli, "Pound @garlic{2%cloves} with @oil{}.").unwrap();
+    index.upsert_file(&aioli).unwrap();
+    assert_eq!(index.get_recipes_for_ingredient("garlic").unwrap().len(), 2);
+
+    assert!(index.remove_file(&aioli));
+    assert!(!index.remove_file(&aioli));
+    assert_eq!(index.get_recipes_for_ingredient("garlic").unwrap().len(), 1);
+    assert!(index.get_recipes_for_ingredient("oil").is_none());
+}
+
+#[test]
+fn test_refresh_files_applies_changes() {
+    let dir = common::recipe_dir(&[
+        ("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}."),
+        ("soups/leek.cook", "Sweat @leeks{2} in @butter{}."),
+        ("soups/miso.cook", "Whisk @miso{2%tbsp} into @dashi{1%l}."),
+    ]);
+    let mut index = IngredientIndex::new(dir.path()).unwrap();
+    let toast = dir.path().join("toast.cook");
+    let aioli = dir.path().join("aioli.cook");
+    let notes = dir.path().join("notes.txt");
+
+    std::fs::write(&toast, "Rub @garlic{1%clove} on @sourdough{2%slices}.").unwrap();
+    std::fs::write(&aioli, "Pound @garlic{2%cloves} with @oil{}.").unwrap();
+    std::fs::write(&notes, "@not a recipe").unwrap();
+    std::fs::remove_dir_all(dir.path().join("soups")).unwrap();
+    let report = index.refresh_files(&[toast.clone(), aioli.clone(), notes, dir.path().join("soups")]);
+
+    assert_eq!(report.updated, [aioli.clone(), toast.clone()]);
+    assert_eq!(report.removed, [dir.path().join("soups/leek.cook"), dir.path().join("soups/miso.cook")]);
+    assert!(report.failed.is_empty());
+    assert_eq!(index.ingredients(), ["garlic", "oil", "sourdough"]);
+    assert_eq!(index.get_recipes_for_ingredient("garlic").unwrap(), &vec![aioli, toast]);
+
+    // A directory moved in has all its recipes read
+    let moved = common::recipe_dir(&[("salads/slaw.cook", "Shred @cabbage{} and @carrots{}.")]);
+    std::fs::rename(moved.path().join("salads"), dir.path().join("salads")).unwrap();
+    let report = index.refresh_files(&[dir.path().join("salads")]);
+    assert_eq!(report.updated, [dir.path().join("salads/slaw.cook")]);
+    assert!(index.get_recipes_for_ingredient("cabbage").is_some());
+    assert!(index.refresh_files(&[dir.path().join("missing.cook")]).is_empty());
+}
+
+#[test]
+fn test_refresh_files_reports_failures() {
+    let dir = common::recipe_dir(&[("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}.")]);
+    let mut index = IngredientIndex::builder(dir.path()).strict(true).build().unwrap();
+    let toast = dir.path().join("toast.cook");
+
+    std::fs::write(&toast, "Rub @garlic{1%clove on @bread{2%slices}.").unwrap();
+    let report = index.refresh_files(std::slice::from_ref(&toast));
+    assert!(report.updated.is_empty());
+    assert_eq!(report.failed.len(), 1);
+    assert_eq!(report.failed[0].0, toast);
+    assert!(report.failed[0].1.contains("unclosed"), "{}", report.failed[0].1);
+    assert!(index.get_recipes_for_ingredient("bread").is_none());
+}
+
+#[test]
+fn test_low_memory_updates_match_full_index() {
+    let dir = common::recipe_dir(&[
+        ("pesto.cook", "Blend @basil{} with @garlic{1%clove}."),
+        ("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}."),
+    ]);
+    let other = common::recipe_dir(&[("aioli.cook", "Pound @garlic{3%cloves} into @oil{}.")]);
+    let mut full = IngredientIndex::new(dir.path()).unwrap();
+    let mut lean = IngredientIndex::builder(dir.path()).low_memory(true).build().unwrap();
+    // Asked for once before the changes, so they must not be answered from a stale copy
+    assert_eq!(lean.get_recipes_for_ingredient("garlic"), full.get_recipes_for_ingredient("garlic"));
+
+    std::fs::write(dir.path().join("pesto.cook"), "Blend @basil{} with @pine nuts{}.").unwrap();
+    std::fs::write(dir.path().join("salad.cook"), "Toss @garlic{} and @basil{}.").unwrap();
+    for index in [&mut full, &mut lean] {
+        index.upsert_file(dir.path().join("pesto.cook")).unwrap();
+        index.upsert_file(dir.path().join("salad.cook")).unwrap();
+        index.remove_file(dir.path().join("toast.cook"));
+        index.merge(IngredientIndex::new(other.path()).unwrap());
+    }
+
+    assert_eq!(lean.ingredients(), full.ingredients());
+    for ingredient in full.ingredients() {
+        assert_eq!(lean.get_recipes_for_ingredient(ingredient), full.get_recipes_for_ingredient(ingredient));
+    }
+    let garlic = lean.get_recipes_for_ingredient("garlic").unwrap();
+    assert_eq!(garlic.len(), 2);
+    assert!(garlic.contains(&other.path().join("aioli.cook")) && garlic.contains(&dir.path().join("salad.cook")));
+}
@@ -0,0 +1,330 @@
+// tests/url_test.rs
+use cooklang_indexer::{path_to_url, try_path_to_url, UrlError};
+use std::path::Path;
+
+#[test]
+fn test_try_path_to_url_nested() {
+    let url = try_path_to_url(
+        Path::new("recipes/soups/tomato-soup.cook"),
+        "http://example.com/r/",
+        Path::new("recipes"),
+    )
+    .unwrap();
+    assert_eq!(url, "http://example.com/r/soups/tomato-soup");
+}
+
+#[test]
+fn test_try_path_to_url_rejects_parent_dir() {
+    let err = try_path_to_url(
+        Path::new("recipes/../pasta.cook"),
+        "http://example.com/r",
+        Path::new("recipes"),
+    )
+    .unwrap_err();
+    assert_eq!(err, UrlError::UnsupportedComponent { component: "..".into() });
+}
+
+#[test]
+fn test_base_dir_spelled_differently() {
+    let absolute = std::fs::canonicalize("test_recipes").unwrap();
+    let recipe = Path::new("test_recipes/soups/tomato-soup.cook");
+    for base_dir in [absolute.clone(), Path::new("test_recipes/../test_recipes").to_path_buf()] {
+        assert_eq!(path_to_url(recipe, "https://example.com/r", &base_dir), "https://example.com/r/soups/tomato-soup");
+    }
+    assert_eq!(
+        path_to_url(&absolute.join("pancakes.cook"), "https://example.com/r", Path::new("./test_recipes")),
+        "https://example.com/r/pancakes"
+    );
+
+    // A recipe re-read through a relative path is linked like the others
+    use cooklang_indexer::{HtmlOptions, IngredientIndex};
+    let mut index = IngredientIndex::new(&absolute).unwrap();
+    assert!(index.remove_file(absolute.join("soups/tomato-soup.cook")));
+    index.upsert_file(recipe).unwrap();
+    let options = HtmlOptions { base_url: Some("https://example.com/r".to_string()), ..HtmlOptions::default() };
+    let report = index.generate_html_report(&options).unwrap();
+    assert!(report.html.contains("href=\"https://example.com/r/soups/tomato-soup\""));
+    assert!(!report.html.contains("test_recipes"));
+    assert!(report.warnings.is_empty());
+}
+
+#[cfg(unix)]
+#[test]
+fn test_try_path_to_url_non_utf8() {
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let base = Path::new("recipes");
+    let stem = OsStr::from_bytes(b"cr\xeape");
+    let path = base.join("desserts").join(stem).with_extension("cook");
+
+    let err = try_path_to_url(&path, "http://example.com/r", base).unwrap_err();
+    assert_eq!(err, UrlError::NonUtf8 { component: stem.to_os_string() });
+
+    // The infallible wrapper still produces a (lossy) link
+    let url = path_to_url(&path, "http://example.com/r", base);
+    assert_eq!(url, "http://example.com/r/desserts/cr%EF%BF%BDpe");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_html_report_warns_on_non_utf8_path() {
+    use cooklang_indexer::{BrokenLinks, HtmlOptions, IngredientIndex};
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("pesto.cook"), "Blend @basil{}.").unwrap();
+    let bad = dir.path().join(OsStr::from_bytes(b"cr\xeape.cook"));
+    std::fs::write(&bad, "Toss with @basil{}.").unwrap();
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let mut options = HtmlOptions {
+        base_url: Some("http://example.com/r".to_string()),
+        ..HtmlOptions::default()
+    };
+
+    let report = index.generate_html_report(&options).unwrap();
+    assert_eq!(report.warnings.len(), 1);
+    assert_eq!(report.warnings[0].path, bad);
+    assert!(report.html.contains("http://example.com/r/pesto"));
+    assert!(!report.html.contains("cr%EF%BF%BDpe"));
+    // The count matches the recipes actually listed
+    assert!(report.html.contains("basil</a> <span class=\"ingredient-count\">(1 recipe)</span>"));
+
+    options.show_summary = true;
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("across 1 recipe, generated from "));
+    assert!(html.contains("<span class=\"skipped-count\">(skipped 1 recipe that could not be linked)</span>"));
+    options.show_summary = false;
+
+    options.broken_links = BrokenLinks::Lossy;
+    let report = index.generate_html_report(&options).unwrap();
+    assert_eq!(report.warnings.len(), 1);
+    assert!(report.html.contains("http://example.com/r/cr%EF%BF%BDpe"));
+    assert!(report.html.contains("basil</a> <span class=\"ingredient-count\">(2 recipes)</span>"));
+}
+
+#[test]
+fn test_html_keeps_extension() {
+    use cooklang_indexer::{HtmlOptions, IngredientIndex, UrlExtension, UrlOptions};
+
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions {
+        base_url: Some("http://example.com/r".to_string()),
+        url: UrlOptions { extension: UrlExtension::Keep, ..UrlOptions::default() },
+        ..HtmlOptions::default()
+    };
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("href=\"http://example.com/r/soups/tomato-soup.cook\""));
+
+    // The default stays extensionless
+    let html = index.generate_html("http://example.com/r").unwrap();
+    assert!(html.contains("href=\"http://example.com/r/soups/tomato-soup\""));
+}
+
+#[test]
+fn test_url_mapper_query_string() {
+    use cooklang_indexer::{HtmlOptions, IngredientIndex};
+
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions::default().url_mapper(|path, base_dir| {
+        let relative = path.strip_prefix(base_dir).unwrap().with_extension("");
+        format!("/recipe.php?name={}&lang=en", relative.display())
+    });
+
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("href=\"/recipe.php?name=soups/tomato-soup&amp;lang=en\""));
+    assert!(!html.contains("&lang=en"));
+}
+
+#[test]
+fn test_builder_url_builder() {
+    use cooklang_indexer::{HtmlOptions, IngredientIndex};
+
+    let index = IngredientIndex::builder("./test_recipes")
+        .url_builder(|path, _base_dir| format!("?recipe={}", path.file_stem().unwrap().to_string_lossy()))
+        .build()
+        .unwrap();
+
+    let html = index.generate_html("http://example.com/r").unwrap();
+    assert!(html.contains("href=\"?recipe=tomato-soup\""));
+    assert!(!html.contains("http://example.com/r"));
+
+    // A mapper on the HTML options still wins
+    let options = HtmlOptions::default().url_mapper(|_, _| "/elsewhere".to_string());
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("href=\"/elsewhere\""));
+    assert!(!html.contains("?recipe="));
+}
+
+#[test]
+fn test_relative_links() {
+    use cooklang_indexer::{relative_url, IngredientIndex, UrlOptions};
+
+    let base = Path::new("test_recipes");
+    let recipe = base.join("soups/tomato-soup.cook");
+    let options = UrlOptions::default();
+
+    assert_eq!(relative_url(&recipe, base, base, &options).unwrap(), "soups/tomato-soup.cook");
+    assert_eq!(
+        relative_url(&recipe, base, Path::new("."), &options).unwrap(),
+        "test_recipes/soups/tomato-soup.cook"
+    );
+    assert_eq!(
+        relative_url(&recipe, base, Path::new("test_recipes/mains"), &options).unwrap(),
+        "../soups/tomato-soup.cook"
+    );
+
+    // Written into the recipes directory itself (the default) and one level above it
+    let index = IngredientIndex::new(base).unwrap();
+    let html = index.generate_html_report(&Default::default()).unwrap().html;
+    assert!(html.contains("href=\"soups/tomato-soup.cook\""));
+    let html = index.generate_html_relative(".").unwrap();
+    assert!(html.contains("href=\"test_recipes/soups/tomato-soup.cook\""));
+}
+
+#[test]
+fn test_relative_links_from_absolute_output_dir() {
+    use cooklang_indexer::{relative_url, UrlOptions};
+
+    let cwd = std::env::current_dir().unwrap();
+    let url = relative_url(
+        Path::new("test_recipes/mains/chicken_curry.cook"),
+        Path::new("test_recipes"),
+        &cwd.join("test_recipes"),
+        &UrlOptions::default(),
+    )
+    .unwrap();
+    assert_eq!(url, "mains/chicken_curry.cook");
+}
+
+#[cfg(unix)]
+#[test]
+fn test_file_scheme_urls() {
+    use cooklang_indexer::{file_url, HtmlOptions, IngredientIndex, UrlMode, UrlOptions};
+
+    let dir = tempfile::tempdir().unwrap();
+    let recipe = dir.path().join("chicken pasta.cook");
+    std::fs::write(&recipe, "Boil @pasta{200%g}.").unwrap();
+    let root = std::fs::canonicalize(dir.path()).unwrap();
+    let expected = format!(
+        "file://{}/chicken%20pasta.cook",
+        root.display().to_string().replace(' ', "%20")
+    );
+
+    assert_eq!(file_url(&recipe, &UrlOptions::default()).unwrap(), expected);
+
+    // The mode applies to HTML generation whatever the base URL
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    let options = HtmlOptions {
+        base_url: Some("http://example.com/r".to_string()),
+        url: UrlOptions { mode: UrlMode::FileScheme, ..UrlOptions::default() },
+        ..HtmlOptions::default()
+    };
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains(&format!("href=\"{}\"", expected)));
+}
+
+#[test]
+fn test_validate_base_url() {
+    use cooklang_indexer::validate_base_url;
+
+    assert_eq!(validate_base_url("http://localhost:8080/r", false).unwrap(), "http://localhost:8080/r");
+    assert!(validate_base_url("file:///srv/recipes", false).is_ok());
+
+    let err = validate_base_url("example.com/recipes", false).unwrap_err();
+    assert_eq!(err.problems.len(), 1);
+    assert!(err.to_string().contains("no scheme"));
+    assert!(err.to_string().contains("https://example.com/recipes"));
+
+    assert!(validate_base_url("", false).unwrap_err().to_string().contains("empty"));
+    assert!(validate_base_url("https://example.com/my recipes", false).is_err());
+    assert!(validate_base_url("ftp://example.com", false).is_err());
+    assert!(validate_base_url("https:///recipes", false).is_err());
+
+    // Lenient mode only fixes up things that look like a host
+    assert_eq!(
+        validate_base_url("example.com/recipes", true).unwrap(),
+        "https://example.com/recipes"
+    );
+    assert!(validate_base_url("recipes", true).is_err());
+}
+
+#[test]
+fn test_generate_html_rejects_invalid_base_url() {
+    use cooklang_indexer::{IngredientIndex, InvalidBaseUrl};
+
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let err = index.generate_html("example.com/recipes").unwrap_err();
+    assert!(err.downcast_ref::<InvalidBaseUrl>().is_some());
+}
+
+#[test]
+fn test_dir_base_urls_longest_prefix() {
+    use cooklang_indexer::{path_to_url_with, DirBaseUrl, UrlOptions};
+
+    let options = UrlOptions {
+        dir_base_urls: vec![
+            DirBaseUrl::new("", "https://example.com/recipes"),
+            DirBaseUrl::new("desserts", "https://sweets.example.com/r"),
+            DirBaseUrl {
+                strip_dir: false,
+                ..DirBaseUrl::new("desserts/frozen", "https://ice.example.com")
+            },
+        ],
+        ..UrlOptions::default()
+    };
+    let base = Path::new("recipes");
+    let url = |p: &str| path_to_url_with(&base.join(p), "https://fallback.example.com", base, &options);
+
+    assert_eq!(url("desserts/flan.cook"), "https://sweets.example.com/r/flan");
+    assert_eq!(url("desserts/frozen/sorbet.cook"), "https://ice.example.com/desserts/frozen/sorbet");
+    assert_eq!(url("mains/curry.cook"), "https://example.com/recipes/mains/curry");
+    // Directory prefixes match whole components only
+    assert_eq!(url("desserts-old/pie.cook"), "https://example.com/recipes/desserts-old/pie");
+}
+
+#[test]
+fn test_dir_base_urls_in_html() {
+    use cooklang_indexer::{DirBaseUrl, HtmlOptions, IngredientIndex, UrlOptions};
+
+    let index = IngredientIndex::new("./test_recipes").unwrap();
+    let options = HtmlOptions {
+        base_url: Some("https://example.com/recipes".to_string()),
+        url: UrlOptions {
+            dir_base_urls: vec![DirBaseUrl::new("soups", "https://soups.example.com")],
+            ..UrlOptions::default()
+        },
+        ..HtmlOptions::default()
+    };
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("href=\"https://soups.example.com/tomato-soup\""));
+    assert!(html.contains("href=\"https://example.com/recipes/mains/chicken_curry\""));
+
+    // Without a default base URL, unmatched recipes are linked relatively
+    let options = HtmlOptions { base_url: None, ..options };
+    let html = index.generate_html_report(&options).unwrap().html;
+    assert!(html.contains("href=\"https://soups.example.com/tomato-soup\""));
+    assert!(html.contains("href=\"mains/chicken_curry.cook\""));
+}
+
+#[cfg(unix)]
+#[test]
+fn test_unlinkable_recipes() {
+    use cooklang_indexer::IngredientIndex;
+    use std::ffi::OsStr;
+    use std::os::unix::ffi::OsStrExt;
+
+    let dir = tempfile::tempdir().unwrap();
+    std::fs::write(dir.path().join("pesto.cook"), "Blend @basil{}.").unwrap();
+    let bad = dir.path().join(OsStr::from_bytes(b"cr\xeape.cook"));
+    std::fs::write(&bad, "Toss with @basil{}.").unwrap();
+
+    let index = IngredientIndex::new(dir.path()).unwrap();
+    assert_eq!(index.unlinkable_recipes(), [&bad]);
+    assert!(matches!(try_path_to_url(&bad, "", dir.path()), Err(UrlError::NonUtf8 { .. })));
+
+    let mapped = IngredientIndex::builder(dir.path()).url_builder(|_, _| "/recipe".to_string()).build().unwrap();
+    assert!(mapped.unlinkable_recipes().is_empty());
+}
@@ -0,0 +1,36 @@
+// tests/watch_test.rs
+#![cfg(feature = "watch")]
+
+mod common;
+
+use cooklang_indexer::{IngredientIndex, RecipeWatcher};
+use std::thread;
+use std::time::Duration;
+
+#[test]
+fn test_reports_changed_recipes() {
+    let dir = common::recipe_dir(&[("toast.cook", "Rub @garlic{1%clove} on @bread{2%slices}.")]);
+    let mut index = IngredientIndex::new(dir.path()).unwrap();
+    let watcher = RecipeWatcher::new(dir.path()).unwrap().debounce(Duration::from_millis(50));
+
+    let aioli = dir.path().join("aioli.cook");
+    std::fs::write(&aioli, "Pound @garlic{2%cloves} with @oil{}.").unwrap();
+    let paths = watcher.wait().unwrap();
+    assert!(paths.contains(&aioli), "{:?}", paths);
+
+    let report = index.refresh_files(&paths);
+    assert_eq!(report.updated, [aioli]);
+    assert!(index.get_recipes_for_ingredient("oil").is_some());
+}
+
+#[test]
+fn test_stopper_ends_the_wait() {
+    let dir = common::recipe_dir(&[]);
+    let watcher = RecipeWatcher::new(dir.path()).unwrap();
+    let stopper = watcher.stopper();
+    thread::spawn(move || {
+        thread::sleep(Duration::from_millis(50));
+        stopper.stop();
+    });
+    assert_eq!(watcher.wait(), None);
+}